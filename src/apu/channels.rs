@@ -0,0 +1,239 @@
+use crate::apu::ChannelState;
+use crate::apu::envelope::Envelope;
+use crate::apu::length::LengthCounter;
+
+// Output of each of the 8 steps of the four duty cycles: 12.5%, 25%, 50% and 75%
+const DUTY_PATTERNS: [u8; 4] = [0b0000_0001, 0b1000_0001, 0b1000_0111, 0b0111_1110];
+
+// Right shift applied to the wave samples by NR32 bits 5-6: mute, 100%, 50% and 25%
+const WAVE_VOLUME_SHIFTS: [u8; 4] = [4, 0, 1, 2];
+
+// Count a timer down by the given T-cycles, calling on_expire each time it reaches zero and reloading it with period
+fn step_timer(timer: &mut u32, period: u32, mut t_cycles: u32, mut on_expire: impl FnMut()) {
+    while t_cycles >= *timer {
+        t_cycles -= *timer;
+        *timer = period;
+        on_expire();
+    }
+
+    *timer -= t_cycles;
+}
+
+// Channels 1 and 2
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Pulse {
+    pub enabled: bool,
+    // NRx2 upper 5 bits, the channel is silent when they are all cleared
+    pub dac_enabled: bool,
+    // 11 bit period value from NRx3 and NRx4, the tone is 131072 / (2048 - frequency) Hz
+    pub frequency: u16,
+    pub duty: u8,
+    duty_step: u8,
+    pub envelope: Envelope,
+    pub length: LengthCounter<64>,
+    timer: u32,
+}
+
+impl Pulse {
+    // T-cycles between two duty steps
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 4
+    }
+
+    pub fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.timer = self.period();
+        self.envelope.trigger();
+    }
+
+    // Turning the DAC off stops the channel straight away, turning it back on leaves it stopped until the next trigger
+    pub fn set_dac_enabled(&mut self, dac_enabled: bool) {
+        self.dac_enabled = dac_enabled;
+        self.enabled &= dac_enabled;
+    }
+
+    pub fn step(&mut self, t_cycles: u32) {
+        let period = self.period();
+        let duty_step = &mut self.duty_step;
+        step_timer(&mut self.timer, period, t_cycles, || *duty_step = (*duty_step + 1) % 8);
+    }
+
+    // Digital output, 0-15
+    pub fn output(&self) -> u8 {
+        let high = DUTY_PATTERNS[self.duty as usize & 0x3] & (0x80 >> self.duty_step) != 0;
+
+        if self.enabled && high { self.envelope.volume } else { 0 }
+    }
+
+    pub fn state(&self) -> ChannelState {
+        ChannelState {
+            enabled: self.enabled,
+            dac_enabled: self.dac_enabled,
+            frequency: self.frequency,
+            volume: self.envelope.volume,
+            duty: self.duty,
+            length: self.length.counter(),
+        }
+    }
+}
+
+// Channel 3, plays the 32 4-bit samples of wave RAM
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Wave {
+    pub enabled: bool,
+    // NR30 bit 7
+    pub dac_enabled: bool,
+    // The channel steps through the samples at 65536 / (2048 - frequency) Hz
+    pub frequency: u16,
+    // NR32 bits 5-6
+    pub volume_code: u8,
+    pub length: LengthCounter<256>,
+    position: u8,
+    // Last sample read from wave RAM, played until the next step
+    sample: u8,
+    timer: u32,
+}
+
+impl Wave {
+    // T-cycles between two samples
+    fn period(&self) -> u32 {
+        (2048 - self.frequency as u32) * 2
+    }
+
+    pub fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.timer = self.period();
+        self.position = 0;
+    }
+
+    // Turning the DAC off stops the channel straight away, turning it back on leaves it stopped until the next trigger
+    pub fn set_dac_enabled(&mut self, dac_enabled: bool) {
+        self.dac_enabled = dac_enabled;
+        self.enabled &= dac_enabled;
+    }
+
+    pub fn step(&mut self, t_cycles: u32, wave_ram: &[u8]) {
+        let period = self.period();
+        let (position, sample) = (&mut self.position, &mut self.sample);
+        step_timer(&mut self.timer, period, t_cycles, || {
+            *position = (*position + 1) % 32;
+            // High nibble first
+            let byte = wave_ram[*position as usize / 2];
+            *sample = if *position % 2 == 0 { byte >> 4 } else { byte & 0xF };
+        });
+    }
+
+    pub fn output(&self) -> u8 {
+        if self.enabled { self.sample >> WAVE_VOLUME_SHIFTS[self.volume_code as usize & 0x3] } else { 0 }
+    }
+
+    pub fn state(&self) -> ChannelState {
+        ChannelState {
+            enabled: self.enabled,
+            dac_enabled: self.dac_enabled,
+            frequency: self.frequency,
+            volume: 0xF >> WAVE_VOLUME_SHIFTS[self.volume_code as usize & 0x3],
+            duty: 0,
+            length: self.length.counter(),
+        }
+    }
+}
+
+// Channel 4, pseudo-random noise from a linear feedback shift register
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Noise {
+    pub enabled: bool,
+    pub dac_enabled: bool,
+    // NR43 as written: clock shift in bits 4-7, 7 bit mode in bit 3 and clock divider in bits 0-2
+    pub control: u8,
+    pub envelope: Envelope,
+    pub length: LengthCounter<64>,
+    lfsr: u16,
+    timer: u32,
+}
+
+impl Noise {
+    // T-cycles between two shifts of the LFSR
+    fn period(&self) -> u32 {
+        let divider = match self.control & 0x7 {
+            0 => 8,
+            divider => divider as u32 * 16,
+        };
+
+        divider << (self.control >> 4)
+    }
+
+    pub fn trigger(&mut self) {
+        self.enabled = self.dac_enabled;
+        self.timer = self.period();
+        self.envelope.trigger();
+        self.lfsr = 0x7FFF;
+    }
+
+    pub fn set_dac_enabled(&mut self, dac_enabled: bool) {
+        self.dac_enabled = dac_enabled;
+        self.enabled &= dac_enabled;
+    }
+
+    pub fn step(&mut self, t_cycles: u32) {
+        let period = self.period();
+        let short_mode = self.control & (1 << 3) != 0;
+        let lfsr = &mut self.lfsr;
+        step_timer(&mut self.timer, period, t_cycles, || {
+            let feedback = (*lfsr ^ (*lfsr >> 1)) & 1;
+            *lfsr = (*lfsr >> 1) | (feedback << 14);
+            // In 7 bit mode the feedback goes into bit 6 as well
+            if short_mode {
+                *lfsr = (*lfsr & !(1 << 6)) | (feedback << 6);
+            }
+        });
+    }
+
+    pub fn output(&self) -> u8 {
+        if self.enabled && self.lfsr & 1 == 0 { self.envelope.volume } else { 0 }
+    }
+
+    pub fn state(&self) -> ChannelState {
+        ChannelState {
+            enabled: self.enabled,
+            dac_enabled: self.dac_enabled,
+            frequency: self.control as u16,
+            volume: self.envelope.volume,
+            duty: 0,
+            length: self.length.counter(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pulse_follows_duty_pattern() {
+        let mut pulse = Pulse { dac_enabled: true, frequency: 2047, duty: 2, ..Default::default() };
+        pulse.envelope.write(0xF0, false);
+        pulse.trigger();
+
+        // At the highest frequency the duty advances every 4 T-cycles, 50% duty is 1000_0111
+        let outputs: Vec<u8> = (0..8).map(|_| {
+            pulse.step(4);
+            pulse.output()
+        }).collect();
+        assert_eq!(outputs, [0, 0, 0, 0, 15, 15, 15, 15]);
+    }
+
+    #[test]
+    fn wave_plays_high_nibble_first() {
+        let wave_ram = [0x12, 0x34, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        let mut wave = Wave { dac_enabled: true, frequency: 2047, volume_code: 1, ..Default::default() };
+        wave.trigger();
+
+        // Triggering starts at sample 0 but only plays from sample 1, once the timer first expires
+        let outputs: Vec<u8> = (0..3).map(|_| {
+            wave.step(2, &wave_ram);
+            wave.output()
+        }).collect();
+        assert_eq!(outputs, [2, 3, 4]);
+    }
+}
@@ -0,0 +1,102 @@
+// Bit 3 of NRx2, the volume goes up instead of down
+const INCREASING: u8 = 1 << 3;
+
+// Volume of channels 1, 2 and 4, stepped up or down every NRx2 period at 64 Hz
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Envelope {
+    pub volume: u8,
+    // NRx2 as last written, most of it only takes effect on the next trigger
+    initial_volume: u8,
+    increasing: bool,
+    period: u8,
+    timer: u8,
+    // Cleared once the volume reaches 0 or 15, until the next trigger
+    running: bool,
+}
+
+impl Envelope {
+    // Writing NRx2 while the channel plays changes the volume through the "zombie mode" quirks of the DMG
+    pub fn write(&mut self, value: u8, channel_enabled: bool) {
+        let increasing = value & INCREASING != 0;
+
+        if channel_enabled {
+            if self.period == 0 && self.running {
+                self.volume += 1;
+            }
+            else if !self.increasing {
+                self.volume += 2;
+            }
+
+            if increasing != self.increasing {
+                self.volume = 16 - self.volume;
+            }
+
+            self.volume &= 0xF;
+        }
+
+        self.initial_volume = value >> 4;
+        self.increasing = increasing;
+        self.period = value & 0x7;
+    }
+
+    pub fn trigger(&mut self) {
+        self.volume = self.initial_volume;
+        self.timer = self.reload();
+        self.running = true;
+    }
+
+    // A period of 0 counts as 8 for the timer, but never steps the volume
+    fn reload(&self) -> u8 {
+        if self.period == 0 { 8 } else { self.period }
+    }
+
+    // Clocked at 64 Hz by the frame sequencer
+    pub fn clock(&mut self) {
+        if self.period == 0 || !self.running {
+            return;
+        }
+
+        self.timer -= 1;
+        if self.timer > 0 {
+            return;
+        }
+
+        self.timer = self.reload();
+        match (self.increasing, self.volume) {
+            (true, 0..15) => self.volume += 1,
+            (false, 1..) => self.volume -= 1,
+            _ => self.running = false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zombie_mode_writes_adjust_the_volume() {
+        let mut envelope = Envelope::default();
+        envelope.write(0x50, false);
+        envelope.trigger();
+        assert_eq!(envelope.volume, 5);
+
+        // Period 0 and still running adds one, then decreasing mode adds two
+        envelope.write(0x50, true);
+        assert_eq!(envelope.volume, 6);
+        envelope.write(0x51, true);
+        assert_eq!(envelope.volume, 7);
+        envelope.write(0x51, true);
+        assert_eq!(envelope.volume, 9);
+
+        // Switching modes flips the volume around 16, after the decreasing mode's two
+        envelope.write(0x59, true);
+        assert_eq!(envelope.volume, 5);
+        envelope.write(0x51, true);
+        assert_eq!(envelope.volume, 11);
+
+        // Nothing changes while the channel is stopped
+        envelope.write(0x59, false);
+        assert_eq!(envelope.volume, 11);
+    }
+}
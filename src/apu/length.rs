@@ -0,0 +1,83 @@
+use crate::apu::TRIGGER;
+
+// Bit 6 of NRx4 lets the length counter stop the channel
+const LENGTH_ENABLE: u8 = 1 << 6;
+
+// Stops its channel once the length loaded through NRx1 runs out, MAX is 64 for the pulse and noise channels and 256 for the wave channel
+#[derive(Copy, Clone, Debug, Default)]
+pub struct LengthCounter<const MAX: u16> {
+    pub enabled: bool,
+    counter: u16,
+}
+
+impl<const MAX: u16> LengthCounter<MAX> {
+    pub fn counter(&self) -> u16 { self.counter }
+
+    // The channel plays for MAX - length frame sequencer clocks
+    pub fn load(&mut self, length: u16) {
+        self.counter = MAX - length;
+    }
+
+    // Clocked at 256 Hz by the frame sequencer
+    pub fn clock(&mut self, channel_enabled: &mut bool) {
+        if self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            if self.counter == 0 {
+                *channel_enabled = false;
+            }
+        }
+    }
+
+    // NRx4 write, before the trigger itself is handled. first_half is set when the next frame sequencer step doesn't clock
+    // the length counters, in which case enabling the counter clocks it once straight away, and a trigger reloading
+    // an expired counter loads MAX - 1 if it is enabled
+    pub fn write_control(&mut self, value: u8, first_half: bool, channel_enabled: &mut bool) {
+        let was_enabled = self.enabled;
+        self.enabled = value & LENGTH_ENABLE != 0;
+        let trigger = value & TRIGGER != 0;
+
+        if first_half && !was_enabled && self.enabled && self.counter > 0 {
+            self.counter -= 1;
+            if self.counter == 0 && !trigger {
+                *channel_enabled = false;
+            }
+        }
+
+        if trigger && self.counter == 0 {
+            self.counter = if first_half && self.enabled { MAX - 1 } else { MAX };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enabling_in_first_half_clocks_once() {
+        let mut channel_enabled = true;
+        let mut length = LengthCounter::<64>::default();
+        length.load(62);
+
+        length.write_control(LENGTH_ENABLE, true, &mut channel_enabled);
+        assert_eq!(length.counter(), 1);
+        assert!(channel_enabled);
+
+        // Already enabled, so no extra clock this time
+        length.write_control(LENGTH_ENABLE, true, &mut channel_enabled);
+        assert_eq!(length.counter(), 1);
+
+        length.enabled = false;
+        length.write_control(LENGTH_ENABLE, true, &mut channel_enabled);
+        assert_eq!(length.counter(), 0);
+        assert!(!channel_enabled);
+
+        // Triggering reloads the expired counter, minus the extra clock
+        length.write_control(TRIGGER | LENGTH_ENABLE, true, &mut channel_enabled);
+        assert_eq!(length.counter(), 63);
+
+        let mut length = LengthCounter::<256>::default();
+        length.write_control(TRIGGER, false, &mut channel_enabled);
+        assert_eq!(length.counter(), 256);
+    }
+}
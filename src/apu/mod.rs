@@ -0,0 +1,705 @@
+mod channels;
+mod envelope;
+mod length;
+mod sweep;
+pub mod ring_buffer;
+
+use crate::apu::channels::{Noise, Pulse, Wave};
+use crate::apu::ring_buffer::{ring_buffer, Consumer, Producer};
+use crate::apu::sweep::Sweep;
+
+// The channels are generated once per M-cycle, ~1.05 MHz, then averaged down to the output sample rate
+pub const GENERATION_RATE: u32 = 1_048_576;
+
+// NR10 to the end of wave RAM
+pub const REGISTERS_START: usize = 0xFF10;
+pub const REGISTERS_END: usize = 0xFF3F;
+
+// The frame sequencer clocks the length counters, envelopes and sweep at 512 Hz
+const FRAME_SEQUENCER_T_CYCLES: u32 = 8192;
+
+const NR10: usize = 0xFF10;
+const NR11: usize = 0xFF11;
+const NR12: usize = 0xFF12;
+const NR13: usize = 0xFF13;
+const NR14: usize = 0xFF14;
+const NR21: usize = 0xFF16;
+const NR22: usize = 0xFF17;
+const NR23: usize = 0xFF18;
+const NR24: usize = 0xFF19;
+const NR30: usize = 0xFF1A;
+const NR31: usize = 0xFF1B;
+const NR32: usize = 0xFF1C;
+const NR33: usize = 0xFF1D;
+const NR34: usize = 0xFF1E;
+const NR41: usize = 0xFF20;
+const NR42: usize = 0xFF21;
+const NR43: usize = 0xFF22;
+const NR44: usize = 0xFF23;
+const NR50: usize = 0xFF24;
+const NR51: usize = 0xFF25;
+pub const NR52: usize = 0xFF26;
+const WAVE_RAM: usize = 0xFF30;
+
+// Bit 7 of NRx4 restarts the channel
+const TRIGGER: u8 = 1 << 7;
+
+// Bit 7 of NR52 powers the APU on
+const POWER: u8 = 1 << 7;
+
+// Bits of NR10-NR52 that always read as 1, either unused or write-only. Wave RAM reads back as written
+const READ_MASKS: [u8; WAVE_RAM - REGISTERS_START] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF,
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF,
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF,
+    0xFF, 0xFF, 0x00, 0x00, 0xBF,
+    0x00, 0x00, 0x70,
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+// Output format expected by the audio backend
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AudioConfig {
+    // Stereo frames per second, usually 44100 or 48000
+    pub sample_rate: u32,
+    // Frames held until the backend takes them, new frames are dropped once it is full
+    pub buffer_frames: usize,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self { sample_rate: 48000, buffer_frames: 4096 }
+    }
+}
+
+// Snapshot of a channel for debugging views, see Apu::channel_state
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChannelState {
+    // Playing, as reported by NR52
+    pub enabled: bool,
+    pub dac_enabled: bool,
+    // 11 bit frequency of channels 1-3, NR43 for channel 4
+    pub frequency: u16,
+    // Current envelope volume (0-15), for channel 3 the loudest sample after the NR32 volume shift
+    pub volume: u8,
+    // Duty cycle (0-3) of channels 1 and 2
+    pub duty: u8,
+    // Length counter clocks left before the channel stops
+    pub length: u16,
+}
+
+#[derive(Clone)]
+pub struct Apu {
+    config: AudioConfig,
+    // NR10-NR52 and wave RAM as last written
+    registers: [u8; REGISTERS_END - REGISTERS_START + 1],
+
+    pulse1: Pulse,
+    sweep: Sweep,
+    pulse2: Pulse,
+    wave: Wave,
+    noise: Noise,
+    // Channels 1-4 left out of the mix for debugging, the registers and status bits still behave as usual
+    channel_enabled: [bool; 4],
+
+    // T-cycles since the last frame sequencer step, and the next step (0-7)
+    // The length counters are clocked on the even steps, the sweep on steps 2 and 6 and the envelopes on step 7
+    frame_sequencer_t_cycles: u32,
+    frame_sequencer_step: u8,
+
+    // T-cycles not generated yet, less than an M-cycle
+    pending_t_cycles: u32,
+    // Advanced by the sample rate every M-cycle, a frame is output each time it goes past GENERATION_RATE
+    sample_phase: u32,
+    // Sum and count of the generated stereo samples averaged into the next output frame
+    sample_sum: [f32; 2],
+    sample_count: u32,
+    // High-pass filter removing the DC offset of the DACs, as the capacitor on the real output does
+    capacitors: [f32; 2],
+    charge_factor: f32,
+
+    // Shared with the audio backend, which reads it through take_consumer
+    output: Producer,
+    // Frames are still generated but not queued, see set_output_muted
+    output_muted: bool,
+}
+
+impl Apu {
+    pub fn new(config: AudioConfig) -> Self {
+        Self {
+            config,
+            registers: [0; REGISTERS_END - REGISTERS_START + 1],
+            pulse1: Pulse::default(),
+            sweep: Sweep::default(),
+            pulse2: Pulse::default(),
+            wave: Wave::default(),
+            noise: Noise::default(),
+            channel_enabled: [true; 4],
+            frame_sequencer_t_cycles: 0,
+            frame_sequencer_step: 0,
+            pending_t_cycles: 0,
+            sample_phase: 0,
+            sample_sum: [0.0; 2],
+            sample_count: 0,
+            capacitors: [0.0; 2],
+            // The capacitor discharges by 0.999958 every T-cycle
+            charge_factor: 0.999958_f64.powf(4_194_304.0 / config.sample_rate as f64) as f32,
+            output: ring_buffer(config.buffer_frames),
+            output_muted: false,
+        }
+    }
+
+    pub fn config(&self) -> AudioConfig { self.config }
+
+    // Times the backend asked for more frames than were ready, and frames dropped because the buffer was full
+    pub fn underruns(&self) -> u64 { self.output.underruns() }
+    pub fn overruns(&self) -> u64 { self.output.overruns() }
+
+    // Frames waiting for the backend
+    pub fn queued_frames(&self) -> usize { self.output.len() }
+
+    // The end of the output buffer the backend plays from, None if it was already taken
+    pub fn take_consumer(&self) -> Option<Consumer> { self.output.consumer() }
+
+    // Stop queueing frames, for emulation that shouldn't be heard such as replays of a snapshot, which shares the output buffer
+    pub fn set_output_muted(&mut self, muted: bool) {
+        self.output_muted = muted;
+    }
+
+    // Mute or unmute channel 1-4
+    pub fn set_channel_enabled(&mut self, channel: u8, on: bool) {
+        if let Some(enabled) = self.channel_enabled.get_mut((channel as usize).wrapping_sub(1)) {
+            *enabled = on;
+        }
+    }
+
+    pub fn is_channel_enabled(&self, channel: u8) -> bool {
+        self.channel_enabled.get((channel as usize).wrapping_sub(1)).copied().unwrap_or(false)
+    }
+
+    // State of channel 1-4, None for any other number
+    pub fn channel_state(&self, channel: u8) -> Option<ChannelState> {
+        match channel {
+            1 => Some(self.pulse1.state()),
+            2 => Some(self.pulse2.state()),
+            3 => Some(self.wave.state()),
+            4 => Some(self.noise.state()),
+            _ => None,
+        }
+    }
+
+    fn register(&self, address: usize) -> u8 {
+        self.registers[address - REGISTERS_START]
+    }
+
+    fn is_powered(&self) -> bool {
+        self.register(NR52) & POWER != 0
+    }
+
+    // NR52 bits 0-3, set while each channel is playing
+    fn channel_status(&self) -> u8 {
+        [self.pulse1.enabled, self.pulse2.enabled, self.wave.enabled, self.noise.enabled].iter()
+            .enumerate()
+            .fold(0, |status, (channel, enabled)| status | ((*enabled as u8) << channel))
+    }
+
+    // 11 bit frequency from the low byte in NRx3 and the upper 3 bits in NRx4
+    fn frequency(&self, low: usize, high: usize) -> u16 {
+        self.register(low) as u16 | ((self.register(high) as u16 & 0x7) << 8)
+    }
+
+    pub fn read_register(&self, address: usize) -> u8 {
+        match address {
+            NR52 => READ_MASKS[NR52 - REGISTERS_START] | (self.register(NR52) & POWER) | self.channel_status(),
+            WAVE_RAM.. => self.register(address),
+            _ => self.register(address) | READ_MASKS[address - REGISTERS_START],
+        }
+    }
+
+    pub fn write_register(&mut self, address: usize, value: u8) {
+        // While powered off only NR52 and wave RAM can be written, apart from the lengths in NRx1 on the DMG
+        if !self.is_powered() && address < NR52 {
+            match address {
+                NR11 => self.pulse1.length.load(value as u16 & 0x3F),
+                NR21 => self.pulse2.length.load(value as u16 & 0x3F),
+                NR31 => self.wave.length.load(value as u16),
+                NR41 => self.noise.length.load(value as u16 & 0x3F),
+                _ => {}
+            }
+            return;
+        }
+
+        if address == NR52 {
+            self.write_power(value & POWER != 0);
+            return;
+        }
+
+        self.registers[address - REGISTERS_START] = value;
+
+        match address {
+            NR10 => self.sweep.write(value, &mut self.pulse1.enabled),
+            NR11 => {
+                self.pulse1.duty = value >> 6;
+                self.pulse1.length.load(value as u16 & 0x3F);
+            }
+            NR12 => {
+                self.pulse1.envelope.write(value, self.pulse1.enabled);
+                self.pulse1.set_dac_enabled(value & 0xF8 != 0);
+            }
+            NR13 | NR14 => {
+                self.pulse1.frequency = self.frequency(NR13, NR14);
+                if address == NR14 {
+                    self.pulse1.length.write_control(value, self.length_first_half(), &mut self.pulse1.enabled);
+                    if value & TRIGGER != 0 {
+                        self.pulse1.trigger();
+                        self.sweep.trigger(self.pulse1.frequency, &mut self.pulse1.enabled);
+                    }
+                }
+            }
+            NR21 => {
+                self.pulse2.duty = value >> 6;
+                self.pulse2.length.load(value as u16 & 0x3F);
+            }
+            NR22 => {
+                self.pulse2.envelope.write(value, self.pulse2.enabled);
+                self.pulse2.set_dac_enabled(value & 0xF8 != 0);
+            }
+            NR23 | NR24 => {
+                self.pulse2.frequency = self.frequency(NR23, NR24);
+                if address == NR24 {
+                    self.pulse2.length.write_control(value, self.length_first_half(), &mut self.pulse2.enabled);
+                    if value & TRIGGER != 0 {
+                        self.pulse2.trigger();
+                    }
+                }
+            }
+            NR30 => self.wave.set_dac_enabled(value & (1 << 7) != 0),
+            NR31 => self.wave.length.load(value as u16),
+            NR32 => self.wave.volume_code = (value >> 5) & 0x3,
+            NR33 | NR34 => {
+                self.wave.frequency = self.frequency(NR33, NR34);
+                if address == NR34 {
+                    self.wave.length.write_control(value, self.length_first_half(), &mut self.wave.enabled);
+                    if value & TRIGGER != 0 {
+                        self.wave.trigger();
+                    }
+                }
+            }
+            NR41 => self.noise.length.load(value as u16 & 0x3F),
+            NR42 => {
+                self.noise.envelope.write(value, self.noise.enabled);
+                self.noise.set_dac_enabled(value & 0xF8 != 0);
+            }
+            NR43 => self.noise.control = value,
+            NR44 => {
+                self.noise.length.write_control(value, self.length_first_half(), &mut self.noise.enabled);
+                if value & TRIGGER != 0 {
+                    self.noise.trigger();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Powering off clears NR10-NR51 and stops the channels, only the length counters are kept on the DMG
+    // Powering on restarts the frame sequencer from step 0
+    fn write_power(&mut self, on: bool) {
+        if on == self.is_powered() {
+            return;
+        }
+
+        if on {
+            self.frame_sequencer_t_cycles = 0;
+            self.frame_sequencer_step = 0;
+            self.registers[NR52 - REGISTERS_START] = POWER;
+            return;
+        }
+
+        let lengths = (self.pulse1.length, self.pulse2.length, self.wave.length, self.noise.length);
+        self.registers[..=NR52 - REGISTERS_START].fill(0);
+        self.pulse1 = Pulse::default();
+        self.sweep = Sweep::default();
+        self.pulse2 = Pulse::default();
+        self.wave = Wave::default();
+        self.noise = Noise::default();
+        (self.pulse1.length, self.pulse2.length, self.wave.length, self.noise.length) = lengths;
+        // NRx4 is cleared along with the rest, so the counters are left disabled
+        self.pulse1.length.enabled = false;
+        self.pulse2.length.enabled = false;
+        self.wave.length.enabled = false;
+        self.noise.length.enabled = false;
+    }
+
+    // The sweep writes its new frequencies back to NR13 and NR14
+    fn set_pulse1_frequency(&mut self, frequency: u16) {
+        self.pulse1.frequency = frequency;
+        self.registers[NR13 - REGISTERS_START] = frequency as u8;
+        self.registers[NR14 - REGISTERS_START] = (self.register(NR14) & !0x7) | (frequency >> 8) as u8;
+    }
+
+    // Whether the next frame sequencer step leaves the length counters alone
+    fn length_first_half(&self) -> bool {
+        !self.frame_sequencer_step.is_multiple_of(2)
+    }
+
+    fn step_frame_sequencer(&mut self) {
+        if self.frame_sequencer_step.is_multiple_of(2) {
+            self.pulse1.length.clock(&mut self.pulse1.enabled);
+            self.pulse2.length.clock(&mut self.pulse2.enabled);
+            self.wave.length.clock(&mut self.wave.enabled);
+            self.noise.length.clock(&mut self.noise.enabled);
+        }
+
+        if matches!(self.frame_sequencer_step, 2 | 6)
+            && let Some(frequency) = self.sweep.clock(&mut self.pulse1.enabled)
+        {
+            self.set_pulse1_frequency(frequency);
+        }
+
+        if self.frame_sequencer_step == 7 {
+            self.pulse1.envelope.clock();
+            self.pulse2.envelope.clock();
+            self.noise.envelope.clock();
+        }
+
+        self.frame_sequencer_step = (self.frame_sequencer_step + 1) % 8;
+    }
+
+    // Advance the channels by the T-cycles taken by the last CPU step, queueing the output frames they complete
+    pub fn emulation_loop(&mut self, t_cycles: u8) {
+        self.pending_t_cycles += t_cycles as u32;
+
+        while self.pending_t_cycles >= 4 {
+            self.pending_t_cycles -= 4;
+            self.tick();
+        }
+    }
+
+    // Silent frames keep being output while the APU is powered off
+    fn tick(&mut self) {
+        if self.is_powered() {
+            self.frame_sequencer_t_cycles += 4;
+            if self.frame_sequencer_t_cycles == FRAME_SEQUENCER_T_CYCLES {
+                self.frame_sequencer_t_cycles = 0;
+                self.step_frame_sequencer();
+            }
+
+            self.pulse1.step(4);
+            self.pulse2.step(4);
+            let wave_ram = &self.registers[WAVE_RAM - REGISTERS_START..];
+            self.wave.step(4, wave_ram);
+            self.noise.step(4);
+        }
+
+        let [left, right] = self.mix();
+        self.sample_sum[0] += left;
+        self.sample_sum[1] += right;
+        self.sample_count += 1;
+
+        self.sample_phase += self.config.sample_rate;
+        if self.sample_phase >= GENERATION_RATE {
+            self.sample_phase -= GENERATION_RATE;
+
+            let frame = self.sample_sum.map(|sum| sum / self.sample_count as f32);
+            self.sample_sum = [0.0; 2];
+            self.sample_count = 0;
+
+            let frame = self.high_pass(frame);
+            if !self.output_muted {
+                self.output.push(frame);
+            }
+        }
+    }
+
+    // Stereo output of the channels that aren't muted, panned by NR51 and scaled by the master volumes of NR50
+    fn mix(&self) -> [f32; 2] {
+        if !self.is_powered() {
+            return [0.0; 2];
+        }
+
+        let outputs = [
+            Self::dac(self.pulse1.output(), self.pulse1.dac_enabled),
+            Self::dac(self.pulse2.output(), self.pulse2.dac_enabled),
+            Self::dac(self.wave.output(), self.wave.dac_enabled),
+            Self::dac(self.noise.output(), self.noise.dac_enabled),
+        ];
+
+        let panning = self.register(NR51);
+        let master_volume = self.register(NR50);
+        // Right is in the lower nibble of both registers, left in the upper one
+        [4, 0].map(|shift| {
+            let sum: f32 = outputs.iter().enumerate()
+                .filter(|(channel, _)| self.channel_enabled[*channel] && panning & (1 << (channel + shift)) != 0)
+                .map(|(_, output)| output)
+                .sum();
+            let volume = ((master_volume >> shift) & 0x7) as f32 + 1.0;
+
+            sum / 4.0 * volume / 8.0
+        })
+    }
+
+    // Digital channel output (0-15) to -1.0..1.0, a disabled DAC outputs nothing
+    fn dac(output: u8, dac_enabled: bool) -> f32 {
+        if dac_enabled { output as f32 / 7.5 - 1.0 } else { 0.0 }
+    }
+
+    fn high_pass(&mut self, frame: [f32; 2]) -> [f32; 2] {
+        std::array::from_fn(|side| {
+            let output = frame[side] - self.capacitors[side];
+            self.capacitors[side] = frame[side] - output * self.charge_factor;
+
+            output
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_second_at_48_khz() {
+        let mut apu = Apu::new(AudioConfig { sample_rate: 48000, buffer_frames: 1024 });
+        let mut consumer = apu.take_consumer().unwrap();
+        let mut buffer = [[0.0; 2]; 1024];
+
+        // 4194304 T-cycles, taken by the backend every 65536 T-cycles
+        let mut frames = 0;
+        for _ in 0..64 {
+            (0..65536 / 4).for_each(|_| apu.emulation_loop(4));
+
+            let queued = apu.queued_frames();
+            frames += consumer.take_samples(&mut buffer[..queued]);
+        }
+
+        assert_eq!(frames, 48000);
+        assert_eq!(apu.underruns(), 0);
+        assert_eq!(apu.overruns(), 0);
+    }
+
+    #[test]
+    fn wave_dac_off_stops_channel_3() {
+        let mut apu = Apu::new(AudioConfig::default());
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR30, 0x80);
+        apu.write_register(NR34, TRIGGER);
+        assert_eq!(apu.read_register(NR52) & (1 << 2), 1 << 2);
+
+        apu.write_register(NR30, 0x00);
+        assert_eq!(apu.read_register(NR52) & (1 << 2), 0);
+
+        // Turning the DAC back on doesn't restart the channel
+        apu.write_register(NR30, 0x80);
+        assert_eq!(apu.read_register(NR52) & (1 << 2), 0);
+        apu.write_register(NR34, TRIGGER);
+        assert_eq!(apu.read_register(NR52) & (1 << 2), 1 << 2);
+    }
+
+    #[test]
+    fn length_counter_stops_channel() {
+        let mut apu = Apu::new(AudioConfig::default());
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR22, 0xF0);
+        // Two length clocks left, which happen on frame sequencer steps 0 and 2
+        apu.write_register(NR21, 62);
+        apu.write_register(NR24, TRIGGER | (1 << 6));
+
+        (0..3 * FRAME_SEQUENCER_T_CYCLES / 4 - 1).for_each(|_| apu.emulation_loop(4));
+        assert_eq!(apu.read_register(NR52) & (1 << 1), 1 << 1);
+
+        apu.emulation_loop(4);
+        assert_eq!(apu.read_register(NR52) & (1 << 1), 0);
+    }
+
+    #[test]
+    fn envelope_steps_volume_down_at_64_hz() {
+        let mut apu = Apu::new(AudioConfig::default());
+        apu.write_register(NR52, 0x80);
+        // Volume 3, decreasing every envelope clock
+        apu.write_register(NR12, 0x31);
+        apu.write_register(NR14, TRIGGER);
+        assert_eq!(apu.pulse1.envelope.volume, 3);
+
+        // The envelopes are clocked on the last of the 8 frame sequencer steps
+        let volumes: Vec<u8> = (0..4).map(|_| {
+            (0..8 * FRAME_SEQUENCER_T_CYCLES / 4).for_each(|_| apu.emulation_loop(4));
+            apu.pulse1.envelope.volume
+        }).collect();
+        assert_eq!(volumes, [2, 1, 0, 0]);
+        assert_eq!(apu.read_register(NR52) & 1, 1);
+    }
+
+    #[test]
+    fn sweep_overflow_stops_channel_1() {
+        let mut apu = Apu::new(AudioConfig::default());
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR12, 0xF0);
+        // Every sweep clock adds half of the frequency
+        apu.write_register(NR10, 0x11);
+        apu.write_register(NR13, 0x00);
+        apu.write_register(NR14, TRIGGER | 0x05);
+        assert_eq!(apu.read_register(NR52) & 1, 1);
+
+        // Frame sequencer step 2 sweeps 0x500 up to 0x780, whose next step 0xB40 overflows
+        (0..3 * FRAME_SEQUENCER_T_CYCLES / 4).for_each(|_| apu.emulation_loop(4));
+        assert_eq!(apu.pulse1.frequency, 0x780);
+        assert_eq!(apu.frequency(NR13, NR14), 0x780);
+        assert_eq!(apu.read_register(NR52) & 1, 0);
+    }
+
+    // Start the given channels (1-4) at full volume, panned to both sides
+    fn play_channels(apu: &mut Apu, channels: &[u8]) {
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR50, 0x77);
+        apu.write_register(NR51, 0xFF);
+        (0..16).for_each(|i| apu.write_register(WAVE_RAM + i, 0x0F));
+
+        for channel in channels {
+            match channel {
+                1 => [(NR12, 0xF0), (NR13, 0x00), (NR14, TRIGGER | 0x07)].iter().for_each(|(address, value)| apu.write_register(*address, *value)),
+                2 => [(NR22, 0xF0), (NR23, 0x80), (NR24, TRIGGER | 0x06)].iter().for_each(|(address, value)| apu.write_register(*address, *value)),
+                3 => [(NR30, 0x80), (NR32, 0x20), (NR34, TRIGGER | 0x07)].iter().for_each(|(address, value)| apu.write_register(*address, *value)),
+                _ => [(NR42, 0xF0), (NR43, 0x11), (NR44, TRIGGER)].iter().for_each(|(address, value)| apu.write_register(*address, *value)),
+            }
+        }
+    }
+
+    fn output_frames(apu: &mut Apu, count: usize) -> Vec<[f32; 2]> {
+        let mut consumer = apu.take_consumer().unwrap();
+        while apu.queued_frames() < count {
+            apu.emulation_loop(4);
+        }
+
+        let mut frames = vec![[0.0; 2]; count];
+        consumer.take_samples(&mut frames);
+        frames
+    }
+
+    #[test]
+    fn muted_channels_are_left_out_of_the_mix() {
+        let mut solo = Apu::new(AudioConfig::default());
+        play_channels(&mut solo, &[1, 2, 3, 4]);
+        (2..=4).for_each(|channel| solo.set_channel_enabled(channel, false));
+        assert!(!solo.is_channel_enabled(2));
+        assert!(solo.is_channel_enabled(1));
+
+        let mut channel_1 = Apu::new(AudioConfig::default());
+        play_channels(&mut channel_1, &[1]);
+
+        let frames = output_frames(&mut solo, 256);
+        assert_eq!(frames, output_frames(&mut channel_1, 256));
+        assert!(frames.iter().any(|frame| frame[0].abs() > 0.1));
+        // Muting doesn't touch the emulated state
+        assert_eq!(solo.read_register(NR52), 0xFF);
+    }
+
+    #[test]
+    fn muted_output_queues_nothing() {
+        let mut apu = Apu::new(AudioConfig::default());
+        let snapshot = apu.clone();
+        apu.set_output_muted(true);
+
+        // A clone shares the output buffer, only the unmuted one fills it
+        apu.emulation_loop(200);
+        assert_eq!(snapshot.queued_frames(), 0);
+        apu.set_output_muted(false);
+        apu.emulation_loop(200);
+        assert!(snapshot.queued_frames() > 0);
+    }
+
+    #[test]
+    fn channel_state_follows_trigger() {
+        let mut apu = Apu::new(AudioConfig::default());
+        apu.write_register(NR52, 0x80);
+        assert_eq!(apu.channel_state(1), Some(ChannelState::default()));
+
+        apu.write_register(NR11, 0x80 | 60);
+        apu.write_register(NR12, 0xA0);
+        apu.write_register(NR13, 0x34);
+        apu.write_register(NR14, TRIGGER | 0x06);
+
+        let state = apu.channel_state(1).unwrap();
+        assert!(state.enabled);
+        assert_eq!(state.frequency, 0x634);
+        assert_eq!(state.volume, 10);
+        assert_eq!(state.duty, 2);
+        assert_eq!(state.length, 4);
+        assert!(!apu.channel_state(2).unwrap().enabled);
+        assert_eq!(apu.channel_state(5), None);
+    }
+
+    #[test]
+    fn nr52_unused_bits_read_as_1() {
+        let mut apu = Apu::new(AudioConfig::default());
+        assert_eq!(apu.read_register(NR52), 0x70);
+
+        // The channel status bits are read-only
+        apu.write_register(NR52, 0xFF);
+        assert_eq!(apu.read_register(NR52), 0xF0);
+
+        apu.write_register(NR12, 0xF0);
+        apu.write_register(NR14, TRIGGER);
+        assert_eq!(apu.read_register(NR52), 0xF1);
+
+        // Write-only bits read as 1 too
+        assert_eq!(apu.read_register(NR14), 0xBF);
+        assert_eq!(apu.read_register(NR13), 0xFF);
+        assert_eq!(apu.read_register(NR12), 0xF0);
+        assert_eq!(apu.read_register(0xFF27), 0xFF);
+    }
+
+    #[test]
+    fn dac_off_stops_channel() {
+        let mut apu = Apu::new(AudioConfig::default());
+        play_channels(&mut apu, &[1, 2, 4]);
+        assert_eq!(apu.read_register(NR52), 0xFB);
+
+        // Volume 0 and decreasing turns the DAC off, anything else leaves the channel stopped until a trigger
+        apu.write_register(NR22, 0x00);
+        apu.write_register(NR42, 0x07);
+        assert_eq!(apu.read_register(NR52), 0xF1);
+        apu.write_register(NR22, 0x08);
+        assert_eq!(apu.read_register(NR52), 0xF1);
+    }
+
+    #[test]
+    fn power_off_clears_registers_and_ignores_writes() {
+        let mut apu = Apu::new(AudioConfig::default());
+        play_channels(&mut apu, &[1, 3]);
+        apu.write_register(NR11, 0x80 | 60);
+
+        apu.write_register(NR52, 0x00);
+        assert_eq!(apu.read_register(NR52), 0x70);
+        assert_eq!(apu.read_register(NR50), 0x00);
+        assert_eq!(apu.read_register(NR11), 0x3F);
+        assert_eq!(apu.read_register(WAVE_RAM), 0x0F);
+
+        apu.write_register(NR50, 0x77);
+        apu.write_register(NR14, TRIGGER);
+        assert_eq!(apu.read_register(NR50), 0x00);
+        assert_eq!(apu.read_register(NR52), 0x70);
+
+        // The length counters are kept, and can still be loaded on the DMG
+        assert_eq!(apu.pulse1.length.counter(), 4);
+        apu.write_register(NR21, 0xC0 | 63);
+        assert_eq!(apu.pulse2.length.counter(), 1);
+        apu.write_register(NR52, 0x80);
+        assert_eq!(apu.read_register(NR21), 0x3F);
+    }
+
+    #[test]
+    fn buffer_overrun_and_underrun_are_counted() {
+        let mut apu = Apu::new(AudioConfig { sample_rate: 48000, buffer_frames: 16 });
+        let mut consumer = apu.take_consumer().unwrap();
+
+        // 20 frames worth of M-cycles at 48 kHz, the last 4 don't fit
+        (0..20 * GENERATION_RATE / 48000 + 1).for_each(|_| apu.emulation_loop(4));
+        assert_eq!(apu.queued_frames(), 16);
+        assert_eq!(apu.overruns(), 4);
+
+        let mut buffer = [[1.0; 2]; 32];
+        assert_eq!(consumer.take_samples(&mut buffer), 16);
+        assert_eq!(apu.underruns(), 1);
+        assert_eq!(buffer[31], [0.0; 2]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+// Lock-free single producer, single consumer queue of stereo frames, between the APU and the audio backend's callback thread
+// Neither side ever waits on the other: the producer drops frames when the queue is full, the consumer plays silence when it is empty
+struct Shared {
+    // Left sample in the upper 32 bits, right sample in the lower ones
+    slots: Box<[AtomicU64]>,
+    // Frames read and written so far, a frame goes in the slot at its index modulo the capacity
+    // Only the consumer stores to read and only the producer to written
+    read: AtomicUsize,
+    written: AtomicUsize,
+    // Set while a Consumer exists, there can only be one
+    consumer_taken: AtomicBool,
+    // Frames dropped because the queue was full, and reads that ran out of frames
+    overruns: AtomicU64,
+    underruns: AtomicU64,
+}
+
+// Clones push into the same queue, so an APU restored from a snapshot plays through the same output unless muted
+#[derive(Clone)]
+pub struct Producer {
+    shared: Arc<Shared>,
+}
+
+pub struct Consumer {
+    shared: Arc<Shared>,
+}
+
+pub fn ring_buffer(capacity: usize) -> Producer {
+    let shared = Shared {
+        slots: (0..capacity.max(1)).map(|_| AtomicU64::new(0)).collect(),
+        read: AtomicUsize::new(0),
+        written: AtomicUsize::new(0),
+        consumer_taken: AtomicBool::new(false),
+        overruns: AtomicU64::new(0),
+        underruns: AtomicU64::new(0),
+    };
+
+    Producer { shared: Arc::new(shared) }
+}
+
+fn pack(frame: [f32; 2]) -> u64 {
+    ((frame[0].to_bits() as u64) << 32) | frame[1].to_bits() as u64
+}
+
+fn unpack(slot: u64) -> [f32; 2] {
+    [f32::from_bits((slot >> 32) as u32), f32::from_bits(slot as u32)]
+}
+
+impl Shared {
+    fn len(&self) -> usize {
+        self.written.load(Ordering::Acquire).wrapping_sub(self.read.load(Ordering::Acquire))
+    }
+}
+
+impl Producer {
+    pub fn capacity(&self) -> usize { self.shared.slots.len() }
+
+    // Frames waiting for the consumer
+    pub fn len(&self) -> usize { self.shared.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn overruns(&self) -> u64 { self.shared.overruns.load(Ordering::Relaxed) }
+    pub fn underruns(&self) -> u64 { self.shared.underruns.load(Ordering::Relaxed) }
+
+    // Queue a frame, returns false and counts an overrun if the queue is full
+    pub fn push(&self, frame: [f32; 2]) -> bool {
+        let written = self.shared.written.load(Ordering::Relaxed);
+        if written.wrapping_sub(self.shared.read.load(Ordering::Acquire)) >= self.capacity() {
+            self.shared.overruns.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        self.shared.slots[written % self.capacity()].store(pack(frame), Ordering::Relaxed);
+        // Publishes the slot to the consumer
+        self.shared.written.store(written.wrapping_add(1), Ordering::Release);
+
+        true
+    }
+
+    // The reading end of the queue, None while another consumer exists
+    pub fn consumer(&self) -> Option<Consumer> {
+        let taken = self.shared.consumer_taken.swap(true, Ordering::AcqRel);
+
+        (!taken).then(|| Consumer { shared: self.shared.clone() })
+    }
+}
+
+impl Consumer {
+    pub fn len(&self) -> usize { self.shared.len() }
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn pop(&mut self) -> Option<[f32; 2]> {
+        let read = self.shared.read.load(Ordering::Relaxed);
+        if read == self.shared.written.load(Ordering::Acquire) {
+            return None;
+        }
+
+        let frame = unpack(self.shared.slots[read % self.shared.slots.len()].load(Ordering::Relaxed));
+        // Hands the slot back to the producer
+        self.shared.read.store(read.wrapping_add(1), Ordering::Release);
+
+        Some(frame)
+    }
+
+    // Fill out with the queued frames, padding with silence when there aren't enough. Returns the frames taken
+    pub fn take_samples(&mut self, out: &mut [[f32; 2]]) -> usize {
+        let mut taken = 0;
+        for frame in out.iter_mut() {
+            match self.pop() {
+                Some(sample) => {
+                    *frame = sample;
+                    taken += 1;
+                }
+                None => *frame = [0.0; 2],
+            }
+        }
+
+        if taken < out.len() {
+            self.shared.underruns.fetch_add(1, Ordering::Relaxed);
+        }
+
+        taken
+    }
+
+    // Same as take_samples, for backends wanting the left and right samples interleaved
+    pub fn take_interleaved(&mut self, out: &mut [f32]) -> usize {
+        let (frames, _) = out.as_chunks_mut::<2>();
+
+        self.take_samples(frames)
+    }
+}
+
+impl Drop for Consumer {
+    fn drop(&mut self) {
+        self.shared.consumer_taken.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_wrap_around_the_slots() {
+        let producer = ring_buffer(4);
+        let mut consumer = producer.consumer().unwrap();
+        assert!(producer.consumer().is_none());
+
+        // Each round moves the indices 3 slots further, wrapping past the end of the 4 slots
+        for round in 0..5 {
+            let frames: Vec<[f32; 2]> = (0..3).map(|i| [(round * 3 + i) as f32, -1.0]).collect();
+            assert!(frames.iter().all(|frame| producer.push(*frame)));
+            assert_eq!(consumer.len(), 3);
+
+            let mut out = [[0.0; 2]; 3];
+            assert_eq!(consumer.take_samples(&mut out), 3);
+            assert_eq!(out.to_vec(), frames);
+        }
+        assert_eq!(producer.underruns(), 0);
+
+        (0..4).for_each(|i| assert!(producer.push([i as f32; 2])));
+        assert!(!producer.push([4.0; 2]));
+        assert_eq!(producer.overruns(), 1);
+        assert_eq!(consumer.pop(), Some([0.0; 2]));
+
+        let mut out = [1.0; 8];
+        assert_eq!(consumer.take_interleaved(&mut out), 3);
+        assert_eq!(out, [1.0, 1.0, 2.0, 2.0, 3.0, 3.0, 0.0, 0.0]);
+        assert_eq!(producer.underruns(), 1);
+
+        drop(consumer);
+        assert!(producer.consumer().is_some());
+    }
+}
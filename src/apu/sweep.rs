@@ -0,0 +1,125 @@
+// Bit 3 of NR10, the frequency goes down instead of up
+const NEGATE: u8 = 1 << 3;
+
+const MAX_FREQUENCY: u16 = 0x7FF;
+
+// Frequency sweep of channel 1, recomputing the frequency every NR10 period at 128 Hz
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Sweep {
+    period: u8,
+    negate: bool,
+    shift: u8,
+    timer: u8,
+    // Set on trigger when the period or shift is non-zero
+    enabled: bool,
+    // Frequency the sweep works from, copied on trigger so that NR13/NR14 writes don't affect it
+    shadow_frequency: u16,
+    // A subtraction was done since the last trigger
+    negate_used: bool,
+}
+
+impl Sweep {
+    // Clearing the negate bit after a subtraction has been done stops the channel
+    pub fn write(&mut self, value: u8, channel_enabled: &mut bool) {
+        self.period = (value >> 4) & 0x7;
+        self.negate = value & NEGATE != 0;
+        self.shift = value & 0x7;
+
+        if self.negate_used && !self.negate {
+            *channel_enabled = false;
+        }
+    }
+
+    // A non-zero shift checks the next frequency straight away, which can stop the channel
+    pub fn trigger(&mut self, frequency: u16, channel_enabled: &mut bool) {
+        self.shadow_frequency = frequency;
+        self.timer = self.reload();
+        self.enabled = self.period != 0 || self.shift != 0;
+        self.negate_used = false;
+
+        if self.shift != 0 {
+            self.calculate(channel_enabled);
+        }
+    }
+
+    // A period of 0 counts as 8 for the timer, but never updates the frequency
+    fn reload(&self) -> u8 {
+        if self.period == 0 { 8 } else { self.period }
+    }
+
+    // Next frequency from the shadow frequency, stopping the channel when it goes past 0x7FF
+    fn calculate(&mut self, channel_enabled: &mut bool) -> u16 {
+        let delta = self.shadow_frequency >> self.shift;
+        let frequency = if self.negate {
+            self.negate_used = true;
+            self.shadow_frequency - delta
+        }
+        else {
+            self.shadow_frequency + delta
+        };
+
+        if frequency > MAX_FREQUENCY {
+            *channel_enabled = false;
+        }
+
+        frequency
+    }
+
+    // Clocked at 128 Hz by the frame sequencer, returns the new frequency of the channel when it changes
+    // The new frequency is checked again for an overflow, without being used
+    pub fn clock(&mut self, channel_enabled: &mut bool) -> Option<u16> {
+        // The timer is still 0 if the channel was never triggered
+        self.timer = self.timer.saturating_sub(1);
+        if self.timer > 0 {
+            return None;
+        }
+
+        self.timer = self.reload();
+        if !self.enabled || self.period == 0 {
+            return None;
+        }
+
+        let frequency = self.calculate(channel_enabled);
+        if frequency > MAX_FREQUENCY || self.shift == 0 {
+            return None;
+        }
+
+        self.shadow_frequency = frequency;
+        self.calculate(channel_enabled);
+
+        Some(frequency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_before_trigger_does_nothing() {
+        let mut channel_enabled = false;
+        let mut sweep = Sweep::default();
+
+        assert_eq!(sweep.clock(&mut channel_enabled), None);
+        assert_eq!(sweep.clock(&mut channel_enabled), None);
+        assert!(!channel_enabled);
+    }
+
+    #[test]
+    fn clearing_negate_after_a_subtraction_stops_the_channel() {
+        let mut channel_enabled = true;
+        let mut sweep = Sweep::default();
+        sweep.write(0x19, &mut channel_enabled);
+        sweep.trigger(0x400, &mut channel_enabled);
+
+        sweep.write(0x11, &mut channel_enabled);
+        assert!(!channel_enabled);
+
+        // Without a subtraction since the trigger, the negate bit can be cleared
+        channel_enabled = true;
+        sweep.write(0x18, &mut channel_enabled);
+        sweep.trigger(0x400, &mut channel_enabled);
+        sweep.write(0x10, &mut channel_enabled);
+        assert!(channel_enabled);
+    }
+}
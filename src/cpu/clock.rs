@@ -3,26 +3,52 @@ use std::rc::Rc;
 use crate::cpu::interrupts::Interrupt;
 use crate::mmu::Mmu;
 
+#[derive(Clone)]
 pub struct Clock {
     mmu: Rc<RefCell<Mmu>>,
-    cycles: usize
+    cycles: usize,
+    // TIMA overflowed on the previous M-cycle and still has to be reloaded from TMA
+    tima_reload_pending: bool,
 }
 
 impl Clock {
     pub fn new(mmu: Rc<RefCell<Mmu>>) -> Self {
         Self {
             mmu,
-            cycles: 0
+            cycles: 0,
+            tima_reload_pending: false,
         }
     }
 
     pub fn update_clock_cycles(&mut self, count: u8) {
-        self.cycles += count as usize;
+        for _ in 0..count {
+            self.tick();
+        }
+    }
+
+    // Advance the timers by a single M-cycle
+    fn tick(&mut self) {
+        let mut mmu = self.mmu.borrow_mut();
+
+        // DIV keeps counting whether or not the timer is enabled
+        mmu.increment_div_counter(4);
+
+        // TIMA reads 0x00 for one M-cycle after overflowing, then gets TMA and the interrupt fires
+        if self.tima_reload_pending {
+            self.tima_reload_pending = false;
+
+            let tma = mmu.tma();
+            mmu.set_tima(tma);
 
-        let tac = self.mmu.borrow().tac();
+            mmu.request_interrupt(Interrupt::Timer);
+        }
+
+        let tac = mmu.tac();
         let clock_enable = tac & (1 << 2) != 0;
 
         if clock_enable {
+            self.cycles += 1;
+
             let clock_select = tac & 0b11;
 
             let cycle_increment = match clock_select {
@@ -35,18 +61,62 @@ impl Clock {
 
             if self.cycles >= cycle_increment {
                 self.cycles -= cycle_increment;
-                let tima = self.mmu.borrow().tima();
+                let tima = mmu.tima();
 
                 if tima == 0xFF {
-                    let tma = self.mmu.borrow().tma();
-                    self.mmu.borrow_mut().set_tima(tma);
-
-                    let iflag = self.mmu.borrow().iflag();
-                    self.mmu.borrow_mut().set_iflag(iflag | (1 << Interrupt::Timer as u8));
+                    mmu.set_tima(0x00);
+                    self.tima_reload_pending = true;
                 } else {
-                    self.mmu.borrow_mut().set_tima(tima + 1);
+                    mmu.set_tima(tima + 1);
                 }
             }
         };
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn div_increments_with_timer_disabled() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut clock = Clock::new(mmu.clone());
+        mmu.borrow_mut().set_tac(0x00);
+
+        // DIV ticks every 64 M-cycles
+        clock.update_clock_cycles(63);
+        assert_eq!(mmu.borrow().div(), 0);
+        clock.update_clock_cycles(1);
+        assert_eq!(mmu.borrow().div(), 1);
+        assert_eq!(mmu.borrow().tima(), 0);
+
+        // Writing DIV resets it regardless of the value
+        mmu.borrow_mut().set_div(0xAB);
+        assert_eq!(mmu.borrow().div(), 0);
+        assert_eq!(mmu.borrow().div_counter(), 0);
+    }
+
+    #[test]
+    fn tima_reload_is_delayed_by_one_cycle() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut clock = Clock::new(mmu.clone());
+        let timer_bit = 1 << Interrupt::Timer as u8;
+
+        {
+            let mut mmu = mmu.borrow_mut();
+            mmu.set_tac(0b101); // Enabled, increments every 4 M-cycles
+            mmu.set_tima(0xFF);
+            mmu.set_tma(0x42);
+            mmu.set_iflag(0x00);
+        }
+
+        clock.update_clock_cycles(4);
+        assert_eq!(mmu.borrow().tima(), 0x00);
+        assert_eq!(mmu.borrow().iflag() & timer_bit, 0);
+
+        clock.update_clock_cycles(1);
+        assert_eq!(mmu.borrow().tima(), 0x42);
+        assert_eq!(mmu.borrow().iflag() & timer_bit, timer_bit);
+    }
+}
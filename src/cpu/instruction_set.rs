@@ -4,11 +4,14 @@ use std::mem::MaybeUninit;
 use std::rc::Rc;
 use crate::bit_utils::{concatenate_bytes, half_carry_check_add_16bit, half_carry_check_add_8bit, half_carry_check_sub_8bit, split_2bytes};
 use crate::cpu::registers::{Register, Registers};
-use crate::mmu::{Mmu};
+use crate::mmu::{Mmu, MmuError};
 
-type NullaryOperation = Rc<dyn Fn(&mut Mmu, &mut Registers) -> u8>;
-type UnaryOperation = Rc<dyn Fn(&mut Mmu, &mut Registers, u8) -> u8>;
-type BinaryOperation = Rc<dyn Fn(&mut Mmu, &mut Registers, u8, u8) -> u8>;
+// Cycles taken by an operation, or the memory fault that stopped it
+pub type OperationResult = Result<u8, MmuError>;
+
+type NullaryOperation = Rc<dyn Fn(&mut Mmu, &mut Registers) -> OperationResult>;
+type UnaryOperation = Rc<dyn Fn(&mut Mmu, &mut Registers, u8) -> OperationResult>;
+type BinaryOperation = Rc<dyn Fn(&mut Mmu, &mut Registers, u8, u8) -> OperationResult>;
 
 #[derive(Default, Clone)]
 pub enum Operation {
@@ -34,6 +37,8 @@ pub struct DebugInstruction {
     pub first_operand: Option<u8>,
     pub second_operand: Option<u8>,
     pub name: String,
+    // Symbol for the instruction's own address
+    pub label: Option<String>,
 }
 
 impl fmt::Display for Instruction {
@@ -64,154 +69,154 @@ impl InstructionSet {
 
         instructions_8bit[0x00] = Instruction{ name: String::from("NOP"), opcode: 0x00, length: 1, cycles: 1, operation: Operation::None, };
         instructions_8bit[0x01] = Instruction{ name: String::from("LD BC, d16"), opcode: 0x01, length: 3, cycles: 3,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Self::ld_16bit(registers, Register::BC, lower_byte, higher_byte) })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Ok(Self::ld_16bit(registers, Register::BC, lower_byte, higher_byte)) })) };
         instructions_8bit[0x02] = Instruction{ name: String::from("LD (BC), A"), opcode: 0x02, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit_mem(mmu, registers.bc(), registers.a()) })) } ;
         instructions_8bit[0x03] = Instruction{ name: String::from("INC BC"), opcode: 0x03, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_16bit(registers, Register::BC ) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_16bit(registers, Register::BC )) })) };
         instructions_8bit[0x04] = Instruction{ name: String::from("INC B"), opcode: 0x04, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::B) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::B)) })) };
         instructions_8bit[0x05] = Instruction{ name: String::from("DEC B"), opcode: 0x05, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::B) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::B)) })) };
         instructions_8bit[0x06] = Instruction{ name: String::from("LD B, d8"), opcode: 0x06, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::B, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::B, value); Ok(2) })) } ;
         instructions_8bit[0x07] = Instruction{ name: String::from("RLCA"), opcode: 0x07, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.a_ref()); registers.set_zero_flag(false); 1 })) } ;
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.a_ref()))?; registers.set_zero_flag(false); Ok(1) })) } ;
         instructions_8bit[0x08] = Instruction{ name: String::from("LD (a16), SP"), opcode: 0x08, length: 3, cycles: 5,
             operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, upper_byte: u8| {
                 let (lower_sp, higher_sp) = split_2bytes(registers.sp());
                 let address = concatenate_bytes(lower_byte, upper_byte);
-                Self::ld_8bit_mem(mmu, address, lower_sp);
-                Self::ld_8bit_mem(mmu, address + 1, higher_sp);
+                Self::ld_8bit_mem(mmu, address, lower_sp)?;
+                Self::ld_8bit_mem(mmu, address + 1, higher_sp)?;
 
-                5
+                Ok(5)
             })) } ;
         instructions_8bit[0x09] = Instruction{ name: String::from("ADD HL, BC"), opcode: 0x09, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add_16bit(registers, Register::HL, registers.bc()) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add_16bit(registers, Register::HL, registers.bc())) })) } ;
         instructions_8bit[0x0A] = Instruction{ name: String::from("LD A, (BC)"), opcode: 0x0A, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.bc() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.bc() as usize)?); Ok(2) })) };
         instructions_8bit[0x0B] = Instruction{ name: String::from("DEC BC"), opcode: 0x0B, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::dec_16bit(registers, Register::BC) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(Self::dec_16bit(registers, Register::BC)) })) };
         instructions_8bit[0x0C] = Instruction{ name: String::from("INC C"), opcode: 0x0C, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::C) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::C)) })) };
         instructions_8bit[0x0D] = Instruction{ name: String::from("DEC C"), opcode: 0x0D, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::C) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::C)) })) };
         instructions_8bit[0x0E] = Instruction{ name: String::from("LD C, d8"), opcode: 0x0E, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::C, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::C, value); Ok(2) })) } ;
         instructions_8bit[0x0F] = Instruction{ name: String::from("RRCA"), opcode: 0x0F, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.a_ref()); registers.set_zero_flag(false); 1 })) } ;
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.a_ref()))?; registers.set_zero_flag(false); Ok(1) })) } ;
 
         instructions_8bit[0x10] = Instruction{ name: String::from("STOP"), opcode: 0x10, length: 2, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { 1 })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(1) })) } ;
         instructions_8bit[0x11] = Instruction{ name: String::from("LD DE, d16"), opcode: 0x11, length: 3, cycles: 3,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Self::ld_16bit(registers, Register::DE, lower_byte, higher_byte) })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Ok(Self::ld_16bit(registers, Register::DE, lower_byte, higher_byte)) })) };
         instructions_8bit[0x12] = Instruction{ name: String::from("LD (DE), A"), opcode: 0x12, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit_mem(mmu, registers.de(), registers.a()) })) } ;
         instructions_8bit[0x13] = Instruction{ name: String::from("INC DE"), opcode: 0x13, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_16bit(registers, Register::DE ) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_16bit(registers, Register::DE )) })) };
         instructions_8bit[0x14] = Instruction{ name: String::from("INC D"), opcode: 0x14, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::D) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::D)) })) };
         instructions_8bit[0x15] = Instruction{ name: String::from("DEC D"), opcode: 0x15, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::D) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::D)) })) };
         instructions_8bit[0x16] = Instruction{ name: String::from("LD D, d8"), opcode: 0x16, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::D, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::D, value); Ok(2) })) } ;
         instructions_8bit[0x17] = Instruction{ name: String::from("RLA"), opcode: 0x17, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.a_ref()); registers.set_zero_flag(false); 1 })) } ;
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.a_ref()))?; registers.set_zero_flag(false); Ok(1) })) } ;
         instructions_8bit[0x18] = Instruction{ name: String::from("JR s8"), opcode: 0x18, length: 2, cycles: 3,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::jr(registers, value) })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Ok(Self::jr(registers, value)) })) } ;
         instructions_8bit[0x19] = Instruction{ name: String::from("ADD HL, DE"), opcode: 0x19, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add_16bit(registers, Register::HL, registers.de()) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add_16bit(registers, Register::HL, registers.de())) })) } ;
         instructions_8bit[0x1A] = Instruction{ name: String::from("LD A, (DE)"), opcode: 0x1A, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.de() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.de() as usize)?); Ok(2) })) };
         instructions_8bit[0x1B] = Instruction{ name: String::from("DEC DE"), opcode: 0x1B, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::dec_16bit(registers, Register::DE) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(Self::dec_16bit(registers, Register::DE)) })) };
         instructions_8bit[0x1C] = Instruction{ name: String::from("INC E"), opcode: 0x1C, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::E) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::E)) })) };
         instructions_8bit[0x1D] = Instruction{ name: String::from("DEC E"), opcode: 0x1D, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::E) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::E)) })) };
         instructions_8bit[0x1E] = Instruction{ name: String::from("LD E, d8"), opcode: 0x1E, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::E, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::E, value); Ok(2) })) } ;
         instructions_8bit[0x1F] = Instruction{ name: String::from("RRA"), opcode: 0x1F, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.a_ref()); registers.set_zero_flag(false); 1 })) } ;
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.a_ref()))?; registers.set_zero_flag(false); Ok(1) })) } ;
 
         instructions_8bit[0x20] = Instruction{ name: String::from("JR NZ, s8"), opcode: 0x20, length: 2, cycles: 3, // TODO: Check the variable cycles implementation
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, steps: u8| { if registers.zero_flag() == false { Self::jr(registers, steps) } else { 2 } })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, steps: u8| { Ok(if !registers.zero_flag() { Self::jr(registers, steps) } else { 2 }) })) };
         instructions_8bit[0x21] = Instruction{ name: String::from("LD HL, d16"), opcode: 0x21, length: 3, cycles: 3,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Self::ld_16bit(registers, Register::HL, lower_byte, higher_byte) })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Ok(Self::ld_16bit(registers, Register::HL, lower_byte, higher_byte)) })) };
         instructions_8bit[0x22] = Instruction{ name: String::from("LD (HL+), A"), opcode: 0x22, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
-                Self::ld_8bit_mem(mmu, registers.hl(), registers.a());
-                registers.set_hl(registers.hl() + 1); 2 })) } ;
+                Self::ld_8bit_mem(mmu, registers.hl(), registers.a())?;
+                registers.set_hl(registers.hl().wrapping_add(1)); Ok(2) })) } ;
         instructions_8bit[0x23] = Instruction{ name: String::from("INC HL"), opcode: 0x23, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_16bit(registers, Register::HL ) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_16bit(registers, Register::HL )) })) };
         instructions_8bit[0x24] = Instruction{ name: String::from("INC H"), opcode: 0x24, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::H) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::H)) })) };
         instructions_8bit[0x25] = Instruction{ name: String::from("DEC H"), opcode: 0x25, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::H) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::H)) })) };
         instructions_8bit[0x26] = Instruction{ name: String::from("LD H, d8"), opcode: 0x26, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::H, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::H, value); Ok(2) })) } ;
         instructions_8bit[0x27] = Instruction{ name: String::from("DAA"), opcode: 0x27, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::daa(registers) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::daa(registers)) })) } ;
         instructions_8bit[0x28] = Instruction{ name: String::from("JR Z, s8"), opcode: 0x28, length: 2, cycles: 3,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { if registers.zero_flag() { Self::jr(registers, value) } else { 2 } })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Ok(if registers.zero_flag() { Self::jr(registers, value) } else { 2 }) })) } ;
         instructions_8bit[0x29] = Instruction{ name: String::from("ADD HL, HL"), opcode: 0x29, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add_16bit(registers, Register::HL, registers.hl()) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add_16bit(registers, Register::HL, registers.hl())) })) } ;
         instructions_8bit[0x2A] = Instruction{ name: String::from("LD A, (HL+)"), opcode: 0x2A, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
-                Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.hl() as usize).unwrap());
-                registers.set_hl(registers.hl() + 1); 2})) };
+                Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.hl() as usize)?);
+                registers.set_hl(registers.hl().wrapping_add(1)); Ok(2)})) };
         instructions_8bit[0x2B] = Instruction{ name: String::from("DEC HL"), opcode: 0x3B, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::dec_16bit(registers, Register::HL) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(Self::dec_16bit(registers, Register::HL)) })) };
         instructions_8bit[0x2C] = Instruction{ name: String::from("INC L"), opcode: 0x2C, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::L) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::L)) })) };
         instructions_8bit[0x2D] = Instruction{ name: String::from("DEC L"), opcode: 0x2D, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::L) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::L)) })) };
         instructions_8bit[0x2E] = Instruction{ name: String::from("LD L, d8"), opcode: 0x2E, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::L, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::L, value); Ok(2) })) } ;
         instructions_8bit[0x2F] = Instruction{ name: String::from("CPL"), opcode: 0x2F, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cpl(registers) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cpl(registers)) })) } ;
 
         instructions_8bit[0x30] = Instruction{ name: String::from("JR NC, s8"), opcode: 0x30, length: 2, cycles: 3, // TODO: Check the variable cycles implementation
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, steps: u8| { if registers.carry_flag() == false { Self::jr(registers, steps) } else { 3 } })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, steps: u8| { Ok(if !registers.carry_flag() { Self::jr(registers, steps) } else { 2 }) })) };
         instructions_8bit[0x31] = Instruction{ name: String::from("LD SP, d16"), opcode: 0x31, length: 3, cycles: 3,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Self::ld_16bit(registers, Register::SP, lower_byte, higher_byte) })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Ok(Self::ld_16bit(registers, Register::SP, lower_byte, higher_byte)) })) };
         instructions_8bit[0x32] = Instruction{ name: String::from("LD (HL-), A"), opcode: 0x32, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
-                Self::ld_8bit_mem(mmu, registers.hl(), registers.a());
-                registers.set_hl(registers.hl() - 1); 2 })) } ;
+                Self::ld_8bit_mem(mmu, registers.hl(), registers.a())?;
+                registers.set_hl(registers.hl().wrapping_sub(1)); Ok(2) })) } ;
         instructions_8bit[0x33] = Instruction{ name: String::from("INC SP"), opcode: 0x33, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_16bit(registers, Register::SP ) })) };
-        instructions_8bit[0x34] = Instruction{ name: String::from("INC (HL)"), opcode: 0x34, length: 1, cycles: 2,
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_16bit(registers, Register::SP )) })) };
+        instructions_8bit[0x34] = Instruction{ name: String::from("INC (HL)"), opcode: 0x34, length: 1, cycles: 3,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::inc_mem(registers, mmu, registers.hl() as usize) })) };
-        instructions_8bit[0x35] = Instruction{ name: String::from("DEC (HL)"), opcode: 0x35, length: 1, cycles: 2,
+        instructions_8bit[0x35] = Instruction{ name: String::from("DEC (HL)"), opcode: 0x35, length: 1, cycles: 3,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::dec_mem(registers, mmu, registers.hl() as usize) })) };
         instructions_8bit[0x36] = Instruction{ name: String::from("LD (HL), d8"), opcode: 0x36, length: 2, cycles: 3,
-            operation: Operation::Unary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, value: u8| { Self::ld_8bit_mem(mmu, registers.hl(), value); 3 })) } ;
+            operation: Operation::Unary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, value: u8| { Self::ld_8bit_mem(mmu, registers.hl(), value)?; Ok(3) })) } ;
         instructions_8bit[0x37] = Instruction{ name: String::from("SCF"), opcode: 0x37, length: 1, cycles: 1,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
                 registers.set_subtraction_flag(false);
                 registers.set_half_carry_flag(false);
                 registers.set_carry_flag(true);
-                1 }))
+                Ok(1) }))
         };
-        instructions_8bit[0x38] = Instruction{ name: String::from("JRCZ, s8"), opcode: 0x38, length: 2, cycles: 3,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { if registers.carry_flag() { Self::jr(registers, value) } else { 2 } })) } ;
+        instructions_8bit[0x38] = Instruction{ name: String::from("JR C, s8"), opcode: 0x38, length: 2, cycles: 3,
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Ok(if registers.carry_flag() { Self::jr(registers, value) } else { 2 }) })) } ;
         instructions_8bit[0x39] = Instruction{ name: String::from("ADD HL, SP"), opcode: 0x39, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add_16bit(registers, Register::HL, registers.sp()) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add_16bit(registers, Register::HL, registers.sp())) })) } ;
         instructions_8bit[0x3A] = Instruction{ name: String::from("LD A, (HL-)"), opcode: 0x3A, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
-                Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.hl() as usize).unwrap());
-                registers.set_hl(registers.hl() - 1); 2})) };
+                Self::ld_8bit(registers, Register::A, mmu.read_byte(registers.hl() as usize)?);
+                registers.set_hl(registers.hl().wrapping_sub(1)); Ok(2)})) };
         instructions_8bit[0x3B] = Instruction{ name: String::from("DEC SP"), opcode: 0x3B, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::dec_16bit(registers, Register::SP) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(Self::dec_16bit(registers, Register::SP)) })) };
         instructions_8bit[0x3C] = Instruction{ name: String::from("INC A"), opcode: 0x3C, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::inc_8bit(registers, Register::A) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::inc_8bit(registers, Register::A)) })) };
         instructions_8bit[0x3D] = Instruction{ name: String::from("DEC A"), opcode: 0x3D, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::dec_8bit(registers, Register::A) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::dec_8bit(registers, Register::A)) })) };
         instructions_8bit[0x3E] = Instruction{ name: String::from("LD A, d8"), opcode: 0x3E, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::A, value); 2 })) } ;
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::ld_8bit(registers, Register::A, value); Ok(2) })) } ;
         instructions_8bit[0x3F] = Instruction{ name: String::from("CCF"), opcode: 0x3F, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::ccf(registers) })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::ccf(registers)) })) } ;
 
 
         let source_regs: [(&str, fn(&Registers) -> u8); 8] = [
@@ -241,7 +246,7 @@ impl InstructionSet {
             for (j, (source_name, source_accessor)) in source_regs.iter().enumerate() {
                 let name = format!("LD {}, {}", destination_name, source_name);
                 let opcode = 0x40 + i as u8 * 8 + j as u8;
-                let cycles = if name == "(HL)" { 2 } else { 1 };
+                let cycles = if *source_name == "(HL)" || *destination_name == "(HL)" { 2 } else { 1 };
                 let source_accessor = *source_accessor;
                 let destination_accessor = *destination_accessor;
 
@@ -254,9 +259,9 @@ impl InstructionSet {
                     }
                 } else {
                     if *source_name == "(HL)" {
-                        Operation::Nullary(Rc::new(move |mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit(registers, destination_accessor, mmu.read_byte(registers.hl() as usize).unwrap()); 2 }))
+                        Operation::Nullary(Rc::new(move |mmu: &mut Mmu, registers: &mut Registers| { Self::ld_8bit(registers, destination_accessor, mmu.read_byte(registers.hl() as usize)?); Ok(2) }))
                     } else {
-                        Operation::Nullary(Rc::new(move |_, registers: &mut Registers| { Self::ld_8bit(registers, destination_accessor, source_accessor(registers)) }))
+                        Operation::Nullary(Rc::new(move |_, registers: &mut Registers| { Ok(Self::ld_8bit(registers, destination_accessor, source_accessor(registers))) }))
                     }
                 };
 
@@ -266,203 +271,203 @@ impl InstructionSet {
 
 
         instructions_8bit[0x76] = Instruction{ name: String::from("HALT"), opcode: 0x76, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { 1 })) } ;
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(1) })) } ;
 
         instructions_8bit[0x80] = Instruction{ name: String::from("ADD A, B"), opcode: 0x80, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0x81] = Instruction{ name: String::from("ADD A, C"), opcode: 0x81, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0x82] = Instruction{ name: String::from("ADD A, D"), opcode: 0x82, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0x83] = Instruction{ name: String::from("ADD A, E"), opcode: 0x83, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0x84] = Instruction{ name: String::from("ADD A, H"), opcode: 0x84, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0x85] = Instruction{ name: String::from("ADD A, L"), opcode: 0x85, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0x86] = Instruction{ name: String::from("ADD A, (HL)"), opcode: 0x86, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::add(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::add(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0x87] = Instruction{ name: String::from("ADD A, A"), opcode: 0x87, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::add(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::add(registers, registers.a(), registers.a())) })) };
         instructions_8bit[0x88] = Instruction{ name: String::from("ADC A, B"), opcode: 0x88, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0x89] = Instruction{ name: String::from("ADC A, C"), opcode: 0x89, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0x8A] = Instruction{ name: String::from("ADC A, D"), opcode: 0x8A, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0x8B] = Instruction{ name: String::from("ADC A, E"), opcode: 0x8B, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0x8C] = Instruction{ name: String::from("ADC A, H"), opcode: 0x8C, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0x8D] = Instruction{ name: String::from("ADC A, L"), opcode: 0x8D, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0x8E] = Instruction{ name: String::from("ADC A, (HL)"), opcode: 0x8E, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::adc(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::adc(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0x8F] = Instruction{ name: String::from("ADC A, A"), opcode: 0x8F, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::adc(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::adc(registers, registers.a(), registers.a())) })) };
 
         instructions_8bit[0x90] = Instruction{ name: String::from("SUB B"), opcode: 0x90, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0x91] = Instruction{ name: String::from("SUB C"), opcode: 0x91, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0x92] = Instruction{ name: String::from("SUB D"), opcode: 0x92, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0x93] = Instruction{ name: String::from("SUB E"), opcode: 0x93, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0x94] = Instruction{ name: String::from("SUB H"), opcode: 0x94, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0x95] = Instruction{ name: String::from("SUB L"), opcode: 0x95, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0x96] = Instruction{ name: String::from("SUB (HL)"), opcode: 0x96, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sub(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sub(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0x97] = Instruction{ name: String::from("SUB A"), opcode: 0x97, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sub(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sub(registers, registers.a(), registers.a())) })) };
         instructions_8bit[0x98] = Instruction{ name: String::from("SBC B"), opcode: 0x98, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0x99] = Instruction{ name: String::from("SBC C"), opcode: 0x99, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0x9A] = Instruction{ name: String::from("SBC D"), opcode: 0x9A, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0x9B] = Instruction{ name: String::from("SBC E"), opcode: 0x9B, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0x9C] = Instruction{ name: String::from("SBC H"), opcode: 0x9C, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0x9D] = Instruction{ name: String::from("SBC L"), opcode: 0x9D, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0x9E] = Instruction{ name: String::from("SBC (HL)"), opcode: 0x9E, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sbc(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sbc(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0x9F] = Instruction{ name: String::from("SBC A"), opcode: 0x9F, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::sbc(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::sbc(registers, registers.a(), registers.a())) })) };
 
         instructions_8bit[0xA0] = Instruction{ name: String::from("AND B"), opcode: 0xA0, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0xA1] = Instruction{ name: String::from("AND C"), opcode: 0xA1, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0xA2] = Instruction{ name: String::from("AND D"), opcode: 0xA2, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0xA3] = Instruction{ name: String::from("AND E"), opcode: 0xA3, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0xA4] = Instruction{ name: String::from("AND H"), opcode: 0xA4, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0xA5] = Instruction{ name: String::from("AND L"), opcode: 0xA5, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0xA6] = Instruction{ name: String::from("AND (HL)"), opcode: 0xA6, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::and(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::and(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0xA7] = Instruction{ name: String::from("AND A"), opcode: 0xA7, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::and(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::and(registers, registers.a(), registers.a())) })) };
         instructions_8bit[0xA8] = Instruction{ name: String::from("XOR B"), opcode: 0xA8, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0xA9] = Instruction{ name: String::from("XOR C"), opcode: 0xA9, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0xAA] = Instruction{ name: String::from("XOR D"), opcode: 0xAA, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0xAB] = Instruction{ name: String::from("XOR E"), opcode: 0xAB, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0xAC] = Instruction{ name: String::from("XOR H"), opcode: 0xAC, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0xAD] = Instruction{ name: String::from("XOR L"), opcode: 0xAD, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0xAE] = Instruction{ name: String::from("XOR (HL)"), opcode: 0xAE, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::xor(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::xor(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0xAF] = Instruction{ name: String::from("XOR A"), opcode: 0xAF, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::xor(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::xor(registers, registers.a(), registers.a())) })) };
 
         instructions_8bit[0xB0] = Instruction{ name: String::from("OR B"), opcode: 0xB0, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0xB1] = Instruction{ name: String::from("OR C"), opcode: 0xB1, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0xB2] = Instruction{ name: String::from("OR D"), opcode: 0xB2, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0xB3] = Instruction{ name: String::from("OR E"), opcode: 0xB3, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0xB4] = Instruction{ name: String::from("OR H"), opcode: 0xB4, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0xB5] = Instruction{ name: String::from("OR L"), opcode: 0xB5, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0xB6] = Instruction{ name: String::from("OR (HL)"), opcode: 0xB6, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::or(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::or(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0xB7] = Instruction{ name: String::from("OR A"), opcode: 0xB7, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::or(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::or(registers, registers.a(), registers.a())) })) };
         instructions_8bit[0xB8] = Instruction{ name: String::from("CP B"), opcode: 0xB8, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(), registers.b()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(), registers.b())) })) };
         instructions_8bit[0xB9] = Instruction{ name: String::from("CP C"), opcode: 0xB9, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(), registers.c()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(), registers.c())) })) };
         instructions_8bit[0xBA] = Instruction{ name: String::from("CP D"), opcode: 0xBA, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(), registers.d()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(), registers.d())) })) };
         instructions_8bit[0xBB] = Instruction{ name: String::from("CP E"), opcode: 0xBB, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(),  registers.e()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(),  registers.e())) })) };
         instructions_8bit[0xBC] = Instruction{ name: String::from("CP H"), opcode: 0xBC, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(), registers.h()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(), registers.h())) })) };
         instructions_8bit[0xBD] = Instruction{ name: String::from("CP L"), opcode: 0xBD, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(), registers.l()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(), registers.l())) })) };
         instructions_8bit[0xBE] = Instruction{ name: String::from("CP (HL)"), opcode: 0xBE, length: 1, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::cp(registers, registers.a(), mmu.read_byte(registers.hl() as usize).unwrap()); 2 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::cp(registers, registers.a(), mmu.read_byte(registers.hl() as usize)?); Ok(2) })) };
         instructions_8bit[0xBF] = Instruction{ name: String::from("CP A"), opcode: 0xBF, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Self::cp(registers, registers.a(), registers.a()) })) };
+            operation: Operation::Nullary(Rc::new(|_, registers: &mut Registers| { Ok(Self::cp(registers, registers.a(), registers.a())) })) };
 
         instructions_8bit[0xC0] = Instruction{ name: String::from("RET NZ"), opcode: 0xC0, length: 1, cycles: 5,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { if !registers.zero_flag() { Self::ret(mmu, registers); 5 } else { 2 } })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(if !registers.zero_flag() { Self::ret(mmu, registers)?; 5 } else { 2 }) })) };
         instructions_8bit[0xC1] = Instruction{ name: String::from("POP BC"), opcode: 0xC1, length: 1, cycles: 3,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::pop(mmu, registers, Register::BC) })) };
         instructions_8bit[0xC2] = Instruction{ name: String::from("JP NZ, a16"), opcode: 0xC2, length: 3, cycles: 4,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { if !registers.zero_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 } })) };
-        instructions_8bit[0xC3] = Instruction{ name: String::from("JP a16"), opcode: 0xC3, length: 3, cycles: 2,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Self::jmp(registers, lower_bits, upper_bits) })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(if !registers.zero_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 }) })) };
+        instructions_8bit[0xC3] = Instruction{ name: String::from("JP a16"), opcode: 0xC3, length: 3, cycles: 4,
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(Self::jmp(registers, lower_bits, upper_bits)) })) };
         instructions_8bit[0xC4] = Instruction{ name: String::from("CALL NZ, a16"), opcode: 0xC4, length: 3, cycles: 6,
-            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { if !registers.zero_flag() { Self::call(mmu, registers, lower_bits, upper_bits) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(if !registers.zero_flag() { Self::call(mmu, registers, lower_bits, upper_bits)? } else { 3 }) })) };
         instructions_8bit[0xC5] = Instruction{ name: String::from("PUSH BC"), opcode: 0xC5, length: 1, cycles: 4,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::push(mmu, registers, registers.bc()) })) };
         instructions_8bit[0xC6] = Instruction{ name: String::from("ADD A, d8"), opcode: 0xC6, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::add(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::add(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xC7] = Instruction{ name: String::from("RST 0"), opcode: 0xC7, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0, 0); 4 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0, 0)?; Ok(4) })) };
         instructions_8bit[0xC8] = Instruction{ name: String::from("RET Z"), opcode: 0xC8, length: 1, cycles: 5,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { if registers.zero_flag() { Self::ret(mmu, registers); 5 } else { 2 } })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(if registers.zero_flag() { Self::ret(mmu, registers)?; 5 } else { 2 }) })) };
         instructions_8bit[0xC9] = Instruction{ name: String::from("RET"), opcode: 0xC9, length: 1, cycles: 4,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::ret(mmu, registers) })) };
         instructions_8bit[0xCA] = Instruction{ name: String::from("JP Z, a16"), opcode: 0xCA, length: 3, cycles: 4,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { if registers.zero_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(if registers.zero_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 }) })) };
         instructions_8bit[0xCC] = Instruction{ name: String::from("CALL Z, a16"), opcode: 0xCC, length: 3, cycles: 6,
-            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { if registers.zero_flag() { Self::call(mmu, registers, lower_byte, higher_byte) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Ok(if registers.zero_flag() { Self::call(mmu, registers, lower_byte, higher_byte)? } else { 3 }) })) };
         instructions_8bit[0xCD] = Instruction{ name: String::from("CALL a16"), opcode: 0xCD, length: 3, cycles: 6,
             operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Self::call(mmu, registers, lower_byte, higher_byte) })) };
         instructions_8bit[0xCE] = Instruction{ name: String::from("ADC A, d8"), opcode: 0xCE, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::adc(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::adc(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xCF] = Instruction{ name: String::from("RST 1"), opcode: 0xCF, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x08, 0); 4 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x08, 0)?; Ok(4) })) };
 
         instructions_8bit[0xD0] = Instruction{ name: String::from("RET NC"), opcode: 0xD0, length: 1, cycles: 5,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { if !registers.carry_flag() { Self::ret(mmu, registers); 5 } else { 2 } })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(if !registers.carry_flag() { Self::ret(mmu, registers)?; 5 } else { 2 }) })) };
         instructions_8bit[0xD1] = Instruction{ name: String::from("POP DE"), opcode: 0xD1, length: 1, cycles: 3,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::pop(mmu, registers, Register::DE) })) };
         instructions_8bit[0xD2] = Instruction{ name: String::from("JP NC, a16"), opcode: 0xD2, length: 3, cycles: 4,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { if !registers.carry_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(if !registers.carry_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 }) })) };
         instructions_8bit[0xD4] = Instruction{ name: String::from("CALL NC, a16"), opcode: 0xD4, length: 3, cycles: 6,
-            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { if !registers.carry_flag() { Self::call(mmu, registers, lower_bits, upper_bits) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(if !registers.carry_flag() { Self::call(mmu, registers, lower_bits, upper_bits)? } else { 3 }) })) };
         instructions_8bit[0xD5] = Instruction{ name: String::from("PUSH DE"), opcode: 0xD5, length: 1, cycles: 4,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::push(mmu, registers, registers.de()) })) };
         instructions_8bit[0xD6] = Instruction{ name: String::from("SUB A, d8"), opcode: 0xD6, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::sub(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::sub(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xD7] = Instruction{ name: String::from("RST 2"), opcode: 0xD7, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x10, 0); 4 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x10, 0)?; Ok(4) })) };
         instructions_8bit[0xD8] = Instruction{ name: String::from("RET C"), opcode: 0xD8, length: 1, cycles: 5,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { if registers.carry_flag() { Self::ret(mmu, registers); 5 } else { 2 } })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(if registers.carry_flag() { Self::ret(mmu, registers)?; 5 } else { 2 }) })) };
         instructions_8bit[0xD9] = Instruction{ name: String::from("RETI"), opcode: 0xD9, length: 1, cycles: 4,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::reti(mmu, registers) })) } ;
         instructions_8bit[0xDA] = Instruction{ name: String::from("JP C, a16"), opcode: 0xDA, length: 3, cycles: 4,
-            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { if registers.carry_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|_, registers: &mut Registers, lower_bits: u8, upper_bits: u8| { Ok(if registers.carry_flag() { Self::jmp(registers, lower_bits, upper_bits) } else { 3 }) })) };
         instructions_8bit[0xDC] = Instruction{ name: String::from("CALL C, a16"), opcode: 0xDC, length: 3, cycles: 6,
-            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { if registers.carry_flag() { Self::call(mmu, registers, lower_byte, higher_byte) } else { 3 } })) };
+            operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| { Ok(if registers.carry_flag() { Self::call(mmu, registers, lower_byte, higher_byte)? } else { 3 }) })) };
         instructions_8bit[0xDE] = Instruction{ name: String::from("SBC A, d8"), opcode: 0xDE, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::sbc(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::sbc(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xDF] = Instruction{ name: String::from("RST 3"), opcode: 0xDF, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x18, 0); 4 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x18, 0)?; Ok(4) })) };
 
-        instructions_8bit[0xE0] = Instruction{ name: String::from("LD (a8), A"), opcode: 0xE0, length: 2, cycles: 4,
+        instructions_8bit[0xE0] = Instruction{ name: String::from("LD (a8), A"), opcode: 0xE0, length: 2, cycles: 3,
             operation: Operation::Unary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8| {
                 let address = concatenate_bytes(lower_byte, 0xFF);
-                Self::ld_8bit_mem(mmu, address, registers.a());
-                3 }))
+                Self::ld_8bit_mem(mmu, address, registers.a())?;
+                Ok(3) }))
         };
         instructions_8bit[0xE1] = Instruction{ name: String::from("POP HL"), opcode: 0xE1, length: 1, cycles: 3,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::pop(mmu, registers, Register::HL) })) };
@@ -474,29 +479,29 @@ impl InstructionSet {
         instructions_8bit[0xE5] = Instruction{ name: String::from("PUSH HL"), opcode: 0xE5, length: 1, cycles: 4,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::push(mmu, registers, registers.hl()) })) };
         instructions_8bit[0xE6] = Instruction{ name: String::from("AND A, d8"), opcode: 0xE6, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::and(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::and(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xE7] = Instruction{ name: String::from("RST 4"), opcode: 0xE7, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x20, 0); 4 })) };
-        instructions_8bit[0xE8] = Instruction{ name: String::from("ADD SP, s8"), opcode: 0xE8, length: 1, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::add_8bit_signed(registers, Register::SP, value as i8) })) } ;
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x20, 0)?; Ok(4) })) };
+        instructions_8bit[0xE8] = Instruction{ name: String::from("ADD SP, s8"), opcode: 0xE8, length: 2, cycles: 4,
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Ok(Self::add_8bit_signed(registers, Register::SP, value as i8)) })) } ;
         instructions_8bit[0xE9] = Instruction{ name: String::from("JP HL"), opcode: 0xE9, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::jmp_reg(registers, registers.hl()) })) } ;
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(Self::jmp_reg(registers, registers.hl())) })) } ;
         instructions_8bit[0xEA] = Instruction{ name: String::from("LD (a16), A"), opcode: 0xEA, length: 3, cycles: 4,
             operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| {
                 let address = concatenate_bytes(lower_byte, higher_byte);
-                Self::ld_8bit_mem(mmu, address, registers.a());
-                4 }))
+                Self::ld_8bit_mem(mmu, address, registers.a())?;
+                Ok(4) }))
         };
         instructions_8bit[0xEE] = Instruction{ name: String::from("XOR A, d8"), opcode: 0xEE, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::xor(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::xor(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xEF] = Instruction{ name: String::from("RST 5"), opcode: 0xEF, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x28, 0); 4 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x28, 0)?; Ok(4) })) };
 
         instructions_8bit[0xF0] = Instruction{ name: String::from("LD A, (a8)"), opcode: 0xF0, length: 2, cycles: 3,
             operation: Operation::Unary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8| {
                 let address = concatenate_bytes(lower_byte, 0xFF) as usize;
-                Self::ld_8bit(registers, Register::A, mmu.read_byte(address).unwrap());
-                3
+                Self::ld_8bit(registers, Register::A, mmu.read_byte(address)?);
+                Ok(3)
             }))
         };
         instructions_8bit[0xF1] = Instruction{ name: String::from("POP AF"), opcode: 0xF1, length: 1, cycles: 3,
@@ -504,53 +509,45 @@ impl InstructionSet {
         instructions_8bit[0xF2] = Instruction{ name: String::from("LD A, (C)"), opcode: 0xF2, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
                 let address = concatenate_bytes(registers.c(), 0xFF);
-                Self::ld_8bit(registers, Register::A, mmu.read_byte(address as usize).unwrap()) }))
+                Self::ld_8bit(registers, Register::A, mmu.read_byte(address as usize)?);
+                Ok(2) }))
         };
         instructions_8bit[0xF3] = Instruction{ name: String::from("DI"), opcode: 0xF3, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { registers.set_ime(false); 1 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { registers.set_ime(false); Ok(1) })) };
         instructions_8bit[0xF5] = Instruction{ name: String::from("PUSH AF"), opcode: 0xF5, length: 1, cycles: 4,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::push(mmu, registers, registers.af()) })) };
         instructions_8bit[0xF6] = Instruction{ name: String::from("OR A, d8"), opcode: 0xF6, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::or(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::or(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xF7] = Instruction{ name: String::from("RST 6"), opcode: 0xF7, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x30, 0); 4})) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x30, 0)?; Ok(4)})) };
         instructions_8bit[0xF8] = Instruction{ name: String::from("LD HL, SP+s8"), opcode: 0xF8, length: 2, cycles: 3,
             operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| {
-                let sp = registers.sp();
-                let offset = value as i8 as i16;
-                let result = (sp as i16).wrapping_add(offset) as u16;
+                let result = Self::signed_offset(registers, registers.sp(), value as i8);
 
-                // store result into HL
                 let (low, high) = split_2bytes(result);
                 Self::ld_16bit(registers, Register::HL, low, high);
 
-                // set flags
-                registers.set_zero_flag(false);
-                registers.set_subtraction_flag(false);
-                registers.set_half_carry_flag(((sp ^ (offset as u16) ^ result) & 0x10) != 0);
-                registers.set_carry_flag(((sp ^ (offset as u16) ^ result) & 0x100) != 0);
-
-                3
+                Ok(3)
             })) };
         instructions_8bit[0xF9] = Instruction{ name: String::from("LD SP, HL"), opcode: 0xF9, length: 1, cycles: 2,
             operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| {
                 let (lower_byte, upper_byte) = split_2bytes(registers.hl());
                 Self::ld_16bit(registers, Register::SP, lower_byte, upper_byte);
-                2
+                Ok(2)
             })) } ;
         instructions_8bit[0xFA] = Instruction{ name: String::from("LD A, (a16)"), opcode: 0xFA, length: 3, cycles: 4,
             operation: Operation::Binary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8| {
                 let address = concatenate_bytes(lower_byte, higher_byte) as usize;
-                Self::ld_8bit(registers, Register::A, mmu.read_byte(address).unwrap());
-                4
+                Self::ld_8bit(registers, Register::A, mmu.read_byte(address)?);
+                Ok(4)
             }))
         };
         instructions_8bit[0xFB] = Instruction{ name: String::from("EI"), opcode: 0xFB, length: 1, cycles: 1,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { 1 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Ok(1) })) };
         instructions_8bit[0xFE] = Instruction{ name: String::from("CP d8"), opcode: 0xF8, length: 2, cycles: 2,
-            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::cp(registers, registers.a(), value); 2 })) };
+            operation: Operation::Unary(Rc::new(|_, registers: &mut Registers, value: u8| { Self::cp(registers, registers.a(), value); Ok(2) })) };
         instructions_8bit[0xFF] = Instruction{ name: String::from("RST 7"), opcode: 0xFF, length: 1, cycles: 4,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x38, 0); 4 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::call(mmu, registers, 0x38, 0)?; Ok(4) })) };
 
         // endregion
 
@@ -559,155 +556,155 @@ impl InstructionSet {
         let mut instructions_16bit: [Instruction; 256] = unsafe { mem::transmute(instructions_16bit) };
 
         instructions_16bit[0x00] = Instruction{ name: String::from("RLC B"), opcode: 0x00, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x01] = Instruction{ name: String::from("RLC C"), opcode: 0x01, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x02] = Instruction{ name: String::from("RLC D"), opcode: 0x02, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x03] = Instruction{ name: String::from("RLC E"), opcode: 0x03, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x04] = Instruction{ name: String::from("RLC H"), opcode: 0x04, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x05] = Instruction{ name: String::from("RLC L"), opcode: 0x05, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x06] = Instruction{ name: String::from("RLC (HL)"), opcode: 0x06, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x06] = Instruction{ name: String::from("RLC (HL)"), opcode: 0x06, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x07] = Instruction{ name: String::from("RLC A"), opcode: 0x07, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rlc(mmu, registers, |_, r| Ok(r.a_ref())) })) };
         instructions_16bit[0x08] = Instruction{ name: String::from("RRC B"), opcode: 0x08, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x09] = Instruction{ name: String::from("RRC C"), opcode: 0x09, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x0A] = Instruction{ name: String::from("RRC D"), opcode: 0x0A, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x0B] = Instruction{ name: String::from("RRC E"), opcode: 0x0B, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x0C] = Instruction{ name: String::from("RRC H"), opcode: 0x0C, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x0D] = Instruction{ name: String::from("RRC L"), opcode: 0x0D, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x0E] = Instruction{ name: String::from("RRC (HL)"), opcode: 0x0E, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x0E] = Instruction{ name: String::from("RRC (HL)"), opcode: 0x0E, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x0F] = Instruction{ name: String::from("RRC A"), opcode: 0x0F, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rrc(mmu, registers, |_, r| Ok(r.a_ref())) })) };
 
         instructions_16bit[0x10] = Instruction{ name: String::from("RL B"), opcode: 0x10, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x11] = Instruction{ name: String::from("RL C"), opcode: 0x11, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x12] = Instruction{ name: String::from("RL D"), opcode: 0x12, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x13] = Instruction{ name: String::from("RL E"), opcode: 0x13, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x14] = Instruction{ name: String::from("RL H"), opcode: 0x14, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x15] = Instruction{ name: String::from("RL L"), opcode: 0x15, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x16] = Instruction{ name: String::from("RL (HL)"), opcode: 0x16, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x16] = Instruction{ name: String::from("RL (HL)"), opcode: 0x16, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x17] = Instruction{ name: String::from("RL A"), opcode: 0x17, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rl(mmu, registers, |_, r| Ok(r.a_ref())) })) };
         instructions_16bit[0x18] = Instruction{ name: String::from("RR B"), opcode: 0x18, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x19] = Instruction{ name: String::from("RR C"), opcode: 0x19, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x1A] = Instruction{ name: String::from("RR D"), opcode: 0x1A, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x1B] = Instruction{ name: String::from("RR E"), opcode: 0x1B, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x1C] = Instruction{ name: String::from("RR H"), opcode: 0x1C, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x1D] = Instruction{ name: String::from("RR L"), opcode: 0x1D, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x1E] = Instruction{ name: String::from("RR (HL)"), opcode: 0x1E, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x1E] = Instruction{ name: String::from("RR (HL)"), opcode: 0x1E, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x1F] = Instruction{ name: String::from("RR A"), opcode: 0x1F, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::rr(mmu, registers, |_, r| Ok(r.a_ref())) })) };
 
         instructions_16bit[0x20] = Instruction{ name: String::from("SLA B"), opcode: 0x20, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x21] = Instruction{ name: String::from("SLA C"), opcode: 0x21, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x22] = Instruction{ name: String::from("SLA D"), opcode: 0x22, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x23] = Instruction{ name: String::from("SLA E"), opcode: 0x23, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x24] = Instruction{ name: String::from("SLA H"), opcode: 0x24, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x25] = Instruction{ name: String::from("SLA L"), opcode: 0x25, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x26] = Instruction{ name: String::from("SLA (HL)"), opcode: 0x26, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()); 3 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x26] = Instruction{ name: String::from("SLA (HL)"), opcode: 0x26, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x27] = Instruction{ name: String::from("SLA A"), opcode: 0x27, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sl(mmu, registers, |_, r| Ok(r.a_ref())) })) };
         instructions_16bit[0x28] = Instruction{ name: String::from("SRA B"), opcode: 0x28, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x29] = Instruction{ name: String::from("SRA C"), opcode: 0x29, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x2A] = Instruction{ name: String::from("SRA D"), opcode: 0x2A, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x2B] = Instruction{ name: String::from("SRA E"), opcode: 0x2B, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x2C] = Instruction{ name: String::from("SRA H"), opcode: 0x2C, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x2D] = Instruction{ name: String::from("SRA L"), opcode: 0x2D, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x2E] = Instruction{ name: String::from("SRA (HL)"), opcode: 0x2E, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()); 3 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x2E] = Instruction{ name: String::from("SRA (HL)"), opcode: 0x2E, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x2F] = Instruction{ name: String::from("SRA A"), opcode: 0x2F, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::sr(mmu, registers, |_, r| Ok(r.a_ref())) })) };
 
         instructions_16bit[0x30] = Instruction{ name: String::from("SWAP B"), opcode: 0x30, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x31] = Instruction{ name: String::from("SWAP C"), opcode: 0x31, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x32] = Instruction{ name: String::from("SWAP D"), opcode: 0x32, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers,|_, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers,|_, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x33] = Instruction{ name: String::from("SWAP E"), opcode: 0x33, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x34] = Instruction{ name: String::from("SWAP H"), opcode: 0x34, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x35] = Instruction{ name: String::from("SWAP L"), opcode: 0x35, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| r.l_ref()) })) };
-        instructions_16bit[0x36] = Instruction{ name: String::from("SWAP (HL)"), opcode: 0x36, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize).unwrap()); 3 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x36] = Instruction{ name: String::from("SWAP (HL)"), opcode: 0x36, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |m, r| m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x37] = Instruction{ name: String::from("SWAP A"), opcode: 0x37, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::swap(mmu, registers, |_, r| Ok(r.a_ref())) })) };
         instructions_16bit[0x38] = Instruction{ name: String::from("SRL B"), opcode: 0x38, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.b_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.b_ref())) })) };
         instructions_16bit[0x39] = Instruction{ name: String::from("SRL C"), opcode: 0x39, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.c_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.c_ref())) })) };
         instructions_16bit[0x3A] = Instruction{ name: String::from("SRL D"), opcode: 0x3A, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.d_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.d_ref())) })) };
         instructions_16bit[0x3B] = Instruction{ name: String::from("SRL E"), opcode: 0x3B, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.e_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.e_ref())) })) };
         instructions_16bit[0x3C] = Instruction{ name: String::from("SRL H"), opcode: 0x3C, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.h_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.h_ref())) })) };
         instructions_16bit[0x3D] = Instruction{ name: String::from("SRL L"), opcode: 0x3D, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.l_ref()) })) };
-        instructions_16bit[0x3E] = Instruction{ name: String::from("SRL (HL)"), opcode: 0x3E, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r|  m.get_byte_ref(r.hl() as usize).unwrap()); 3 })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.l_ref())) })) };
+        instructions_16bit[0x3E] = Instruction{ name: String::from("SRL (HL)"), opcode: 0x3E, length: 2, cycles: 4,
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r|  m.get_byte_ref(r.hl() as usize))?; Ok(4) })) };
         instructions_16bit[0x3F] = Instruction{ name: String::from("SRL A"), opcode: 0x3F, length: 2, cycles: 2,
-            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| r.a_ref()) })) };
+            operation: Operation::Nullary(Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { Self::srl(mmu, registers, |m, r| Ok(r.a_ref())) })) };
 
         // BIT (0x40-0x7F)
         for bit in 0..=7 {
             for (i, (destination_name, accessor)) in source_regs.iter().enumerate() {
                 let name = format!("BIT {}, {}", bit, destination_name);
                 let opcode = 0x40 + bit * 8 + i as u8;
-                let cycles = if name == "(HL)" { 3 } else { 2 };
+                let cycles = if *destination_name == "(HL)" { 3 } else { 2 };
                 let accessor = *accessor;
                 let operation = if *destination_name == "(HL)" {
                     Operation::Nullary(Rc::new(move |mmu: &mut Mmu, registers: &mut Registers| {
                         let bit = bit;
-                        Self::bit(registers, mmu.read_byte(registers.hl() as usize).unwrap(), bit);
+                        Self::bit(registers, mmu.read_byte(registers.hl() as usize)?, bit);
 
-                        3
+                        Ok(3)
                     }))
                 } else {
                     Operation::Nullary(Rc::new(move |_, registers: &mut Registers| {
                         let bit = bit;
-                        Self::bit(registers, accessor(registers), bit)
+                        Ok(Self::bit(registers, accessor(registers), bit))
                     }))
                 };
 
@@ -731,19 +728,19 @@ impl InstructionSet {
             for (i, (destination_name, accessor)) in destination_regs.iter().enumerate() {
                 let name = format!("RES {}, {}", bit, destination_name);
                 let opcode = 0x80 + bit * 8 + i as u8;
-                let cycles = if name == "(HL)" { 4 } else { 2 };
+                let cycles = if *destination_name == "(HL)" { 4 } else { 2 };
                 let accessor = *accessor;
                 let operation = if *destination_name == "(HL)" {
                     Operation::Nullary(Rc::new(move |mmu: &mut Mmu, registers: &mut Registers| {
                         let bit = bit;
-                        Self::res(mmu.get_byte_ref(registers.hl() as usize).unwrap(), bit);
+                        Self::res(mmu.get_byte_ref(registers.hl() as usize)?, bit);
 
-                        4
+                        Ok(4)
                     }))
                 } else {
                     Operation::Nullary(Rc::new(move |_, registers: &mut Registers| {
                         let bit = bit;
-                        Self::res(accessor(registers), bit)
+                        Ok(Self::res(accessor(registers), bit))
                     }))
                 };
 
@@ -756,19 +753,19 @@ impl InstructionSet {
             for (i, (destination_name, accessor)) in destination_regs.iter().enumerate() {
                 let name = format!("SET {}, {}", bit, destination_name);
                 let opcode = 0xC0 + bit * 8 + i as u8;
-                let cycles = if name == "(HL)" { 4 } else { 2 };
+                let cycles = if *destination_name == "(HL)" { 4 } else { 2 };
                 let accessor = *accessor;
                 let operation = if *destination_name == "(HL)" {
                     Operation::Nullary(Rc::new(move |mmu: &mut Mmu, registers: &mut Registers| {
                         let bit = bit;
-                        Self::set(mmu.get_byte_ref(registers.hl() as usize).unwrap(), bit);
+                        Self::set(mmu.get_byte_ref(registers.hl() as usize)?, bit);
 
-                        4
+                        Ok(4)
                     }))
                 } else {
                     Operation::Nullary(Rc::new(move |_, registers: &mut Registers| {
                         let bit = bit;
-                        Self::set(accessor(registers), bit)
+                        Ok(Self::set(accessor(registers), bit))
                     }))
                 };
 
@@ -781,12 +778,12 @@ impl InstructionSet {
         InstructionSet { instructions_8bit, instructions_16bit, mmu }
     }
 
-    pub fn fetch_instruction(&self, opcode: u8) -> Instruction {
-        self.instructions_8bit[opcode as usize].clone()
+    pub fn fetch_instruction(&self, opcode: u8) -> &Instruction {
+        &self.instructions_8bit[opcode as usize]
     }
 
-    pub fn fetch_instruction_16bit(&self, opcode: u8) -> Instruction {
-        self.instructions_16bit[opcode as usize].clone()
+    pub fn fetch_instruction_16bit(&self, opcode: u8) -> &Instruction {
+        &self.instructions_16bit[opcode as usize]
     }
 
     // Increment the contents of a register by 1
@@ -829,16 +826,17 @@ impl InstructionSet {
 
     // Increment the content of a memory address by 1
     // Flags: Z 0 8-bit -
-    fn inc_mem(registers: &mut Registers, mmu: &mut Mmu, address: usize) -> u8 {
-        let original_value = mmu.read_byte(address).unwrap();
+    fn inc_mem(registers: &mut Registers, mmu: &mut Mmu, address: usize) -> OperationResult {
+        let value = mmu.get_byte_ref(address)?;
+        let original_value = *value;
         let new_value = original_value + 1;
-        mmu.write_byte(address, new_value).unwrap();
+        *value = new_value;
 
         registers.set_zero_flag(new_value == 0);
         registers.set_subtraction_flag(false);
         registers.set_half_carry_flag(half_carry_check_add_8bit(original_value, 1));
 
-        2
+        Ok(3)
     }
 
     // Decrements the contents of a register pair by 1
@@ -853,16 +851,17 @@ impl InstructionSet {
 
     // Increment the content of a memory address by 1
     // Flags: Z 0 8-bit -
-    fn dec_mem(registers: &mut Registers, mmu: &mut Mmu, address: usize) -> u8 {
-        let original_value = mmu.read_byte(address).unwrap();
+    fn dec_mem(registers: &mut Registers, mmu: &mut Mmu, address: usize) -> OperationResult {
+        let value = mmu.get_byte_ref(address)?;
+        let original_value = *value;
         let new_value = original_value - 1;
-        mmu.write_byte(address, new_value).unwrap();
+        *value = new_value;
 
         registers.set_zero_flag(new_value == 0);
         registers.set_subtraction_flag(true);
         registers.set_half_carry_flag(half_carry_check_sub_8bit(original_value, 1));
 
-        2
+        Ok(3)
     }
 
     // Loads the value of address in the program counter
@@ -905,39 +904,41 @@ impl InstructionSet {
     }
 
     // Add an 8 bit signed value to a register and store the result in that register
-    // Flags: 0 0 16-bit 16-bit
+    // Flags: 0 0 8-bit 8-bit
     fn add_8bit_signed(registers: &mut Registers, register: Register, value: i8) -> u8 {
-        let sp = registers.get_16bit_register(register);
-        let offset = value as i16;
-        let result = (sp as i16).wrapping_add(offset) as u16;
-
+        let result = Self::signed_offset(registers, registers.get_16bit_register(register), value);
         registers.set_16bit_register(register, result);
 
-        registers.set_zero_flag(false); // always cleared
-        registers.set_subtraction_flag(false);
+        4
+    }
 
-        let sp_low = sp & 0xFF;
-        let result_low = (sp_low as i16).wrapping_add(offset) as u16;
+    // Add an 8 bit signed offset to a 16 bit value, the flags come from the unsigned addition of the low bytes
+    // Flags: 0 0 8-bit 8-bit
+    fn signed_offset(registers: &mut Registers, base: u16, value: i8) -> u16 {
+        let result = base.wrapping_add(value as i16 as u16);
+        let base_low = base as u8;
+        let offset = value as u8;
 
-        registers.set_half_carry_flag(((sp_low ^ (offset as u16) ^ result_low) & 0x10) != 0);
-        registers.set_carry_flag(((sp_low ^ (offset as u16) ^ result_low) & 0x100) != 0);
+        registers.set_zero_flag(false); // always cleared
+        registers.set_subtraction_flag(false);
+        registers.set_half_carry_flag(half_carry_check_add_8bit(base_low, offset));
+        registers.set_carry_flag((base_low as u16 + offset as u16) > 0xFF);
 
-        4
+        result
     }
 
     // Add two values along with the carry flag and store the content in register A
     // Flags: Z 0 8-bit 8-bit
     fn adc(registers: &mut Registers, left_operator: u8, right_operator: u8) -> u8 {
-        let sum = left_operator + right_operator + registers.carry_flag() as u8;
+        let carry = registers.carry_flag() as u8;
+        let sum = left_operator.wrapping_add(right_operator).wrapping_add(carry);
 
         registers.set_8bit_register(Register::A, sum);
 
         registers.set_zero_flag(sum == 0);
         registers.set_subtraction_flag(false);
-        registers.set_half_carry_flag(
-            ((left_operator & 0xF) + (right_operator & 0xF) + registers.carry_flag() as u8) > 0xF
-        );
-        registers.set_carry_flag((left_operator as u16 + right_operator as u16 + registers.carry_flag() as u16) > 0xFF);
+        registers.set_half_carry_flag(((left_operator & 0xF) + (right_operator & 0xF) + carry) > 0xF);
+        registers.set_carry_flag((left_operator as u16 + right_operator as u16 + carry as u16) > 0xFF);
 
         1
     }
@@ -1038,10 +1039,10 @@ impl InstructionSet {
 
     // Load the value at the given address
     // Flags: - - - -
-    fn ld_8bit_mem(mmu: &mut Mmu, address: u16, value: u8) -> u8 {
-        mmu.write_byte(address as usize, value).unwrap();
+    fn ld_8bit_mem(mmu: &mut Mmu, address: u16, value: u8) -> OperationResult {
+        mmu.write_byte(address as usize, value)?;
 
-        2
+        Ok(2)
     }
 
     // Load the 2 bytes of immediate data in the given register
@@ -1054,18 +1055,13 @@ impl InstructionSet {
 
     // Unconditional function call to the absolute address specified by the 16-bit operand nn
     // Flags: - - - -
-    fn call(mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8) -> u8 {
+    fn call(mmu: &mut Mmu, registers: &mut Registers, lower_byte: u8, higher_byte: u8) -> OperationResult {
         let jump_address = concatenate_bytes(lower_byte, higher_byte);
-        let (return_lower, return_higher) = split_2bytes(registers.pc());
-
-        registers.decrement_sp();
-        mmu.write_byte(registers.sp() as usize, return_higher).unwrap();
-        registers.decrement_sp();
-        mmu.write_byte(registers.sp() as usize, return_lower).unwrap();
 
+        Self::push(mmu, registers, registers.pc())?;
         registers.set_pc(jump_address);
 
-        6
+        Ok(6)
     }
 
     // Jump n steps from the current pc
@@ -1085,57 +1081,58 @@ impl InstructionSet {
         1
     }
 
-    // Push a value on the stack
+    // Push a value on the stack, the higher byte ends up at the higher address
     // Flags: - - - -
-    fn push(mmu: &mut Mmu, registers: &mut Registers, value: u16) -> u8 {
+    pub(crate) fn push(mmu: &mut Mmu, registers: &mut Registers, value: u16) -> OperationResult {
         let (value_lower, value_higher) = split_2bytes(value);
 
         registers.decrement_sp();
-        mmu.write_byte(registers.sp() as usize, value_higher).unwrap();
+        mmu.write_byte(registers.sp() as usize, value_higher)?;
         registers.decrement_sp();
-        mmu.write_byte(registers.sp() as usize, value_lower).unwrap();
+        mmu.write_byte(registers.sp() as usize, value_lower)?;
 
-        4
+        Ok(4)
     }
 
-    // Pop a value from the stack and store it in the given register
-    // Flags: - - - -
-    fn pop(mmu: &mut Mmu, registers: &mut Registers, register: Register) -> u8 {
-        let value_lower = mmu.read_byte(registers.sp() as usize).unwrap();
+    // Pop a 16-bit value from the stack, mirroring push
+    fn pop_16bit(mmu: &mut Mmu, registers: &mut Registers) -> Result<u16, MmuError> {
+        let value_lower = mmu.read_byte(registers.sp() as usize)?;
         registers.increment_sp();
-        let value_higher = mmu.read_byte(registers.sp() as usize).unwrap();
+        let value_higher = mmu.read_byte(registers.sp() as usize)?;
         registers.increment_sp();
 
-        let mut value = concatenate_bytes(value_lower, value_higher);
+        Ok(concatenate_bytes(value_lower, value_higher))
+    }
+
+    // Pop a value from the stack and store it in the given register
+    // Flags: - - - -
+    fn pop(mmu: &mut Mmu, registers: &mut Registers, register: Register) -> OperationResult {
+        let mut value = Self::pop_16bit(mmu, registers)?;
         if register == Register::AF {
             value &= 0xFFF0;
         }
 
         registers.set_16bit_register(register, value);
 
-        3
+        Ok(3)
     }
 
     // Pop from the memory stack the program counter PC value pushed when the subroutine was called, returning control to the source program.
     // Flags: - - - -
-    fn ret(mmu: &mut Mmu, registers: &mut Registers) -> u8 {
-        let value_lower = mmu.read_byte(registers.sp() as usize).unwrap();
-        registers.increment_sp();
-        let value_higher = mmu.read_byte(registers.sp() as usize).unwrap();
-        registers.increment_sp();
-
-        registers.set_16bit_register(Register::PC, concatenate_bytes(value_lower, value_higher));
+    fn ret(mmu: &mut Mmu, registers: &mut Registers) -> OperationResult {
+        let address = Self::pop_16bit(mmu, registers)?;
+        registers.set_pc(address);
 
-        4
+        Ok(4)
     }
 
     // Unconditional return from a function. Also enables interrupts by setting IME=1.
     // Flags: - - - -
-    fn reti(mmu: &mut Mmu, registers: &mut Registers) -> u8 {
-        Self::ret(mmu, registers);
+    fn reti(mmu: &mut Mmu, registers: &mut Registers) -> OperationResult {
+        Self::ret(mmu, registers)?;
         registers.set_ime(true);
 
-        4
+        Ok(4)
     }
 
     // Adjust the accumulator (register A) to a binary-coded decimal (BCD) number
@@ -1176,13 +1173,13 @@ impl InstructionSet {
 
     // Rotate the contents of register A to the left, through the carry (CY) flag
     // Flags: Z 0 0 A7
-    fn rl<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn rl<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>
     {
         let carry_flag = registers.carry_flag() as u8;
 
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
         let carry_bit = *value & (1 << 7) != 0;
         let new_value = (*value << 1) | carry_flag;
         *value = new_value;
@@ -1191,18 +1188,18 @@ impl InstructionSet {
         registers.set_zero_flag(new_value == 0);
         registers.set_carry_flag(carry_bit);
 
-        1
+        Ok(2)
     }
 
     // Rotate the contents of register A to the right, through the carry (CY) flag
     // Flags: Z 0 0 A0
-    fn rr<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn rr<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>
     {
         let carry_flag = registers.carry_flag() as u8;
 
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
         let carry_bit = *value & 1 != 0;
         let new_value = (*value >> 1) | (carry_flag << 7);
         *value = new_value;
@@ -1211,16 +1208,16 @@ impl InstructionSet {
         registers.set_zero_flag(new_value == 0);
         registers.set_carry_flag(carry_bit);
 
-        1
+        Ok(2)
     }
 
     // Rotate the contents of register A to the left.
     // Flags: Z 0 0 A7
-    fn rlc<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn rlc<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>
     {
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
         let carry_bit = *value & (1 << 7) != 0;
         let new_value = (*value << 1) | carry_bit as u8;
         *value = new_value;
@@ -1229,16 +1226,16 @@ impl InstructionSet {
         registers.set_zero_flag(new_value == 0);
         registers.set_carry_flag(carry_bit);
 
-        1
+        Ok(2)
     }
 
     // Rotate the contents of register A to the right
     // Flags: Z 0 0 A0
-    fn rrc<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn rrc<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8,
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>,
     {
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
         let carry_bit = *value & 1;
         let new_value = (*value >> 1) | (carry_bit << 7);
         *value = new_value;
@@ -1247,16 +1244,16 @@ impl InstructionSet {
         registers.set_zero_flag(new_value == 0);
         registers.set_carry_flag(carry_bit != 0);
 
-        1
+        Ok(2)
     }
 
     // Shift the contents of register A to the right
     // Flags: Z 0 0 A0
-    fn sr<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn sr<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8,
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>,
     {
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
         let carry_bit = (*value & 0x01) != 0;
         let msb = *value & 0x80;
         let new_value = (*value >> 1) | msb;
@@ -1266,16 +1263,16 @@ impl InstructionSet {
         registers.set_zero_flag(new_value == 0);
         registers.set_carry_flag(carry_bit);
 
-        2
+        Ok(2)
     }
 
     // Shift the contents of register A to the left
     // Flags: Z 0 0 A0
-    fn sl<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn sl<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8,
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>,
     {
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
 
         let carry_bit = (*value & 0x80) != 0;
         let new_value = *value << 1;
@@ -1285,7 +1282,7 @@ impl InstructionSet {
         registers.set_zero_flag(new_value == 0);
         registers.set_carry_flag(carry_bit);
 
-        2
+        Ok(2)
     }
 
     // Tests the bit b of the 8-bit register r.
@@ -1341,11 +1338,11 @@ impl InstructionSet {
     // Shift the contents of the lower-order four bits (0-3) of register B to the higher-order four
     // bits (4-7) of the register, and shift the higher-order four bits to the lower-order four bits.
     // Flags: Z 0 0 0
-    fn swap<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn swap<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>
     {
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
 
         let higher_bits = (*value & 0xF0) >> 4;
         let lower_bits = *value & 0x0F;
@@ -1358,16 +1355,16 @@ impl InstructionSet {
         registers.set_half_carry_flag(false);
         registers.set_carry_flag(false);
 
-        2
+        Ok(2)
     }
 
     // Shift the contents of register B to the right.
     // Flags: Z 0 0 B0
-    fn srl<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> u8
+    fn srl<F>(mmu: &mut Mmu, registers: &mut Registers, mut get_value: F) -> OperationResult
     where
-        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> &'a mut u8
+        for<'a> F: FnMut(&'a mut Mmu, &'a mut Registers) -> Result<&'a mut u8, MmuError>
     {
-        let value = get_value(mmu, registers);
+        let value = get_value(mmu, registers)?;
 
         let lsb = *value & 1;
         let new_value = *value >> 1;
@@ -1379,6 +1376,368 @@ impl InstructionSet {
         registers.set_half_carry_flag(false);
         registers.set_carry_flag(lsb != 0);
 
-        2
+        Ok(2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Mmu, InstructionSet, Registers) {
+        let mmu = Mmu::new().unwrap();
+        let instruction_set = InstructionSet::new(Rc::new(RefCell::new(Mmu::new().unwrap())));
+
+        (mmu, instruction_set, Registers::new())
+    }
+
+    // Run an instruction with the given operands and return the cycles it took
+    fn execute(instruction: &Instruction, mmu: &mut Mmu, registers: &mut Registers, operands: &[u8]) -> u8 {
+        match &instruction.operation {
+            Operation::None => Ok(1),
+            Operation::Nullary(operation) => operation(mmu, registers),
+            Operation::Unary(operation) => operation(mmu, registers, operands[0]),
+            Operation::Binary(operation) => operation(mmu, registers, operands[0], operands[1]),
+        }.unwrap()
+    }
+
+    #[test]
+    fn illegal_address_is_a_recoverable_error() {
+        let (mut mmu, _, mut registers) = setup();
+        let operation: NullaryOperation = Rc::new(|mmu: &mut Mmu, registers: &mut Registers| { InstructionSet::inc_mem(registers, mmu, 0x10000) });
+
+        assert_eq!(operation(&mut mmu, &mut registers), Err(MmuError::IllegalAddress(0x10000)));
+    }
+
+    #[test]
+    fn ld_sp_hl_copies_hl() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+        registers.set_hl(0xC123);
+
+        let cycles = execute(instruction_set.fetch_instruction(0xF9), &mut mmu, &mut registers, &[]);
+
+        assert_eq!(cycles, 2);
+        assert_eq!(registers.sp(), 0xC123);
+    }
+
+    #[test]
+    fn ld_hl_sp_plus_offset() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+        let instruction = instruction_set.fetch_instruction(0xF8);
+
+        // 0x0F + 0x01 carries out of the low nibble only
+        registers.set_sp(0xFF0F);
+        registers.set_zero_flag(true);
+        registers.set_subtraction_flag(true);
+        execute(instruction, &mut mmu, &mut registers, &[0x01]);
+        assert_eq!(registers.hl(), 0xFF10);
+        assert_eq!(registers.sp(), 0xFF0F);
+        assert!(!registers.zero_flag());
+        assert!(!registers.subtraction_flag());
+        assert!(registers.half_carry_flag());
+        assert!(!registers.carry_flag());
+
+        // A -1 offset is added as 0xFF to the low byte, so both carries are set
+        registers.set_sp(0x0001);
+        execute(instruction, &mut mmu, &mut registers, &[0xFF]);
+        assert_eq!(registers.hl(), 0x0000);
+        assert!(registers.half_carry_flag());
+        assert!(registers.carry_flag());
+
+        // No carry when the low byte addition stays below 0x100 even though the high byte changes
+        registers.set_sp(0x0100);
+        execute(instruction, &mut mmu, &mut registers, &[0xFF]);
+        assert_eq!(registers.hl(), 0x00FF);
+        assert!(!registers.half_carry_flag());
+        assert!(!registers.carry_flag());
+    }
+
+    #[test]
+    fn add_sp_signed_offset() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+        let instruction = instruction_set.fetch_instruction(0xE8);
+        assert_eq!(instruction.length, 2);
+
+        // ADD SP, -1
+        registers.set_sp(0xFFF8);
+        let cycles = execute(instruction, &mut mmu, &mut registers, &[0xFF]);
+        assert_eq!(cycles, 4);
+        assert_eq!(registers.sp(), 0xFFF7);
+        assert!(!registers.zero_flag());
+        assert!(!registers.subtraction_flag());
+        assert!(registers.half_carry_flag());
+        assert!(registers.carry_flag());
+
+        // ADD SP, +15
+        registers.set_sp(0xFFF8);
+        execute(instruction, &mut mmu, &mut registers, &[0x0F]);
+        assert_eq!(registers.sp(), 0x0007);
+        assert!(registers.half_carry_flag());
+        assert!(registers.carry_flag());
+
+        registers.set_sp(0xC000);
+        execute(instruction, &mut mmu, &mut registers, &[0x0F]);
+        assert_eq!(registers.sp(), 0xC00F);
+        assert!(!registers.half_carry_flag());
+        assert!(!registers.carry_flag());
+    }
+
+    #[test]
+    fn ld_hl_increment_decrement_wrap() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+
+        registers.set_hl(0x0000);
+        execute(instruction_set.fetch_instruction(0x32), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.hl(), 0xFFFF);
+
+        execute(instruction_set.fetch_instruction(0x22), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.hl(), 0x0000);
+
+        execute(instruction_set.fetch_instruction(0x3A), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.hl(), 0xFFFF);
+
+        execute(instruction_set.fetch_instruction(0x2A), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.hl(), 0x0000);
+    }
+
+    #[test]
+    fn push_bc_pop_de() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+        registers.set_sp(0xDFFE);
+        registers.set_bc(0x1234);
+
+        execute(instruction_set.fetch_instruction(0xC5), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.sp(), 0xDFFC);
+        assert_eq!(mmu.read_byte(0xDFFD).unwrap(), 0x12);
+        assert_eq!(mmu.read_byte(0xDFFC).unwrap(), 0x34);
+
+        execute(instruction_set.fetch_instruction(0xD1), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.sp(), 0xDFFE);
+        assert_eq!(registers.de(), 0x1234);
+    }
+
+    #[test]
+    fn pop_af_masks_low_flag_bits() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+        registers.set_sp(0xDFFE);
+        registers.set_bc(0x12FF);
+
+        execute(instruction_set.fetch_instruction(0xC5), &mut mmu, &mut registers, &[]);
+        execute(instruction_set.fetch_instruction(0xF1), &mut mmu, &mut registers, &[]);
+        assert_eq!(registers.a(), 0x12);
+        assert_eq!(registers.f(), 0xF0);
+
+        // The masked value is what gets pushed back
+        execute(instruction_set.fetch_instruction(0xF5), &mut mmu, &mut registers, &[]);
+        assert_eq!(mmu.read_byte(0xDFFC).unwrap(), 0xF0);
+    }
+
+    #[test]
+    fn rst_jumps_to_its_vector() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+
+        for (i, opcode) in [0xC7, 0xCF, 0xD7, 0xDF, 0xE7, 0xEF, 0xF7, 0xFF].into_iter().enumerate() {
+            registers.set_sp(0xDFFE);
+            registers.set_pc(0xC001);
+
+            execute(instruction_set.fetch_instruction(opcode), &mut mmu, &mut registers, &[]);
+            assert_eq!(registers.pc(), i as u16 * 0x08, "RST {:#04X}", opcode);
+            assert_eq!(mmu.read_byte(0xDFFC).unwrap(), 0x01);
+            assert_eq!(mmu.read_byte(0xDFFD).unwrap(), 0xC0);
+        }
+    }
+
+    #[test]
+    fn adc_carry_in_boundary() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+
+        // ADC A, B with A = 0xFF, B = 0x00 and carry set
+        registers.set_a(0xFF);
+        registers.set_b(0x00);
+        registers.set_carry_flag(true);
+        execute(instruction_set.fetch_instruction(0x88), &mut mmu, &mut registers, &[]);
+
+        assert_eq!(registers.a(), 0x00);
+        assert!(registers.zero_flag());
+        assert!(!registers.subtraction_flag());
+        assert!(registers.half_carry_flag());
+        assert!(registers.carry_flag());
+
+        // Without carry in nothing overflows
+        registers.set_a(0xFF);
+        registers.set_carry_flag(false);
+        execute(instruction_set.fetch_instruction(0x88), &mut mmu, &mut registers, &[]);
+
+        assert_eq!(registers.a(), 0xFF);
+        assert!(!registers.zero_flag());
+        assert!(!registers.half_carry_flag());
+        assert!(!registers.carry_flag());
+    }
+
+    #[test]
+    fn sbc_carry_in_boundary() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+
+        // SBC A, d8 with A = 0x00, d8 = 0xFF and carry set
+        registers.set_a(0x00);
+        registers.set_carry_flag(true);
+        execute(instruction_set.fetch_instruction(0xDE), &mut mmu, &mut registers, &[0xFF]);
+
+        assert_eq!(registers.a(), 0x00);
+        assert!(registers.zero_flag());
+        assert!(registers.subtraction_flag());
+        assert!(registers.half_carry_flag());
+        assert!(registers.carry_flag());
+    }
+
+    #[test]
+    fn conditional_control_flow_names() {
+        let (_, instruction_set, _) = setup();
+
+        let expected = [
+            (0x20, "JR NZ, s8"), (0x28, "JR Z, s8"), (0x30, "JR NC, s8"), (0x38, "JR C, s8"),
+            (0xC2, "JP NZ, a16"), (0xCA, "JP Z, a16"), (0xD2, "JP NC, a16"), (0xDA, "JP C, a16"),
+            (0xC4, "CALL NZ, a16"), (0xCC, "CALL Z, a16"), (0xD4, "CALL NC, a16"), (0xDC, "CALL C, a16"),
+            (0xC0, "RET NZ"), (0xC8, "RET Z"), (0xD0, "RET NC"), (0xD8, "RET C"),
+        ];
+
+        for (opcode, name) in expected {
+            assert_eq!(instruction_set.fetch_instruction(opcode).name, name, "opcode {:#04X}", opcode);
+        }
+    }
+
+    // Sets one of the flags the conditional instructions test
+    type SetFlag = fn(&mut Registers, bool);
+
+    #[test]
+    fn conditional_control_flow_polarity() {
+        let (mut mmu, instruction_set, mut registers) = setup();
+
+        // (opcode, taken when the flag is set, flag setter, cycles when taken and not taken)
+        let conditions: [(u8, bool, SetFlag, u8, u8); 12] = [
+            (0xC2, false, Registers::set_zero_flag, 4, 3),
+            (0xCA, true, Registers::set_zero_flag, 4, 3),
+            (0xD2, false, Registers::set_carry_flag, 4, 3),
+            (0xDA, true, Registers::set_carry_flag, 4, 3),
+            (0xC4, false, Registers::set_zero_flag, 6, 3),
+            (0xCC, true, Registers::set_zero_flag, 6, 3),
+            (0xD4, false, Registers::set_carry_flag, 6, 3),
+            (0xDC, true, Registers::set_carry_flag, 6, 3),
+            (0xC0, false, Registers::set_zero_flag, 5, 2),
+            (0xC8, true, Registers::set_zero_flag, 5, 2),
+            (0xD0, false, Registers::set_carry_flag, 5, 2),
+            (0xD8, true, Registers::set_carry_flag, 5, 2),
+        ];
+
+        for (opcode, taken_when_set, set_flag, taken_cycles, not_taken_cycles) in conditions {
+            let instruction = instruction_set.fetch_instruction(opcode);
+            for flag in [false, true] {
+                registers.set_pc(0xC000);
+                // RET cc returns to the same target the jumps and calls are given
+                registers.set_sp(0xDFFC);
+                mmu.write_byte(0xDFFC, 0x34).unwrap();
+                mmu.write_byte(0xDFFD, 0x12).unwrap();
+                set_flag(&mut registers, flag);
+
+                let cycles = execute(instruction, &mut mmu, &mut registers, &[0x34, 0x12]);
+                let taken = flag == taken_when_set;
+
+                assert_eq!(registers.pc(), if taken { 0x1234 } else { 0xC000 }, "opcode {:#04X}", opcode);
+                assert_eq!(cycles, if taken { taken_cycles } else { not_taken_cycles }, "opcode {:#04X}", opcode);
+            }
+        }
+
+        // JR NC not taken takes 2 cycles like the other JR cc
+        registers.set_carry_flag(true);
+        let cycles = execute(instruction_set.fetch_instruction(0x30), &mut mmu, &mut registers, &[0x05]);
+        assert_eq!(cycles, 2);
+    }
+
+    // Machine cycles per opcode from blargg's instr_timing, 0 where the opcode is illegal, HALT/STOP or the CB prefix
+    const INSTRUCTION_TIMINGS: [u8; 256] = [
+        1, 3, 2, 2, 1, 1, 2, 1, 5, 2, 2, 2, 1, 1, 2, 1,
+        0, 3, 2, 2, 1, 1, 2, 1, 3, 2, 2, 2, 1, 1, 2, 1,
+        2, 3, 2, 2, 1, 1, 2, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+        2, 3, 2, 2, 3, 3, 3, 1, 2, 2, 2, 2, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        2, 2, 2, 2, 2, 2, 0, 2, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        1, 1, 1, 1, 1, 1, 2, 1, 1, 1, 1, 1, 1, 1, 2, 1,
+        2, 3, 3, 4, 3, 4, 2, 4, 2, 4, 3, 0, 3, 6, 2, 4,
+        2, 3, 3, 0, 3, 4, 2, 4, 2, 4, 3, 0, 3, 0, 2, 4,
+        3, 3, 2, 0, 0, 4, 2, 4, 4, 1, 4, 0, 0, 0, 2, 4,
+        3, 3, 2, 1, 0, 4, 2, 4, 3, 2, 4, 1, 0, 0, 2, 4,
+    ];
+
+    // Machine cycles for the conditional jumps, calls and returns when the branch is taken
+    fn branch_taken_timing(opcode: u8) -> Option<u8> {
+        match opcode {
+            0x20 | 0x28 | 0x30 | 0x38 => Some(3),
+            0xC0 | 0xC8 | 0xD0 | 0xD8 => Some(5),
+            0xC2 | 0xCA | 0xD2 | 0xDA => Some(4),
+            0xC4 | 0xCC | 0xD4 | 0xDC => Some(6),
+            _ => None,
+        }
+    }
+
+    // Machine cycles for a CB-prefixed opcode, including the prefix fetch
+    fn prefixed_timing(opcode: u8) -> u8 {
+        match (opcode & 0x07, opcode >> 6) {
+            (6, 1) => 3,
+            (6, _) => 4,
+            _ => 2,
+        }
+    }
+
+    // Run the opcode from a state where memory operands point into WRAM, with every flag set or cleared
+    fn timings(instruction: &Instruction, flags: u8) -> (u8, u8) {
+        let (mut mmu, _, mut registers) = setup();
+        registers.set_bc(0xC000);
+        registers.set_de(0xC000);
+        registers.set_hl(0xC000);
+        registers.set_sp(0xDFF0);
+        registers.set_f(flags);
+
+        (execute(instruction, &mut mmu, &mut registers, &[0x00, 0xC0]), instruction.cycles as u8)
+    }
+
+    #[test]
+    fn instruction_timings_match_instr_timing() {
+        let (_, instruction_set, _) = setup();
+
+        let mut mismatches = Vec::new();
+        for opcode in 0..=255u8 {
+            let expected = INSTRUCTION_TIMINGS[opcode as usize];
+            if expected == 0 {
+                continue;
+            }
+
+            let instruction = instruction_set.fetch_instruction(opcode);
+            let (cleared, cycles) = timings(instruction, 0x00);
+            let (set, _) = timings(instruction, 0xF0);
+            let taken = branch_taken_timing(opcode);
+            let valid = match taken {
+                Some(taken) => cleared.min(set) == expected && cleared.max(set) == taken && cycles == taken,
+                None => cleared == expected && set == expected && cycles == expected,
+            };
+            if !valid {
+                mismatches.push(format!("{} took {}/{} (table {}), expected {}{}", instruction, cleared, set, cycles, expected, taken.map_or(String::new(), |taken| format!("/{}", taken))));
+            }
+        }
+
+        for opcode in 0..=255u8 {
+            let expected = prefixed_timing(opcode);
+            let instruction = instruction_set.fetch_instruction_16bit(opcode);
+            let (cleared, cycles) = timings(instruction, 0x00);
+            if cleared != expected || cycles != expected {
+                mismatches.push(format!("CB {} took {} (table {}), expected {}", instruction, cleared, cycles, expected));
+            }
+        }
+
+        assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
     }
 }
\ No newline at end of file
@@ -1,10 +1,10 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use crate::bit_utils::split_2bytes;
+use crate::cpu::instruction_set::InstructionSet;
 use crate::cpu::registers::Registers;
-use crate::mmu::Mmu;
+use crate::mmu::{Mmu, MmuError};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Interrupt {
     VBlank = 0,
     LCD = 1,
@@ -17,13 +17,16 @@ impl Interrupt {
     pub const VALUES: [Self; 5] = [Self::VBlank, Self::LCD, Self::Timer, Self::Serial, Self::Joypad];
 }
 
+#[derive(Clone)]
 pub struct Interrupts {
     mmu: Rc<RefCell<Mmu>>,
+    // Interrupt serviced by the last call to handle_interrupts, if any
+    dispatched: Option<Interrupt>,
 }
 
 impl Interrupts {
     pub fn new(mmu: Rc<RefCell<Mmu>>) -> Self {
-        Interrupts { mmu }
+        Interrupts { mmu, dispatched: None }
     }
 
     pub fn get_interrupt_enable_register(&self) -> u8 {
@@ -35,27 +38,26 @@ impl Interrupts {
     }
     pub fn set_interrupt_flag_registers(&mut self, value: u8) { self.mmu.borrow_mut().set_iflag(value); }
 
-    pub fn handle_interrupts(&mut self, registers: &mut Registers) -> bool {
+    pub fn dispatched(&self) -> Option<Interrupt> { self.dispatched }
+
+    pub fn handle_interrupts(&mut self, registers: &mut Registers) -> Result<bool, MmuError> {
+        self.dispatched = None;
+
         // No interrupt requested
-        if self.get_interrupt_enable_register() == 0 || self.get_interrupt_flag_register() == 0 { return false; }
+        if self.get_interrupt_enable_register() == 0 || self.get_interrupt_flag_register() == 0 { return Ok(false); }
 
         for interrupt in Interrupt::VALUES {
             if self.get_interrupt_enable_register() & (1 << interrupt as u8) != 0 && self.get_interrupt_flag_register() & (1 << interrupt as u8) != 0 {
                 if registers.ime() {
                     // Reset interrupt
                     registers.set_ime(false);
-                    let mut IF = self.get_interrupt_flag_register();
-                    IF &= !(1 << interrupt as u8);
-                    self.set_interrupt_flag_registers(IF);
+                    let mut interrupt_flag = self.get_interrupt_flag_register();
+                    interrupt_flag &= !(1 << interrupt as u8);
+                    self.set_interrupt_flag_registers(interrupt_flag);
 
                     // Load current PC in stack
                     let mut mmu = self.mmu.borrow_mut();
-                    let (return_lower, return_higher) = split_2bytes(registers.pc());
-
-                    registers.decrement_sp();
-                    mmu.write_byte(registers.sp() as usize, return_higher).unwrap();
-                    registers.decrement_sp();
-                    mmu.write_byte(registers.sp() as usize, return_lower).unwrap();
+                    InstructionSet::push(&mut mmu, registers, registers.pc())?;
 
                     match interrupt {
                         Interrupt::VBlank => registers.set_pc(0x40),
@@ -64,11 +66,12 @@ impl Interrupts {
                         Interrupt::Serial => registers.set_pc(0x58),
                         Interrupt::Joypad => registers.set_pc(0x60),
                     }
+                    self.dispatched = Some(interrupt);
                 }
-                return true;
+                return Ok(true);
             }
         }
 
-        false
+        Ok(false)
     }
 }
\ No newline at end of file
@@ -1,36 +1,78 @@
-use std::ascii::AsciiExt;
 use std::cell::RefCell;
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Seek, Write};
-use std::ops::Add;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::path::Path;
+use std::io::{BufReader, Write};
 use std::rc::Rc;
-use crate::cpu::registers::Registers;
-use crate::mmu::{Mmu};
+use crate::cpu::registers::{RegisterSnapshot, Registers};
+use crate::mmu::{DeferredWrite, MemoryRegion, Mmu, MmuError};
 
-use anyhow::Result;
-use color_eyre::owo_colors::OwoColorize;
+use anyhow::{Context, Result};
 use crate::cpu::clock::Clock;
 use crate::cpu::instruction_set::{DebugInstruction, InstructionSet, Operation};
-use crate::cpu::interrupts::Interrupts;
+use crate::cpu::interrupts::{Interrupt, Interrupts};
+use crate::cpu::trace::{ReferenceLog, TraceFormat};
+use crate::crash::{self, LastInstruction};
 
-mod registers;
-mod interrupts;
+pub mod registers;
+pub mod interrupts;
 pub mod instruction_set;
 mod clock;
+pub mod trace;
+
+// Machine cycles spent pushing PC and jumping to an interrupt handler
+const INTERRUPT_DISPATCH_CYCLES: u8 = 5;
+
+// Conditions that stop the CPU, the PC is left on the offending instruction so it can be inspected
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CpuError {
+    UnimplementedOpcode { address: u16, opcode: u8, prefixed: bool },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CpuError::UnimplementedOpcode { address, opcode, prefixed: true } => write!(f, "CPU trapped at PC={:#06X}: unimplemented opcode 0xCB{:02X}", address, opcode),
+            CpuError::UnimplementedOpcode { address, opcode, prefixed: false } => write!(f, "CPU trapped at PC={:#06X}: unimplemented opcode {:#04X}", address, opcode),
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
 
 pub struct Cpu {
     mmu: Rc<RefCell<Mmu>>,
     pub registers: Registers,
     interrupts: Interrupts,
     instruction_set: InstructionSet,
-    log_file: File,
+    // Trace log, only written to once opened with open_log_file
+    log_file: Option<File>,
+    trace_format: TraceFormat,
+    // Checked before every instruction, stopping the CPU at the first divergence
+    reference_log: Option<ReferenceLog>,
+    // Labels from a .sym file, shown by the disassembler
+    symbols: HashMap<u16, String>,
     pub serial_log: String,
+    // Receives every serial byte as soon as its transfer completes
+    serial_sink: Option<Box<dyn Write>>,
     halt: bool,
     clock: Clock,
     i: u128,
     ei: bool,
 }
 
+// Execution state of the CPU, everything else (instruction tables, log file, symbols) is left out
+#[derive(Clone)]
+pub struct CpuSnapshot {
+    registers: RegisterSnapshot,
+    interrupts: Interrupts,
+    clock: Clock,
+    halt: bool,
+    ei: bool,
+    i: u128,
+}
+
 impl Cpu {
     pub fn new(mmu: Rc<RefCell<Mmu>>) -> Self {
         let registers = Registers::new();
@@ -39,8 +81,12 @@ impl Cpu {
             registers: registers.clone(),
             interrupts: Interrupts::new(mmu.clone()),
             instruction_set: InstructionSet::new(mmu.clone()),
-            log_file: OpenOptions::new().write(true).create(true).truncate(true).open("exec.log").unwrap(),
+            log_file: None,
+            trace_format: TraceFormat::default(),
+            reference_log: None,
+            symbols: HashMap::new(),
             serial_log: String::new(),
+            serial_sink: None,
             halt: false,
             clock: Clock::new(mmu.clone()),
             i: 0,
@@ -48,20 +94,109 @@ impl Cpu {
         }
     }
 
+    // Trace every executed instruction to the given file, in the format set with set_trace_format
+    pub fn open_log_file(&mut self, path: &Path) -> Result<()> {
+        let mut log_file = OpenOptions::new().write(true).create(true).truncate(true).open(path)?;
+        if let Some(header) = self.trace_format.header() {
+            writeln!(log_file, "{}", header)?;
+        }
+
+        self.log_file = Some(log_file);
+
+        Ok(())
+    }
+
+    // Stream serial output to the given writer (stdout, a file...), serial_log keeps accumulating it too
+    pub fn set_serial_sink(&mut self, serial_sink: Box<dyn Write>) { self.serial_sink = Some(serial_sink) }
+
+    pub fn set_trace_format(&mut self, trace_format: TraceFormat) {
+        self.trace_format = trace_format;
+        self.mmu.borrow_mut().set_fixed_ly(trace_format == TraceFormat::GameboyDoctor);
+    }
+
+    // Compare every instruction against a Gameboy Doctor log, which requires LY to be fixed the same way it was when taken
+    pub fn set_reference_log(&mut self, reference_log: ReferenceLog) {
+        self.reference_log = Some(reference_log);
+        self.mmu.borrow_mut().set_fixed_ly(true);
+    }
+
+    pub fn open_reference_log(&mut self, path: &Path) -> Result<()> {
+        let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        self.set_reference_log(ReferenceLog::new(BufReader::new(file)));
+
+        Ok(())
+    }
+
+    // Load labels from a .sym file, one `BB:ADDR LABEL` per line with ; comments. Banks are ignored for now
+    pub fn load_symbols(&mut self, path: &Path) -> Result<()> {
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let parsed = line.split_once(char::is_whitespace)
+                .and_then(|(location, label)| Some((location.split_once(':')?, label.trim())))
+                .and_then(|((_, address), label)| Some((u16::from_str_radix(address, 16).ok()?, label)));
+            let Some((address, label)) = parsed else {
+                return Err(anyhow::anyhow!("Invalid symbol on line {} of {}: '{}'", number + 1, path.display(), line));
+            };
+
+            self.symbols.insert(address, label.to_string());
+        }
+
+        Ok(())
+    }
+
+    pub fn symbol(&self, address: u16) -> Option<&str> {
+        self.symbols.get(&address).map(String::as_str)
+    }
+
+    // Instructions executed since the CPU was created
+    pub fn instruction_count(&self) -> u128 { self.i }
+
+    pub fn snapshot(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            registers: self.registers.snapshot(),
+            interrupts: self.interrupts.clone(),
+            clock: self.clock.clone(),
+            halt: self.halt,
+            ei: self.ei,
+            i: self.i,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &CpuSnapshot) {
+        self.registers.restore(&snapshot.registers);
+        self.interrupts = snapshot.interrupts.clone();
+        self.clock = snapshot.clock.clone();
+        self.halt = snapshot.halt;
+        self.ei = snapshot.ei;
+        self.i = snapshot.i;
+    }
+
+    // Interrupt dispatched during the last emulation_loop step, if any
+    pub fn dispatched_interrupt(&self) -> Option<Interrupt> { self.interrupts.dispatched() }
+
     pub fn emulation_loop(&mut self) -> Result<u8> {
-        self.log_serial();
-        // self.log_to_file()?;
+        self.log_serial()?;
+        self.log_to_file()?;
+        self.check_reference_log()?;
 
-        let interrupt_requested = self.interrupts.handle_interrupts(&mut self.registers);
+        let interrupt_requested = self.interrupts.handle_interrupts(&mut self.registers)
+            .with_context(|| format!("CPU fault at PC={:#06X} dispatching an interrupt", self.registers.pc()))?;
         if interrupt_requested {
             self.halt = false;
         }
 
-        let cycles = if !self.halt {
-            let cycles = self.run_next_opcode()?;
-            self.clock.update_clock_cycles(cycles);
+        let dispatch_cycles = if self.interrupts.dispatched().is_some() { INTERRUPT_DISPATCH_CYCLES } else { 0 };
+        self.clock.update_clock_cycles(dispatch_cycles);
 
-            cycles
+        // run_next_opcode clocks the timer itself, in step with the instruction's memory accesses
+        let cycles = if !self.halt {
+            self.run_next_opcode()?
         }
         else {
             self.clock.update_clock_cycles(1);
@@ -69,12 +204,12 @@ impl Cpu {
             1
         };
 
-        Ok(cycles)
+        Ok(dispatch_cycles + cycles)
     }
 
     pub fn run_next_opcode(&mut self) -> Result<u8> {
+        let instruction_address = self.registers.pc();
         let mut opcode = self.read_at_program_counter()?;
-        let mut instruction = self.instruction_set.fetch_instruction(opcode);
 
         if self.ei {
             self.registers.set_ime(true);
@@ -82,44 +217,54 @@ impl Cpu {
         }
 
         // 16-bit opcodes
-        let is_16bit_opcode = if opcode == 0xCB {
+        let is_16bit_opcode = opcode == 0xCB;
+        if is_16bit_opcode {
             opcode = self.read_at_program_counter()?;
-            instruction = self.instruction_set.fetch_instruction_16bit(opcode);
+        }
+
+        crash::record_instruction(LastInstruction { address: instruction_address, opcode, prefixed: is_16bit_opcode });
 
-            true
+        // Borrowed from the table, so dispatching doesn't clone the name and the operation's Rc every instruction
+        let instruction = if is_16bit_opcode {
+            self.instruction_set.fetch_instruction_16bit(opcode)
         } else {
-           false
+            self.instruction_set.fetch_instruction(opcode)
         };
 
-        let cycles = match instruction.operation {
-            Operation::None => {
-                if instruction.name == "" {
-                    if is_16bit_opcode {
-                        panic!("Unimplemented opcode 0xCB{:X} at {:#X}", opcode, self.registers.pc() - 1);
-                    }
-                    else {
-                        panic!("Unimplemented opcode {:#X} at {:#X}", opcode, self.registers.pc());
-                    }
-                }
-
-                // NOP
-                1
-            }
-            Operation::Nullary(ref operation) => {
-                operation(&mut self.mmu.borrow_mut(), &mut self.registers)
-            }
-            Operation::Unary(ref operation) => {
-                let operand = self.read_at_program_counter()?;
+        if matches!(instruction.operation, Operation::None) && instruction.name.is_empty() {
+            self.registers.set_pc(instruction_address);
 
-                operation(&mut self.mmu.borrow_mut(), &mut self.registers, operand)
-            }
-            Operation::Binary(ref operation) => {
-                let first_operand = self.read_at_program_counter()?;
-                let second_operand = self.read_at_program_counter()?;
+            return Err(CpuError::UnimplementedOpcode { address: instruction_address, opcode, prefixed: is_16bit_opcode }.into());
+        }
 
-                operation(&mut self.mmu.borrow_mut(), &mut self.registers, first_operand, second_operand)
-            }
+        let mut operands = [0; 2];
+        let operand_count = match instruction.operation {
+            Operation::Unary(_) => 1,
+            Operation::Binary(_) => 2,
+            _ => 0,
+        };
+        for operand in operands.iter_mut().take(operand_count) {
+            *operand = Self::read_and_increment_pc(&self.mmu, &mut self.registers)?;
+        }
+
+        // Every fetch takes a machine cycle, the operation's first memory access lands on the one after them
+        let fetch_cycles = 1 + is_16bit_opcode as u8 + operand_count as u8;
+        self.clock.update_clock_cycles(fetch_cycles);
+
+        self.mmu.borrow_mut().defer_writes();
+        let result = match &instruction.operation {
+            // NOP
+            Operation::None => Ok(1),
+            Operation::Nullary(operation) => operation(&mut self.mmu.borrow_mut(), &mut self.registers),
+            Operation::Unary(operation) => operation(&mut self.mmu.borrow_mut(), &mut self.registers, operands[0]),
+            Operation::Binary(operation) => operation(&mut self.mmu.borrow_mut(), &mut self.registers, operands[0], operands[1]),
         };
+        let writes = self.mmu.borrow_mut().take_deferred_writes();
+
+        let context = || format!("CPU fault at PC={:#06X} executing {}", instruction_address, instruction.name);
+        let cycles = result.with_context(context)?;
+        let elapsed_cycles = Self::commit_writes(&self.mmu, &mut self.clock, fetch_cycles, &writes).with_context(context)?;
+        self.clock.update_clock_cycles(cycles.saturating_sub(elapsed_cycles));
 
         if !is_16bit_opcode {
             // HALT
@@ -142,8 +287,29 @@ impl Cpu {
 
     // Reads the value in memory pointed at by PC and increments PC
     fn read_at_program_counter(&mut self) -> Result<u8> {
-        let value = self.mmu.borrow().read_byte(self.registers.pc() as usize)?;
-        self.registers.increment_pc();
+        Self::read_and_increment_pc(&self.mmu, &mut self.registers)
+    }
+
+    // Commit an operation's deferred writes one machine cycle apart, clocking the timer up to each of them
+    // Internal delay cycles, like the one before PUSH writes to the stack, are counted after the writes instead
+    // Returns the machine cycles the instruction has clocked so far
+    fn commit_writes(mmu: &RefCell<Mmu>, clock: &mut Clock, mut elapsed_cycles: u8, writes: &[DeferredWrite]) -> Result<u8, MmuError> {
+        for (i, write) in writes.iter().enumerate() {
+            if i > 0 || write.read_first {
+                clock.update_clock_cycles(1);
+                elapsed_cycles += 1;
+            }
+
+            mmu.borrow_mut().write_byte(write.address, write.value)?;
+        }
+
+        Ok(elapsed_cycles)
+    }
+
+    // Same as read_at_program_counter, for when the instruction set is already borrowed
+    fn read_and_increment_pc(mmu: &RefCell<Mmu>, registers: &mut Registers) -> Result<u8> {
+        let value = mmu.borrow().read_byte(registers.pc() as usize)?;
+        registers.increment_pc();
 
         Ok(value)
     }
@@ -158,76 +324,347 @@ impl Cpu {
 
         while pc < memory.len() {
             // Cartridge header
-            if pc >= 0x104 && pc < 0x150 {
+            if (0x104..0x150).contains(&pc) {
                 pc = 0x150;
                 continue;
             }
 
-            let address = pc;
+            let (instruction, length) = self.disassemble_instruction(&mmu, pc);
+            instructions.push(instruction);
 
-            let opcode = *memory.get(address).unwrap();
-            let instruction = self.instruction_set.fetch_instruction(opcode);
+            pc += length;
+        }
 
-            pc += 1;
+        instructions
+    }
+
+    // Disassemble the instructions from start up to (but not including) end, reading through the MMU
+    pub fn disassemble_range(&self, start: usize, end: usize) -> Vec<DebugInstruction> {
+        let mut pc = start;
+        let mut instructions = vec![];
 
-            let (first_operand, second_operand) = match instruction.length {
-                1 => (None, None),
-                2 => {
-                    let operand = mmu.read_byte(pc).unwrap();
-                    pc += 1;
+        let mmu = self.mmu.borrow();
+        let end = end.min(MemoryRegion::InterruptEnableRegister as usize + 1);
 
-                    (Some(operand), None)
-                },
-                3 => {
-                    let first_operand = mmu.read_byte(pc).unwrap();
-                    pc += 1;
+        while pc < end {
+            let (instruction, length) = self.disassemble_instruction(&mmu, pc);
+            instructions.push(instruction);
 
-                    let second_operand = mmu.read_byte(pc).unwrap();
-                    pc += 1;
+            pc += length;
+        }
 
-                    (Some(first_operand), Some(second_operand))
-                },
-                _ => (None, None),
-            };
+        instructions
+    }
 
-            let name = if instruction.name == "" { String::from("Unimplemented instruction ") } else { instruction.name };
+    // Decode the instruction at the given address and return it along with its length in bytes
+    fn disassemble_instruction(&self, mmu: &Mmu, address: usize) -> (DebugInstruction, usize) {
+        let opcode = mmu.read_byte(address).unwrap_or(0);
 
-            instructions.push(
-                DebugInstruction {
-                    address,
-                    opcode,
-                    first_operand,
-                    second_operand,
-                    name,
-                }
-            )
+        // 16-bit opcodes are shown as 0xCB followed by the second opcode byte
+        if opcode == 0xCB {
+            let second_opcode = mmu.read_byte(address + 1).ok();
+            let name = second_opcode.map_or(String::from("Unimplemented instruction "), |second_opcode| self.instruction_set.fetch_instruction_16bit(second_opcode).name.clone());
+
+            let label = self.symbol(address as u16).map(str::to_string);
+
+            return (DebugInstruction { address, opcode, first_operand: second_opcode, second_operand: None, name, label }, 2);
         }
 
-        instructions
+        let instruction = self.instruction_set.fetch_instruction(opcode);
+
+        let (first_operand, second_operand) = match instruction.length {
+            2 => (mmu.read_byte(address + 1).ok(), None),
+            3 => (mmu.read_byte(address + 1).ok(), mmu.read_byte(address + 2).ok()),
+            _ => (None, None),
+        };
+
+        let mut name = if instruction.name.is_empty() { String::from("Unimplemented instruction ") } else { instruction.name.clone() };
+
+        // Name the target of jumps and calls when there is a symbol for it
+        let target = match (first_operand, second_operand) {
+            (Some(lower), Some(higher)) if instruction.name.contains("a16") && !instruction.name.starts_with("LD") => Some(u16::from_le_bytes([lower, higher])),
+            (Some(steps), None) if instruction.name.starts_with("JR") => Some((address as u16).wrapping_add(2).wrapping_add(steps as i8 as u16)),
+            _ => None,
+        };
+        if let Some(target_label) = target.and_then(|target| self.symbol(target)) {
+            name = format!("{} ({})", name, target_label);
+        }
+        let label = self.symbol(address as u16).map(str::to_string);
+
+        (DebugInstruction { address, opcode, first_operand, second_operand, name, label }, instruction.length.max(1))
     }
 
-    fn log_serial(&mut self) {
-        let mut mmu = self.mmu.borrow_mut();
-        if mmu.sc() == 0x81 {
-            let character = mmu.sb() as char;
-            //self.serial_log.write_all(format!("{}", character).as_bytes())?;
-            mmu.set_sc(0);
+    fn log_serial(&mut self) -> Result<()> {
+        // Runs every instruction, so only take the mutable borrow when there is output to collect
+        if !self.mmu.borrow().has_serial_output() {
+            return Ok(());
+        }
 
-            self.serial_log.push(character);
+        let bytes = self.mmu.borrow_mut().take_serial_output();
+        self.serial_log.extend(bytes.iter().map(|byte| *byte as char));
 
+        // Flushed right away so that a harness watching the output sees it in real time
+        if let Some(serial_sink) = &mut self.serial_sink {
+            serial_sink.write_all(&bytes).and_then(|_| serial_sink.flush()).context("Failed to write serial output")?;
         }
+
+        Ok(())
+    }
+
+    fn check_reference_log(&mut self) -> Result<()> {
+        let Some(reference_log) = &mut self.reference_log else {
+            return Ok(());
+        };
+
+        let line = TraceFormat::GameboyDoctor.format_line(&self.registers, &self.mmu.borrow());
+        reference_log.check(&line)
     }
 
     fn log_to_file(&mut self) -> Result<()> {
-        self.log_file.write_all(format!("A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X} 0xDF7D:{:02X}\n",
-                                        self.registers.a(), self.registers.f(), self.registers.b(),
-                                        self.registers.c(), self.registers.d(), self.registers.e(),self.registers.h(), self.registers.l(),
-                                        self.registers.sp(), self. registers.pc(), self.mmu.borrow().read_byte(self.registers.pc() as usize).unwrap(),
-                                        self.mmu.borrow().read_byte(self.registers.pc() as usize + 1).unwrap(),
-                                        self.mmu.borrow().read_byte(self.registers.pc() as usize + 2).unwrap(),
-                                        self.mmu.borrow().read_byte(self.registers.pc() as usize + 3).unwrap(),
-                                        self.mmu.borrow().read_byte(0xDF7D).unwrap()).as_bytes())?;
+        let Some(log_file) = &mut self.log_file else {
+            return Ok(());
+        };
+
+        // Everything needed from memory is read under a single borrow
+        let line = self.trace_format.format_line(&self.registers, &self.mmu.borrow());
+        writeln!(log_file, "{}", line)?;
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+// Instructions after PC and words above SP included in the state dump
+const DUMP_INSTRUCTIONS: usize = 4;
+const DUMP_STACK_WORDS: usize = 4;
+
+// Multi-line dump of the CPU state, memory is left out if the MMU is already mutably borrowed
+impl fmt::Display for Cpu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let r = &self.registers;
+        writeln!(f, "{}", r.snapshot())?;
+        writeln!(f, "HALT:{} EI:{} Instructions:{}", self.halt as u8, self.ei as u8, self.i)?;
+
+        let Ok(mmu) = self.mmu.try_borrow() else {
+            return write!(f, "Memory unavailable, the MMU is borrowed");
+        };
+
+        let mut address = r.pc() as usize;
+        for _ in 0..DUMP_INSTRUCTIONS {
+            let (instruction, length) = self.disassemble_instruction(&mmu, address);
+            let operands: String = [instruction.first_operand, instruction.second_operand].iter().flatten().map(|operand| format!(" {:02X}", operand)).collect();
+            writeln!(f, "{}{:04X}: {:02X}{} {}", if address == r.pc() as usize { '>' } else { ' ' }, address, instruction.opcode, operands, instruction.name)?;
+
+            address += length;
+        }
+
+        write!(f, "{}", format_stack(&mmu, r.sp()))
+    }
+}
+
+// Top words of the stack, starting at SP
+pub fn format_stack(mmu: &Mmu, sp: u16) -> String {
+    let mut stack = String::from("Stack:");
+    for word in 0..DUMP_STACK_WORDS {
+        let address = sp.wrapping_add(word as u16 * 2);
+        let lower = mmu.read_byte(address as usize).unwrap_or(0);
+        let higher = mmu.read_byte(address.wrapping_add(1) as usize).unwrap_or(0);
+        stack.push_str(&format!(" {:04X}:{:04X}", address, u16::from_le_bytes([lower, higher])));
+    }
+
+    stack
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup(program: &[(usize, u8)]) -> Cpu {
+        let mut cartridge = vec![0; 0x8000];
+        for &(address, value) in program {
+            cartridge[address] = value;
+        }
+
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        mmu.borrow_mut().load_cartridge_data(cartridge).unwrap();
+
+        Cpu::new(mmu)
+    }
+
+    #[test]
+    fn disassemble_range_decodes_operands_and_prefix() {
+        // LD A, d8 / CB SWAP A / JP a16 / NOP
+        let cpu = setup(&[(0x150, 0x3E), (0x151, 0x42), (0x152, 0xCB), (0x153, 0x37), (0x154, 0xC3), (0x155, 0x50), (0x156, 0x01)]);
+
+        let instructions = cpu.disassemble_range(0x150, 0x158);
+        let summary: Vec<(usize, &str, Option<u8>, Option<u8>)> = instructions.iter()
+            .map(|instruction| (instruction.address, instruction.name.as_str(), instruction.first_operand, instruction.second_operand))
+            .collect();
+
+        assert_eq!(summary, vec![
+            (0x150, "LD A, d8", Some(0x42), None),
+            (0x152, "SWAP A", Some(0x37), None),
+            (0x154, "JP a16", Some(0x50), Some(0x01)),
+            (0x157, "NOP", None, None),
+        ]);
+    }
+
+    #[test]
+    fn symbols_annotate_disassembly() {
+        // JP a16 to 0x0150 / NOP
+        let mut cpu = setup(&[(0x14D, 0xC3), (0x14E, 0x50), (0x14F, 0x01)]);
+        let path = std::env::temp_dir().join(format!("rainier_symbols_{}.sym", std::process::id()));
+        fs::write(&path, "; generated by rgblink\n00:0150 Main\n\n00:0200 Loop ; comment\n").unwrap();
+
+        cpu.load_symbols(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let instructions = cpu.disassemble_range(0x14D, 0x151);
+        assert_eq!(instructions[0].name, "JP a16 (Main)");
+        assert_eq!(instructions[1].address, 0x150);
+        assert_eq!(instructions[1].label.as_deref(), Some("Main"));
+        assert_eq!(cpu.symbol(0x200), Some("Loop"));
+    }
+
+    #[test]
+    fn unimplemented_opcode_traps() {
+        let mut cpu = setup(&[(0x150, 0xD3)]);
+        cpu.registers.set_pc(0x150);
+
+        let error = cpu.run_next_opcode().unwrap_err();
+
+        assert_eq!(error.downcast_ref::<CpuError>(), Some(&CpuError::UnimplementedOpcode { address: 0x150, opcode: 0xD3, prefixed: false }));
+        assert_eq!(cpu.registers.pc(), 0x150);
+    }
+
+    #[test]
+    fn dispatch_borrowed_instructions() {
+        // LD A, d8 / CB SWAP A / JP a16
+        let mut cpu = setup(&[(0x150, 0x3E), (0x151, 0x42), (0x152, 0xCB), (0x153, 0x37), (0x154, 0xC3), (0x155, 0x00), (0x156, 0xC0)]);
+        cpu.registers.set_pc(0x150);
+
+        cpu.run_next_opcode().unwrap();
+        assert_eq!(cpu.registers.a(), 0x42);
+
+        cpu.run_next_opcode().unwrap();
+        assert_eq!(cpu.registers.a(), 0x24);
+        assert_eq!(cpu.registers.pc(), 0x154);
+
+        cpu.run_next_opcode().unwrap();
+        assert_eq!(cpu.registers.pc(), 0xC000);
+    }
+
+    // Writer handing its bytes back to the test
+    struct SharedSink(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> { Ok(()) }
+    }
+
+    #[test]
+    fn serial_sink_receives_each_byte_once() {
+        let mut cpu = setup(&[]);
+        let output = Rc::new(RefCell::new(Vec::new()));
+        cpu.set_serial_sink(Box::new(SharedSink(output.clone())));
+
+        for byte in b"Passed" {
+            cpu.mmu.borrow_mut().set_sb(*byte);
+            cpu.mmu.borrow_mut().set_sc(0x81);
+            cpu.log_serial().unwrap();
+            // The transfer is acknowledged, so it isn't sent again
+            cpu.log_serial().unwrap();
+        }
+
+        assert_eq!(output.borrow().as_slice(), b"Passed");
+        assert_eq!(cpu.serial_log, "Passed");
+    }
+
+    #[test]
+    fn log_to_file_with_outstanding_mmu_borrow() {
+        let mut cpu = setup(&[(0x150, 0x3E), (0x151, 0x42), (0x152, 0xCB), (0x153, 0x37)]);
+        cpu.registers.set_pc(0x150);
+
+        let path = std::env::temp_dir().join(format!("rainier_log_{}.log", std::process::id()));
+        cpu.open_log_file(&path).unwrap();
+
+        let mmu = cpu.mmu.clone();
+        let _borrow = mmu.borrow();
+        cpu.log_to_file().unwrap();
+        cpu.log_file = None;
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(log.contains("PC:0150 PCMEM:3E,42,CB,37"), "{}", log);
+    }
+
+    #[test]
+    fn interrupt_dispatch_takes_five_cycles() {
+        // NOP in the VBlank handler
+        let mut cpu = setup(&[(0x40, 0x00)]);
+        cpu.registers.set_pc(0x150);
+        cpu.registers.set_sp(0xDFF0);
+        cpu.registers.set_ime(true);
+        cpu.mmu.borrow_mut().set_ie(0x01);
+        cpu.mmu.borrow_mut().set_iflag(0x01);
+
+        assert_eq!(cpu.emulation_loop().unwrap(), 6);
+        assert_eq!(cpu.registers.pc(), 0x41);
+    }
+
+    #[test]
+    fn reads_land_after_the_operand_fetches() {
+        // NOP / LD A, (a16) reading TIMA
+        let mut cpu = setup(&[(0x150, 0x00), (0x151, 0xFA), (0x152, 0x05), (0x153, 0xFF)]);
+        cpu.registers.set_pc(0x150);
+        cpu.mmu.borrow_mut().set_tac(0b101); // Enabled, increments every 4 M-cycles
+
+        cpu.emulation_loop().unwrap();
+        assert_eq!(cpu.emulation_loop().unwrap(), 4);
+
+        // The read happens on the fourth machine cycle, once TIMA has been incremented
+        assert_eq!(cpu.registers.a(), 1);
+    }
+
+    #[test]
+    fn read_modify_write_lands_a_cycle_after_the_read() {
+        // NOP / NOP / INC (HL) on TIMA
+        let mut cpu = setup(&[(0x150, 0x00), (0x151, 0x00), (0x152, 0x34)]);
+        cpu.registers.set_pc(0x150);
+        cpu.registers.set_hl(0xFF05);
+        cpu.mmu.borrow_mut().set_tac(0b101);
+
+        cpu.emulation_loop().unwrap();
+        cpu.emulation_loop().unwrap();
+        assert_eq!(cpu.emulation_loop().unwrap(), 3);
+
+        // INC reads 0 before the timer increments TIMA, then writes 1 over the incremented value
+        assert_eq!(cpu.mmu.borrow().tima(), 1);
+    }
+
+    #[test]
+    fn display_dumps_registers_code_and_stack() {
+        // JP a16 / NOP
+        let mut cpu = setup(&[(0x150, 0xC3), (0x151, 0x00), (0x152, 0x02)]);
+        cpu.registers.set_af(0x12B0);
+        cpu.registers.set_hl(0xC0DE);
+        cpu.registers.set_sp(0xDFF0);
+        cpu.registers.set_pc(0x150);
+        cpu.mmu.borrow_mut().write_byte(0xDFF0, 0x34).unwrap();
+        cpu.mmu.borrow_mut().write_byte(0xDFF1, 0x12).unwrap();
+
+        let dump = cpu.to_string();
+        assert!(dump.contains("AF:12B0 BC:0000 DE:0000 HL:C0DE SP:DFF0 PC:0150"), "{}", dump);
+        assert!(dump.contains("Z:1 N:0 H:1 C:1 IME:0\nHALT:0"), "{}", dump);
+        assert!(dump.contains(">0150: C3 00 02 JP a16"), "{}", dump);
+        assert!(dump.contains(" 0153: 00 NOP"), "{}", dump);
+        assert!(dump.contains("Stack: DFF0:1234 DFF2:0000"), "{}", dump);
+
+        let mmu = cpu.mmu.clone();
+        let _borrow = mmu.borrow_mut();
+        assert!(cpu.to_string().ends_with("Memory unavailable, the MMU is borrowed"));
+    }
+}
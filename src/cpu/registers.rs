@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Register {
     A, B, C, D, E, H, L, AF, BC, DE, HL, SP, PC,
@@ -18,6 +20,39 @@ pub struct Registers {
     ime: bool,
 }
 
+// Plain copy of every register value, for diffing and save states
+// The register pairs and flags are derived from the 8-bit registers, restore only reads the latter
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct RegisterSnapshot {
+    pub a: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub f: u8,
+    pub h: u8,
+    pub l: u8,
+    pub af: u16,
+    pub bc: u16,
+    pub de: u16,
+    pub hl: u16,
+    pub sp: u16,
+    pub pc: u16,
+    pub ime: bool,
+    pub zero: bool,
+    pub subtraction: bool,
+    pub half_carry: bool,
+    pub carry: bool,
+}
+
+impl fmt::Display for RegisterSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AF:{:04X} BC:{:04X} DE:{:04X} HL:{:04X} SP:{:04X} PC:{:04X}\nZ:{} N:{} H:{} C:{} IME:{}",
+               self.af, self.bc, self.de, self.hl, self.sp, self.pc,
+               self.zero as u8, self.subtraction as u8, self.half_carry as u8, self.carry as u8, self.ime as u8)
+    }
+}
+
 #[repr(u8)]
 pub enum Flag {
     Zero = 1 << 7,
@@ -31,6 +66,44 @@ impl Registers {
         Registers::default()
     }
 
+    pub fn snapshot(&self) -> RegisterSnapshot {
+        RegisterSnapshot {
+            a: self.a,
+            b: self.b,
+            c: self.c,
+            d: self.d,
+            e: self.e,
+            f: self.f,
+            h: self.h,
+            l: self.l,
+            af: self.af(),
+            bc: self.bc(),
+            de: self.de(),
+            hl: self.hl(),
+            sp: self.sp,
+            pc: self.pc,
+            ime: self.ime,
+            zero: self.zero_flag(),
+            subtraction: self.subtraction_flag(),
+            half_carry: self.half_carry_flag(),
+            carry: self.carry_flag(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &RegisterSnapshot) {
+        self.a = snapshot.a;
+        self.b = snapshot.b;
+        self.c = snapshot.c;
+        self.d = snapshot.d;
+        self.e = snapshot.e;
+        self.set_f(snapshot.f);
+        self.h = snapshot.h;
+        self.l = snapshot.l;
+        self.sp = snapshot.sp;
+        self.pc = snapshot.pc;
+        self.ime = snapshot.ime;
+    }
+
     pub fn get_8bit_register(&self, register: Register) -> u8 {
         match register {
             Register::A => self.a(),
@@ -142,12 +215,12 @@ impl Registers {
     // --- SP and PC ---
     pub fn sp(&self) -> u16 { self.sp }
     pub fn set_sp(&mut self, val: u16) { self.sp = val }
-    pub fn increment_sp(&mut self) { self.sp += 1 }
-    pub fn decrement_sp(&mut self) { self.sp -= 1 }
+    pub fn increment_sp(&mut self) { self.sp = self.sp.wrapping_add(1) }
+    pub fn decrement_sp(&mut self) { self.sp = self.sp.wrapping_sub(1) }
 
     pub fn pc(&self) -> u16 { self.pc }
     pub fn set_pc(&mut self, val: u16) { self.pc = val }
-    pub fn increment_pc(&mut self) { self.pc += 1 }
+    pub fn increment_pc(&mut self) { self.pc = self.pc.wrapping_add(1) }
 
 
     pub fn ime(&self) -> bool { self.ime }
@@ -183,4 +256,47 @@ impl Registers {
 
     pub fn carry_flag(&self) -> bool { self.get_flag(Flag::Carry) }
     pub fn set_carry_flag(&mut self, val: bool) { self.set_flag(Flag::Carry, val) }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pc_and_sp_wrap() {
+        let mut registers = Registers::new();
+
+        registers.set_pc(0xFFFF);
+        registers.increment_pc();
+        assert_eq!(registers.pc(), 0x0000);
+
+        registers.set_sp(0x0000);
+        registers.decrement_sp();
+        assert_eq!(registers.sp(), 0xFFFF);
+        registers.increment_sp();
+        assert_eq!(registers.sp(), 0x0000);
+    }
+
+    #[test]
+    fn snapshot_then_restore() {
+        let mut registers = Registers::new();
+        registers.set_af(0x12B0);
+        registers.set_bc(0x3456);
+        registers.set_hl(0x9ABC);
+        registers.set_sp(0xFFFE);
+        registers.set_pc(0x0150);
+
+        let snapshot = registers.snapshot();
+        assert_eq!((snapshot.a, snapshot.f, snapshot.bc, snapshot.h), (0x12, 0xB0, 0x3456, 0x9A));
+        assert!(snapshot.zero && !snapshot.subtraction && snapshot.half_carry && snapshot.carry);
+
+        registers.set_af(0x0000);
+        registers.set_de(0x1111);
+        registers.set_pc(0x0200);
+        registers.set_ime(true);
+        assert_ne!(registers.snapshot(), snapshot);
+
+        registers.restore(&snapshot);
+        assert_eq!(registers.snapshot(), snapshot);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::io::BufRead;
+use anyhow::{Context, Result};
+use crate::cpu::registers::Registers;
+use crate::mmu::Mmu;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum TraceFormat {
+    // Gameboy Doctor style registers and PCMEM, plus the byte at 0xDF7D
+    #[default]
+    Text,
+    // Exactly the line format expected by Gameboy Doctor (which relies on LY always reading 0x90, see Mmu::set_fixed_ly)
+    GameboyDoctor,
+    // pc,opcode,a,f,bc,de,hl,sp
+    Csv,
+    // One JSON object per line with the same fields as the CSV format
+    Json,
+}
+
+impl TraceFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(TraceFormat::Text),
+            "doctor" => Some(TraceFormat::GameboyDoctor),
+            "csv" => Some(TraceFormat::Csv),
+            "json" => Some(TraceFormat::Json),
+            _ => None,
+        }
+    }
+
+    // First line of a new trace file, if the format has one
+    pub fn header(self) -> Option<&'static str> {
+        match self {
+            TraceFormat::Csv => Some("pc,opcode,a,f,bc,de,hl,sp"),
+            _ => None,
+        }
+    }
+
+    // Trace line (without the line ending) for the instruction about to run at PC
+    pub fn format_line(self, registers: &Registers, mmu: &Mmu) -> String {
+        let pc = registers.pc();
+        let pcmem: [u8; 4] = std::array::from_fn(|i| mmu.read_byte(pc.wrapping_add(i as u16) as usize).unwrap());
+
+        match self {
+            TraceFormat::Text => format!("A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X} 0xDF7D:{:02X}",
+                                         registers.a(), registers.f(), registers.b(), registers.c(), registers.d(), registers.e(), registers.h(), registers.l(),
+                                         registers.sp(), pc, pcmem[0], pcmem[1], pcmem[2], pcmem[3], mmu.read_byte(0xDF7D).unwrap()),
+            TraceFormat::GameboyDoctor => format!("A:{:02X} F:{:02X} B:{:02X} C:{:02X} D:{:02X} E:{:02X} H:{:02X} L:{:02X} SP:{:04X} PC:{:04X} PCMEM:{:02X},{:02X},{:02X},{:02X}",
+                                                  registers.a(), registers.f(), registers.b(), registers.c(), registers.d(), registers.e(), registers.h(), registers.l(),
+                                                  registers.sp(), pc, pcmem[0], pcmem[1], pcmem[2], pcmem[3]),
+            TraceFormat::Csv => format!("{:04X},{:02X},{:02X},{:02X},{:04X},{:04X},{:04X},{:04X}",
+                                        pc, pcmem[0], registers.a(), registers.f(), registers.bc(), registers.de(), registers.hl(), registers.sp()),
+            TraceFormat::Json => format!("{{\"pc\":{},\"opcode\":{},\"a\":{},\"f\":{},\"bc\":{},\"de\":{},\"hl\":{},\"sp\":{}}}",
+                                         pc, pcmem[0], registers.a(), registers.f(), registers.bc(), registers.de(), registers.hl(), registers.sp()),
+        }
+    }
+}
+
+// Gameboy Doctor log from a known-good emulator, compared line by line with the CPU's own state
+pub struct ReferenceLog {
+    lines: Box<dyn BufRead>,
+    // Index of the next instruction, which is also the next reference line
+    index: u128,
+}
+
+// First field where the CPU diverged from the reference log
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReferenceMismatch {
+    pub index: u128,
+    pub field: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ReferenceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Diverged from the reference log at instruction {}: {} is {}, expected {}", self.index, self.field, self.actual, self.expected)
+    }
+}
+
+impl std::error::Error for ReferenceMismatch {}
+
+impl ReferenceLog {
+    pub fn new(lines: impl BufRead + 'static) -> Self {
+        ReferenceLog { lines: Box::new(lines), index: 0 }
+    }
+
+    // Compare the Gameboy Doctor line of the state about to run with the next reference line
+    // There is nothing left to compare once the reference runs out
+    pub fn check(&mut self, actual: &str) -> Result<()> {
+        let mut expected = String::new();
+        if self.lines.read_line(&mut expected).context("Failed to read the reference log")? == 0 {
+            return Ok(());
+        }
+
+        let index = self.index;
+        self.index += 1;
+
+        match first_mismatch(expected.trim(), actual) {
+            Some((field, expected, actual)) => Err(ReferenceMismatch { index, field, expected, actual }.into()),
+            None => Ok(()),
+        }
+    }
+}
+
+// Field name, expected value and actual value of the first `NAME:VALUE` pair that differs
+fn first_mismatch(expected: &str, actual: &str) -> Option<(String, String, String)> {
+    let expected_fields: Vec<&str> = expected.split_whitespace().collect();
+    let actual_fields: Vec<&str> = actual.split_whitespace().collect();
+    if expected_fields.len() != actual_fields.len() {
+        return Some((String::from("line"), expected.to_string(), actual.to_string()));
+    }
+
+    expected_fields.iter().zip(&actual_fields)
+        .find(|(expected, actual)| expected != actual)
+        .map(|(expected, actual)| {
+            let (field, expected_value) = expected.split_once(':').unwrap_or(("line", expected));
+            let actual_value = actual.split_once(':').map_or(*actual, |(_, value)| value);
+
+            (field.to_string(), expected_value.to_string(), actual_value.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Registers, Mmu) {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.write_byte(0xC000, 0x3E).unwrap();
+        mmu.write_byte(0xC001, 0x42).unwrap();
+
+        let mut registers = Registers::new();
+        registers.set_a(0x01);
+        registers.set_f(0xB0);
+        registers.set_b(0x00);
+        registers.set_c(0x13);
+        registers.set_d(0x00);
+        registers.set_e(0xD8);
+        registers.set_h(0x01);
+        registers.set_l(0x4D);
+        registers.set_sp(0xFFFE);
+        registers.set_pc(0xC000);
+
+        (registers, mmu)
+    }
+
+    #[test]
+    fn json_trace_line() {
+        let (registers, mmu) = setup();
+
+        assert_eq!(TraceFormat::Json.format_line(&registers, &mmu),
+                   r#"{"pc":49152,"opcode":62,"a":1,"f":176,"bc":19,"de":216,"hl":333,"sp":65534}"#);
+    }
+
+    #[test]
+    fn gameboy_doctor_trace_line() {
+        let (mut registers, mut mmu) = setup();
+
+        // First line of the Gameboy Doctor reference logs, at the entry point of the cpu_instrs ROMs
+        let mut rom = vec![0; 0x8000];
+        rom[0x100..0x104].copy_from_slice(&[0x00, 0xC3, 0x13, 0x02]);
+        mmu.load_cartridge_data(rom).unwrap();
+        registers.set_pc(0x0100);
+        mmu.set_fixed_ly(true);
+
+        assert_eq!(TraceFormat::GameboyDoctor.format_line(&registers, &mmu),
+                   "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02");
+        assert_eq!(mmu.read_byte(0xFF44).unwrap(), 0x90);
+    }
+
+    #[test]
+    fn csv_trace_line() {
+        let (registers, mmu) = setup();
+
+        assert_eq!(TraceFormat::Csv.header(), Some("pc,opcode,a,f,bc,de,hl,sp"));
+        assert_eq!(TraceFormat::Csv.format_line(&registers, &mmu), "C000,3E,01,B0,0013,00D8,014D,FFFE");
+    }
+
+    #[test]
+    fn reference_log_reports_first_mismatch() {
+        let reference = "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02\n\
+                         A:01 F:B0 B:00 C:14 D:00 E:D8 H:01 L:4D SP:FFFE PC:0101 PCMEM:C3,13,02,00\n";
+        let mut reference_log = ReferenceLog::new(std::io::Cursor::new(reference));
+
+        reference_log.check("A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02").unwrap();
+        let error = reference_log.check("A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0101 PCMEM:C3,13,02,00").unwrap_err();
+        let mismatch = error.downcast_ref::<ReferenceMismatch>().unwrap();
+
+        assert_eq!(mismatch, &ReferenceMismatch { index: 1, field: String::from("C"), expected: String::from("14"), actual: String::from("13") });
+        assert_eq!(mismatch.to_string(), "Diverged from the reference log at instruction 1: C is 13, expected 14");
+
+        // Past the end of the reference there is nothing to compare against
+        reference_log.check("anything").unwrap();
+    }
+}
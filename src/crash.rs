@@ -0,0 +1,92 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use crate::cpu::Cpu;
+
+// Instruction the CPU was executing, recorded before its operation runs
+// Kept to what the fetch already has at hand since it is recorded every instruction, the registers are read by the hook
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LastInstruction {
+    pub address: u16,
+    pub opcode: u8,
+    pub prefixed: bool,
+}
+
+// Weak so that the hook never keeps the machine alive, the MMU is reached through the CPU
+type Machine = Weak<RefCell<Cpu>>;
+
+// Panic hooks have to be Send + Sync, so the Rc'd machine is reached through thread locals instead of being captured
+// A panic inside an opcode closure happens with the CPU and MMU mutably borrowed, hence the separate last instruction
+thread_local! {
+    static MACHINE: RefCell<Option<Machine>> = const { RefCell::new(None) };
+    static LAST_INSTRUCTION: Cell<Option<LastInstruction>> = const { Cell::new(None) };
+}
+
+// Print the emulator state after the default panic message, for panics on the current thread
+pub fn install_panic_hook(cpu: &Rc<RefCell<Cpu>>) {
+    MACHINE.with(|machine| *machine.borrow_mut() = Some(Rc::downgrade(cpu)));
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        eprintln!("{}", current_report());
+    }));
+}
+
+pub fn record_instruction(instruction: LastInstruction) {
+    LAST_INSTRUCTION.with(|last_instruction| last_instruction.set(Some(instruction)));
+}
+
+fn current_report() -> String {
+    let last_instruction = LAST_INSTRUCTION.try_with(Cell::get).ok().flatten();
+    let machine = MACHINE.try_with(|machine| machine.try_borrow().ok().and_then(|machine| machine.clone())).ok().flatten();
+    let Some(cpu) = machine.and_then(|cpu| cpu.upgrade()) else {
+        return crash_report(None, last_instruction);
+    };
+
+    crash_report(cpu.try_borrow().ok().as_deref(), last_instruction)
+}
+
+// Whatever parts of the machine can be read, the CPU is None when it was borrowed at the time of the panic
+// Its Display impl leaves out the memory when the MMU is borrowed
+pub fn crash_report(cpu: Option<&Cpu>, last_instruction: Option<LastInstruction>) -> String {
+    let mut report = String::from("Emulator state at panic:\n");
+
+    if let Some(last_instruction) = last_instruction {
+        let prefix = if last_instruction.prefixed { "0xCB" } else { "0x" };
+        report.push_str(&format!("Last instruction: {}{:02X} at PC={:#06X}\n", prefix, last_instruction.opcode, last_instruction.address));
+    }
+
+    match cpu {
+        Some(cpu) => report.push_str(&cpu.to_string()),
+        None => report.push_str("CPU state unavailable, the CPU is borrowed"),
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmu::Mmu;
+
+    #[test]
+    fn report_with_a_busy_cpu() {
+        let last_instruction = LastInstruction { address: 0x0150, opcode: 0x37, prefixed: true };
+
+        let report = crash_report(None, Some(last_instruction));
+        assert!(report.contains("Last instruction: 0xCB37 at PC=0x0150"), "{}", report);
+        assert!(report.ends_with("CPU state unavailable, the CPU is borrowed"), "{}", report);
+    }
+
+    #[test]
+    fn report_with_an_idle_cpu() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut cpu = Cpu::new(mmu.clone());
+        cpu.registers.set_pc(0xC000);
+
+        let report = crash_report(Some(&cpu), None);
+        assert!(report.contains("PC:C000"), "{}", report);
+        assert!(report.contains(">C000: 00 NOP"), "{}", report);
+        assert!(!report.contains("Last instruction"), "{}", report);
+    }
+}
@@ -2,81 +2,213 @@
 
 mod cpu;
 mod mmu;
+mod apu;
 mod bit_utils;
+#[cfg(feature = "frontend")]
 mod ui;
 mod ppu;
+mod recording;
+mod rewind;
+mod crash;
 
 use std::cell::RefCell;
 use std::env;
-use std::io::stdout;
-use anyhow::Result;
-use std::path::Path;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
+#[cfg(feature = "frontend")]
+use std::io::stdout;
+#[cfg(feature = "frontend")]
 use ratatui::crossterm::event::EnableMouseCapture;
+#[cfg(feature = "frontend")]
 use ratatui::crossterm::execute;
-use winit::dpi::LogicalSize;
-use winit::event::{Event, WindowEvent};
+#[cfg(feature = "frontend")]
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::keyboard::KeyCode;
 use cpu::*;
 use mmu::*;
+use crate::mmu::cheats::Cheat;
+use crate::mmu::joypad::Button;
+use crate::apu::{Apu, AudioConfig};
+use crate::cpu::trace::TraceFormat;
 use crate::ppu::Ppu;
+use crate::ppu::compatibility::{self, CompatibilityPalette};
+use crate::recording::GifRecorder;
+use crate::rewind::{RewindBuffer, Snapshot};
+#[cfg(feature = "frontend")]
 use crate::ui::{Action, App};
+#[cfg(feature = "frontend")]
 use crate::ui::lcd::LCD;
+#[cfg(feature = "frontend")]
+use crate::ui::key_bindings::{KeyBindings, KEY_BINDINGS_FILE};
 
 const WIDTH: u32 = 320;
 const HEIGHT: u32 = 240;
 const BOX_SIZE: i16 = 64;
 
-#[derive(PartialOrd, PartialEq, Copy, Clone)]
+// Length of a frame in T-cycles (154 scanlines of 456 T-cycles)
+const FRAME_T_CYCLES: u32 = 70224;
+
+// Upper bound on the instructions a boot ROM may take before we consider it stuck (e.g. logo check failure)
+const BOOT_ROM_INSTRUCTION_LIMIT: usize = 10_000_000;
+
+#[derive(PartialOrd, PartialEq, Copy, Clone, Debug)]
 enum EmulationMode {
     Debug(u32),
     Normal,
 }
 
+// Hardware whose post-boot state is set up when skipping the boot ROM
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum Model {
+    #[default]
+    Dmg,
+    Cgb,
+    Sgb,
+}
+
+impl Model {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dmg" => Some(Model::Dmg),
+            "cgb" => Some(Model::Cgb),
+            "sgb" => Some(Model::Sgb),
+            _ => None,
+        }
+    }
+}
+
+// Headless emulation core (CPU, MMU and PPU), usable without any of the frontends
+type Core = Rainier;
+
 struct Rainier {
     mmu: Rc<RefCell<Mmu>>,
     cpu: Rc<RefCell<Cpu>>,
     ppu: Ppu,
+
+    // Number of frames emulated since the ROM was loaded
+    frame_count: u64,
+
+    recording: Option<GifRecorder>,
+
+    // Last seconds of emulation, for instant replays
+    rewind: Option<RewindBuffer>,
+}
+
+// Nintendo logo in the cartridge header, and the ® drawn next to it by the DMG boot ROM
+const HEADER_LOGO: std::ops::Range<usize> = 0x104..0x134;
+const REGISTERED_TILE: [u8; 8] = [0x3C, 0x42, 0xB9, 0xA5, 0xB9, 0xA5, 0x42, 0x3C];
+
+// VRAM (0x8000-0x9FFF) as the DMG boot ROM leaves it, with the header logo in tiles 1-24 and the ® in tile 25
+// Each nibble of the logo is scaled up to two identical rows of doubled pixels
+fn boot_rom_video_ram(logo: &[u8]) -> Vec<u8> {
+    let mut video_ram = vec![0; 0x2000];
+
+    let nibbles = logo.iter().flat_map(|byte| [byte >> 4, byte & 0xF]);
+    for (i, nibble) in nibbles.enumerate() {
+        let row = (0..4).fold(0, |row, bit| row | (((nibble >> (3 - bit)) & 1) * 0b11) << (6 - bit * 2));
+        video_ram[0x10 + i * 4] = row;
+        video_ram[0x10 + i * 4 + 2] = row;
+    }
+
+    for (i, row) in REGISTERED_TILE.iter().enumerate() {
+        video_ram[0x190 + i * 2] = *row;
+    }
+
+    // Tile map rows 8 and 9, the ® sits at the end of the top row
+    for i in 0..12 {
+        video_ram[0x1904 + i] = i as u8 + 1;
+        video_ram[0x1924 + i] = i as u8 + 13;
+    }
+    video_ram[0x1910] = 0x19;
+
+    video_ram
 }
 
 impl Rainier {
     pub fn new() -> Result<Self> {
         let mmu = Rc::new(RefCell::new(Mmu::new()?));
         let cpu = Rc::new(RefCell::new(Cpu::new(mmu.clone())));
-        let ppu = Ppu::new(mmu.clone(), cpu.clone());
+        let ppu = Ppu::new(mmu.clone());
+
+        Ok(Rainier { cpu, mmu, ppu, frame_count: 0, recording: None, rewind: None })
+    }
+
+    // Set up the system as it would be after running the boot rom of the given model
+    // The cartridge is loaded first, since the CGB boot ROM leaves a different state for DMG cartridges
+    pub fn boot(&mut self, rom: &Path, model: Model) -> Result<()> {
+        self.mmu.borrow_mut().load_cartridge(rom)?;
+
+        self.set_post_boot_state(model)
+    }
+
+    // Same as boot, but with a ROM that is already in memory
+    pub fn load_rom(&mut self, rom: Vec<u8>, model: Model) -> Result<()> {
+        self.mmu.borrow_mut().load_cartridge_data(rom)?;
+
+        self.set_post_boot_state(model)
+    }
+
+    // Same as load_rom, but the memory the boot ROM doesn't set up is given fixed contents too, for byte-identical starting states
+    // VRAM holds the logo left by the DMG boot ROM, work RAM, OAM and HRAM are cleared
+    pub fn boot_deterministic(&mut self, rom: Vec<u8>) -> Result<()> {
+        self.load_rom(rom, Model::Dmg)?;
+
+        let mut mmu = self.mmu.borrow_mut();
+        let logo: Vec<u8> = HEADER_LOGO.map(|address| mmu.read_byte(address)).collect::<Result<_, _>>()?;
+
+        mmu.write_slice(0x8000, &boot_rom_video_ram(&logo))?;
+        mmu.write_slice(0xC000, &[0; 0x2000])?;
+        mmu.write_slice(0xFE00, &[0; 0xA0])?;
+        mmu.write_slice(0xFF80, &[0; 0x7F])?;
 
-        Ok(Rainier { cpu, mmu, ppu })
+        Ok(())
     }
 
-    // Set up the system as it would be after running the boot rom
-    pub fn boot(&mut self, rom: &Path) -> Result<()> {
+    // Register values from the Pan Docs power up sequence
+    // Per model: the CPU registers, SC, DMA and on the CGB KEY0, KEY1, VBK, HDMA5 and SVBK
+    // Every other IO register, P1, DIV, STAT and BGP included, gets the DMG value on all models. DIV isn't documented for the CGB and SGB
+    // B, C, D and E for a DMG cartridge on the CGB are those of a non-Nintendo licensee, the real boot ROM derives them from the title checksum
+    fn set_post_boot_state(&mut self, model: Model) -> Result<()> {
+        self.select_model(model);
+
         let mut cpu = self.cpu.borrow_mut();
+        let mut mmu = self.mmu.borrow_mut();
+        let cgb_cartridge = mmu.cgb_mode() != CgbMode::Dmg;
+
+        // A, B, C, D, E, H, L and the zero, half carry and carry flags
+        let (a, b, c, d, e, h, l, flags) = match model {
+            Model::Dmg => (0x01, 0x00, 0x13, 0x00, 0xd8, 0x01, 0x4d, (true, true, true)),
+            Model::Cgb if cgb_cartridge => (0x11, 0x00, 0x00, 0xff, 0x56, 0x00, 0x0d, (true, false, false)),
+            Model::Cgb => (0x11, 0x00, 0x00, 0x00, 0x08, 0x00, 0x7c, (true, false, false)),
+            Model::Sgb => (0x01, 0x00, 0x14, 0x00, 0x00, 0xc0, 0x60, (false, false, false)),
+        };
 
         let registers = &mut cpu.registers;
-        registers.set_a(0x01);
-        registers.set_b(0x00);
-        registers.set_c(0x13);
-        registers.set_d(0x00);
-        registers.set_e(0xd8);
-        registers.set_h(0x01);
-        registers.set_l(0x4d);
+        registers.set_a(a);
+        registers.set_b(b);
+        registers.set_c(c);
+        registers.set_d(d);
+        registers.set_e(e);
+        registers.set_h(h);
+        registers.set_l(l);
         registers.set_pc(0x0100);
         registers.set_sp(0xFFFE);
         registers.clear_all_flags();
-        registers.set_zero_flag(true);
-        registers.set_half_carry_flag(true);
-        registers.set_carry_flag(true);
+        registers.set_zero_flag(flags.0);
+        registers.set_half_carry_flag(flags.1);
+        registers.set_carry_flag(flags.2);
 
-        let mut mmu = self.mmu.borrow_mut();
         mmu.set_p1(0xcf);
         mmu.set_sb(0x00);
-        mmu.set_sc(0x7e);
-        mmu.set_div(0xAB);
+        // Bit 1 of SC is the CGB's fast clock select
+        mmu.set_sc(if model == Model::Cgb { 0x7f } else { 0x7e });
+        mmu.set_div_counter(0xAB00);
         mmu.set_tima(0x00);
         mmu.set_tma(0x00);
         mmu.set_tac(0xf8);
         mmu.set_iflag(0xe1);
+        // The APU ignores the other sound registers while it is powered off
+        mmu.set_nr52(0xf1);
         mmu.set_nr10(0x80);
         mmu.set_nr11(0xbf);
         mmu.set_nr12(0xf3);
@@ -97,167 +229,919 @@ impl Rainier {
         mmu.set_nr44(0xbf);
         mmu.set_nr50(0x77);
         mmu.set_nr51(0xf3);
-        mmu.set_nr52(0xf1);
         mmu.set_lcdc(0x91);
         mmu.set_stat(0x85);
         mmu.set_scy(0x00);
         mmu.set_scx(0x00);
         mmu.set_ly(0x00);
         mmu.set_lyc(0x00);
-        mmu.set_dma(0xff);
+        mmu.set_dma(if model == Model::Cgb { 0x00 } else { 0xff });
         mmu.set_bgp(0xfc);
         mmu.set_wy(0x00);
         mmu.set_wx(0x00);
         mmu.set_ie(0x00);
 
+        if model == Model::Cgb {
+            // The boot ROM locks DMG cartridges into compatibility mode through KEY0
+            mmu.set_key0(if cgb_cartridge { 0xc0 } else { 0x04 });
+            mmu.set_key1(0x00);
+            mmu.set_vbk(0x00);
+            mmu.set_hdma5(0xff);
+            mmu.set_svbk(0x00);
+        }
+
         mmu.write_byte(0xFF44, 0)?;
 
-        mmu.load_cartridge(rom)
+        Ok(())
+    }
+
+    // Reset shared by every way of booting, before either the boot ROM or the post-boot state takes over
+    fn select_model(&mut self, model: Model) {
+        self.frame_count = 0;
+
+        let mut mmu = self.mmu.borrow_mut();
+        mmu.set_cgb_core(model == Model::Cgb);
+        mmu.set_sgb_core(model == Model::Sgb);
+    }
+
+    // Run an actual DMG/CGB/SGB boot ROM instead of setting up the post-boot state by hand
+    // The PPU runs along with the CPU, since the boot ROM waits on LY before handing over to the cartridge
+    pub fn boot_with_rom(&mut self, boot_rom: &Path, rom: &Path, model: Model) -> Result<()> {
+        {
+            let mut mmu = self.mmu.borrow_mut();
+            mmu.load_cartridge(rom)?;
+            mmu.load_boot_rom(boot_rom)?;
+        }
+        self.select_model(model);
+        self.cpu.borrow_mut().registers.set_pc(0x0000);
+
+        let mut instructions_count = 0;
+        while self.mmu.borrow().is_boot_rom_mapped() {
+            self.step()?;
+
+            instructions_count += 1;
+            if instructions_count > BOOT_ROM_INSTRUCTION_LIMIT {
+                return Err(anyhow::anyhow!("Boot ROM did not finish, stuck at {:#X}", self.pc()));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Run a single CPU step and catch the PPU and APU up with it, returns the T-cycles taken
+    // Double speed isn't emulated, so there are always 4 T-cycles per M-cycle
+    pub fn step(&mut self) -> Result<u8> {
+        let t_cycles = self.cpu.borrow_mut().emulation_loop()? * 4;
+        self.ppu.emulation_loop(t_cycles)?;
+        self.mmu.borrow_mut().apu_mut().emulation_loop(t_cycles);
+
+        Ok(t_cycles)
+    }
+
+    // Run the CPU and PPU until the start of the next VBlank
+    // With the display off there is no VBlank, so it stops after a frame's worth of cycles instead
+    pub fn run_frame(&mut self) -> Result<()> {
+        if let Some(mut rewind) = self.rewind.take() {
+            let snapshot = rewind.needs_snapshot().then(|| self.snapshot());
+            rewind.record_frame(snapshot, self.mmu.borrow().pressed_buttons());
+            self.rewind = Some(rewind);
+        }
+
+        let completed_frames = self.ppu.completed_frames();
+
+        let mut t_cycles = 0;
+        while t_cycles < FRAME_T_CYCLES && self.ppu.completed_frames() == completed_frames {
+            t_cycles += self.step()? as u32;
+        }
+
+        self.mmu.borrow_mut().apply_game_shark_codes();
+        self.frame_count += 1;
+
+        if let Some(recording) = &mut self.recording {
+            recording.add_frame(self.ppu.framebuffer_rgba())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn run_frames(&mut self, frames: u32) -> Result<()> {
+        (0..frames).try_for_each(|_| self.run_frame())
+    }
+
+    // Replaces the APU along with its sound registers, so it has to be done before booting
+    pub fn set_audio_config(&mut self, config: AudioConfig) {
+        *self.mmu.borrow_mut().apu_mut() = Apu::new(config);
+    }
+
+    // CGB support declared by the loaded cartridge
+    pub fn cgb_mode(&self) -> CgbMode { self.mmu.borrow().cgb_mode() }
+
+    // Frames completed since the ROM was booted
+    pub fn frame_count(&self) -> u64 { self.frame_count }
+
+    // Whether the PPU finished a frame since the last call, frontends only present new frames
+    pub fn take_frame_ready(&mut self) -> bool { self.ppu.take_frame_ready() }
+
+    // Shades (0-3) of the last completed frame, 160x144 row by row. Safe to call mid-frame
+    pub fn framebuffer(&self) -> &[u8] {
+        self.ppu.framebuffer()
+    }
+
+    // Last completed frame as RGBA, in the compatibility palette's colours for a DMG game on the CGB
+    pub fn framebuffer_rgba(&self) -> Vec<u8> {
+        self.ppu.framebuffer_rgba()
+    }
+
+    // Colours given to DMG games on the CGB, GRAYSCALE_PALETTE keeps the DMG shades
+    pub fn set_compatibility_palette(&mut self, palette: CompatibilityPalette) {
+        self.ppu.set_compatibility_palette(palette);
+    }
+
+    // Frame being drawn, complete above current_line and still showing the previous frame below, for raster effect debugging
+    pub fn frame_in_progress(&self) -> &[u8] {
+        self.ppu.frame_in_progress()
+    }
+
+    pub fn current_line(&self) -> u8 { self.ppu.current_line() }
+
+    // Save the current frame as a PNG
+    pub fn screenshot(&self, path: &Path) -> Result<()> {
+        if self.frame_count == 0 {
+            return Err(anyhow::anyhow!("No frame has been rendered yet"));
+        }
+
+        image::save_buffer(path, &self.ppu.framebuffer_rgba(), ppu::LCD_WIDTH as u32, ppu::LCD_HEIGHT as u32, image::ExtendedColorType::Rgba8)
+            .context("Failed to save screenshot")
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            mmu: self.mmu.borrow().clone(),
+            cpu: self.cpu.borrow().snapshot(),
+            ppu: self.ppu.clone(),
+            frame_count: self.frame_count,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        *self.mmu.borrow_mut() = snapshot.mmu.clone();
+        self.cpu.borrow_mut().restore(&snapshot.cpu);
+        self.ppu.restore(&snapshot.ppu);
+        self.frame_count = snapshot.frame_count;
+    }
+
+    // Keep snapshots of the last given seconds so that they can be replayed
+    pub fn enable_rewind(&mut self, seconds: usize) {
+        self.rewind = Some(RewindBuffer::new(seconds));
+    }
+
+    // Rerun the frames kept by the rewind buffer from its oldest snapshot, calling on_frame after each of them
+    // The emulator is back to its current state afterwards. Returns the number of replayed frames
+    pub fn replay_frames(&mut self, mut on_frame: impl FnMut(&Rainier) -> Result<()>) -> Result<usize> {
+        let rewind = self.rewind.take().ok_or_else(|| anyhow::anyhow!("Rewind is not enabled"))?;
+        let Some(oldest) = rewind.oldest() else {
+            self.rewind = Some(rewind);
+            return Ok(0);
+        };
+
+        let current = self.snapshot();
+        let recording = self.recording.take();
+
+        // The restored APU shares the output buffer with the live one, the replay isn't played back
+        self.restore(oldest);
+        self.mmu.borrow_mut().apu_mut().set_output_muted(true);
+        let result = rewind.inputs().try_for_each(|buttons| {
+            self.mmu.borrow_mut().set_pressed_buttons(buttons);
+            self.run_frame()?;
+            on_frame(self)
+        });
+
+        self.restore(&current);
+        self.recording = recording;
+        let frame_count = rewind.frame_count();
+        self.rewind = Some(rewind);
+
+        result.map(|_| frame_count)
+    }
+
+    // Save the frames kept by the rewind buffer to an animated GIF
+    pub fn export_replay(&mut self, path: &Path) -> Result<usize> {
+        let mut recorder = GifRecorder::new(path)?;
+
+        self.replay_frames(|rainier| recorder.add_frame(rainier.ppu.framebuffer_rgba()))?;
+
+        recorder.finish()
+    }
+
+    // Record every following frame to an animated GIF until stop_recording is called
+    pub fn start_recording(&mut self, path: &Path) -> Result<()> {
+        self.recording = Some(GifRecorder::new(path)?);
+
+        Ok(())
+    }
+
+    // Returns the number of recorded frames
+    pub fn stop_recording(&mut self) -> Result<usize> {
+        let recording = self.recording.take().ok_or_else(|| anyhow::anyhow!("Not recording"))?;
+
+        recording.finish()
+    }
+
+    pub fn pc(&self) -> u16 { self.cpu.borrow().registers.pc() }
+
+    // Read a byte as the debugger sees it, VRAM and OAM stay readable while the PPU has them locked
+    pub fn peek(&self, address: u16) -> u8 {
+        self.mmu.borrow().ppu_read_byte(address as usize).unwrap()
+    }
+
+    // Write a byte anywhere in memory, including ROM (see Mmu::poke for the caveats)
+    pub fn poke(&mut self, address: u16, value: u8) {
+        self.mmu.borrow_mut().poke(address as usize, value).unwrap()
+    }
+
+    pub fn apply_patch(&mut self, patch: &[(u16, u8)]) {
+        let mut mmu = self.mmu.borrow_mut();
+        for &(address, value) in patch {
+            mmu.poke(address as usize, value).unwrap();
+        }
+    }
+
+    // Addresses currently holding the given value, for cheat searches
+    pub fn search_memory(&self, value: u8) -> Vec<u16> {
+        self.mmu.borrow().to_vec().iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte == value)
+            .map(|(address, _)| address as u16)
+            .collect()
+    }
+
+    // Add a Game Genie or Game Shark code
+    pub fn add_cheat(&mut self, code: &str) -> Result<()> {
+        let cheat = Cheat::parse(code)?;
+        self.mmu.borrow_mut().add_cheat(cheat);
+
+        Ok(())
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        let mut mmu = self.mmu.borrow_mut();
+        if pressed {
+            mmu.press_button(button);
+        }
+        else {
+            mmu.release_button(button);
+        }
+    }
+}
+
+#[derive(PartialEq, Debug)]
+struct Config {
+    rom: PathBuf,
+    emulation_mode: EmulationMode,
+    // Trace every instruction to exec.log
+    trace: Option<TraceFormat>,
+    // Window scale of the LCD in normal mode
+    scale: u32,
+    // Where serial output is streamed to as it is sent
+    serial: Option<SerialOutput>,
+    // Gameboy Doctor log the CPU is checked against, instruction by instruction
+    reference: Option<PathBuf>,
+    audio: AudioConfig,
+    // Hardware whose boot state is set up
+    model: Model,
+    // Run this boot ROM instead of setting up the post-boot state of the model
+    boot_rom: Option<PathBuf>,
+    // Keep the DMG shades for DMG games on the CGB, instead of the boot ROM's colours
+    grayscale: bool,
+}
+
+#[derive(PartialEq, Debug)]
+enum SerialOutput {
+    Stdout,
+    File(PathBuf),
+}
+
+impl SerialOutput {
+    fn open(&self) -> Result<Box<dyn std::io::Write>> {
+        Ok(match self {
+            SerialOutput::Stdout => Box::new(std::io::stdout()),
+            SerialOutput::File(path) => Box::new(std::fs::File::create(path).with_context(|| format!("Failed to create {}", path.display()))?),
+        })
+    }
+}
+
+const USAGE: &str = "Usage: Rainier <rom> [--normal | --debug[=N]] [--trace[=text|doctor|csv|json]] [--scale=1-6] [--serial[=file]] [--reference=doctor.log] [--sample-rate=Hz] [--audio-buffer=frames] [--model=dmg|cgb|sgb] [--boot-rom=file] [--grayscale]";
+
+const DEFAULT_SCALE: u32 = 2;
+
+// Build the configuration from the command line arguments (including the program name)
+fn parse_args(args: &[String]) -> Result<Config> {
+    let mut rom = None;
+    let mut emulation_mode = EmulationMode::Debug(1);
+    let mut trace = None;
+    let mut scale = DEFAULT_SCALE;
+    let mut serial = None;
+    let mut reference = None;
+    let mut audio = AudioConfig::default();
+    let mut model = Model::default();
+    let mut boot_rom = None;
+    let mut grayscale = false;
+
+    for arg in args.iter().skip(1) {
+        match arg.as_str() {
+            "--normal" => emulation_mode = EmulationMode::Normal,
+            "--debug" => emulation_mode = EmulationMode::Debug(1),
+            flag if flag.starts_with("--debug=") => {
+                let steps = flag["--debug=".len()..].parse::<u32>()
+                    .map_err(|_| anyhow::anyhow!("Invalid debug step count in '{}'\n{}", flag, USAGE))?;
+                emulation_mode = EmulationMode::Debug(steps);
+            }
+            "--trace" => trace = Some(TraceFormat::default()),
+            flag if flag.starts_with("--trace=") => {
+                trace = Some(TraceFormat::from_name(&flag["--trace=".len()..])
+                    .ok_or_else(|| anyhow::anyhow!("Unknown trace format in '{}'\n{}", flag, USAGE))?);
+            }
+            "--grayscale" => grayscale = true,
+            "--serial" => serial = Some(SerialOutput::Stdout),
+            flag if flag.starts_with("--serial=") => serial = Some(SerialOutput::File(PathBuf::from(&flag["--serial=".len()..]))),
+            flag if flag.starts_with("--boot-rom=") => boot_rom = Some(PathBuf::from(&flag["--boot-rom=".len()..])),
+            flag if flag.starts_with("--reference=") => reference = Some(PathBuf::from(&flag["--reference=".len()..])),
+            flag if flag.starts_with("--scale=") => {
+                scale = flag["--scale=".len()..].parse::<u32>().ok().filter(|scale| (1..=6).contains(scale))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid scale in '{}'\n{}", flag, USAGE))?;
+            }
+            flag if flag.starts_with("--sample-rate=") => {
+                audio.sample_rate = flag["--sample-rate=".len()..].parse::<u32>().ok().filter(|rate| (8000..=192000).contains(rate))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid sample rate in '{}'\n{}", flag, USAGE))?;
+            }
+            flag if flag.starts_with("--audio-buffer=") => {
+                audio.buffer_frames = flag["--audio-buffer=".len()..].parse::<usize>().ok().filter(|frames| *frames > 0)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid audio buffer size in '{}'\n{}", flag, USAGE))?;
+            }
+            flag if flag.starts_with("--model=") => {
+                model = Model::from_name(&flag["--model=".len()..])
+                    .ok_or_else(|| anyhow::anyhow!("Unknown model in '{}'\n{}", flag, USAGE))?;
+            }
+            flag if flag.starts_with("--") => return Err(anyhow::anyhow!("Unknown option '{}'\n{}", flag, USAGE)),
+            path if rom.is_none() => rom = Some(PathBuf::from(path)),
+            extra => return Err(anyhow::anyhow!("Unexpected argument '{}'\n{}", extra, USAGE)),
+        }
+    }
+
+    let rom = rom.ok_or_else(|| anyhow::anyhow!("No ROM given\n{}", USAGE))?;
+    if !rom.is_file() {
+        return Err(anyhow::anyhow!("ROM '{}' does not exist\n{}", rom.display(), USAGE));
+    }
+
+    // The debugger owns the terminal, serial output streamed to it would draw over the TUI
+    if cfg!(feature = "frontend") && matches!(emulation_mode, EmulationMode::Debug(_)) && serial == Some(SerialOutput::Stdout) {
+        return Err(anyhow::anyhow!("--serial needs a file in debug mode, e.g. --serial=serial.txt\n{}", USAGE));
+    }
+
+    Ok(Config { rom, emulation_mode, trace, scale, serial, reference, audio, model, boot_rom, grayscale })
+}
+
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().collect();
+    let config = match parse_args(&args) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    };
+
+    run(config)
+}
+
+#[cfg(feature = "frontend")]
+fn run(config: Config) -> Result<()> {
+    let rainier = Rc::new(RefCell::new(Rainier::new()?));
+    crash::install_panic_hook(&rainier.borrow().cpu);
+    rainier.borrow_mut().set_audio_config(config.audio);
+    match &config.boot_rom {
+        Some(boot_rom) => rainier.borrow_mut().boot_with_rom(boot_rom, &config.rom, config.model)?,
+        None => rainier.borrow_mut().boot(&config.rom, config.model)?,
+    }
+    if config.grayscale {
+        rainier.borrow_mut().set_compatibility_palette(compatibility::GRAYSCALE_PALETTE);
+    }
+    if let Some(trace_format) = config.trace {
+        let rainier = rainier.borrow();
+        let mut cpu = rainier.cpu.borrow_mut();
+        cpu.set_trace_format(trace_format);
+        cpu.open_log_file(Path::new("exec.log"))?;
+    }
+    if let Some(serial) = &config.serial {
+        rainier.borrow().cpu.borrow_mut().set_serial_sink(serial.open()?);
+    }
+    if let Some(reference) = &config.reference {
+        rainier.borrow().cpu.borrow_mut().open_reference_log(reference)?;
+    }
+
+    match config.emulation_mode {
+        EmulationMode::Normal => {
+            rainier.borrow_mut().enable_rewind(rewind::REPLAY_SECONDS);
+
+            let event_loop = EventLoop::new()?;
+            event_loop.set_control_flow(ControlFlow::Poll);
+
+            let mut app = LCD::new(rainier.clone(), config.scale);
+            app.set_key_bindings(load_key_bindings()?);
+            #[cfg(feature = "audio")]
+            app.start_audio();
+            event_loop.run_app(&mut app)?;
+        }
+        EmulationMode::Debug(steps) => {
+            // Labels for the disassembly are picked up from a .sym file next to the ROM
+            let symbols = config.rom.with_extension("sym");
+            if symbols.exists() {
+                rainier.borrow().cpu.borrow_mut().load_symbols(&symbols)?;
+            }
+
+            let mut terminal = ratatui::init();
+            let mut debugger = App::new(rainier.clone());
+            debugger.set_key_bindings(load_key_bindings()?);
+
+            execute!(stdout(), EnableMouseCapture)?;
+
+            while !debugger.exit {
+                debugger.run(&mut terminal)?;
+                // Long Runs go in slices with a redraw in between
+                debugger.continue_run();
+
+                let mut rainier = rainier.borrow_mut();
+
+                if let Some(requested_action) = &debugger.requested_action {
+                    // A CPU fault halts the emulation and is shown in the debugger instead of ending the session
+                    let result = match requested_action {
+                        Action::NextFrame => {
+                            debugger.last_hit_breakpoint = None;
+                            rainier.run_frames(1)
+                        }
+                        Action::RunFrames => {
+                            debugger.last_hit_breakpoint = None;
+                            rainier.run_frames(steps)
+                        }
+                        Action::Trace | Action::StepOver => {
+                            debugger.last_hit_breakpoint = None;
+                            (0..steps).try_for_each(|_| rainier.step().map(|_| ()))
+                        }
+                        Action::Run => {
+                            debugger.start_run();
+                            Ok(())
+                        }
+                    };
+                    debugger.requested_action = None;
+
+                    if let Err(error) = result {
+                        debugger.report_fault(&error);
+                    }
+                }
+            }
+
+            ratatui::restore();
+        }
     }
+
+    Ok(())
 }
 
-// fn main() -> Result<()> {
-
-
-
-    // let rainier = Rc::new(RefCell::new(Rainier::new()?));
-    // rainier.borrow_mut().boot(Path::new("roms/cpu_instrs/individual/08-misc instrs.gb"))?;
-    //
-    // let mut terminal = ratatui::init();
-    // let mut debugger = App::new(rainier.clone());
-    //
-    // let emulation_mode = match env::var("mode").unwrap_or(String::from("debug")).as_str() {
-    //     "normal" => EmulationMode::Normal,
-    //     _ => EmulationMode::Debug(1),
-    // };
-    //
-    // if emulation_mode == EmulationMode::Normal {
-    //     loop {
-    //         let m_cycles = rainier.borrow_mut().cpu.borrow_mut().emulation_loop()?;
-    //         rainier.borrow_mut().ppu.emulation_loop(m_cycles * 4)?;
-    //     }
-    // }
-    // else {
-    //     execute!(stdout(), EnableMouseCapture)?;
-    //
-    //     while !debugger.exit {
-    //         debugger.run(&mut terminal)?;
-    //
-    //         let mut rainier = rainier.borrow_mut();
-    //         let mut cpu = rainier.cpu.borrow_mut();
-    //
-    //         if let Some(requested_action) = &debugger.requested_action {
-    //             match requested_action {
-    //                 Action::Trace | Action::StepOver => {
-    //                     cpu.emulation_loop()?;
-    //                     debugger.requested_action = None;
-    //                     debugger.last_hit_breakpoint = None;
-    //                 }
-    //                 Action::Run => {
-    //                     while !debugger.breakpoints.contains(&cpu.registers.pc()) || debugger.last_hit_breakpoint.map_or(false, |breakpoint| cpu.registers.pc() == breakpoint) {
-    //                         cpu.emulation_loop()?;
-    //                         debugger.last_hit_breakpoint = None;
-    //                     }
-    //
-    //                     debugger.requested_action = None;
-    //                     debugger.last_hit_breakpoint = Some(cpu.registers.pc());
-    //                 }
-    //             }
-    //         }
-    //     }
-    //
-    //     ratatui::restore();
-    // }
-
-    // Ok(())
-// }
-
-fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.set_control_flow(ControlFlow::Poll);
-
-    let mut app = LCD::default();
-    event_loop.run_app(&mut app).unwrap();
+#[cfg(feature = "frontend")]
+fn load_key_bindings() -> Result<KeyBindings> {
+    let path = Path::new(KEY_BINDINGS_FILE);
+    if path.exists() { KeyBindings::load(path) } else { Ok(KeyBindings::default()) }
 }
 
+// Without a frontend there is nothing to display, so just run the ROM and forward its serial output
+#[cfg(not(feature = "frontend"))]
+fn run(config: Config) -> Result<()> {
+    let mut core = Core::new()?;
+    crash::install_panic_hook(&core.cpu);
+    core.set_audio_config(config.audio);
+    match &config.boot_rom {
+        Some(boot_rom) => core.boot_with_rom(boot_rom, &config.rom, config.model)?,
+        None => core.boot(&config.rom, config.model)?,
+    }
+    if config.grayscale {
+        core.set_compatibility_palette(compatibility::GRAYSCALE_PALETTE);
+    }
+    if let Some(trace_format) = config.trace {
+        let mut cpu = core.cpu.borrow_mut();
+        cpu.set_trace_format(trace_format);
+        cpu.open_log_file(Path::new("exec.log"))?;
+    }
+    let serial = config.serial.unwrap_or(SerialOutput::Stdout);
+    core.cpu.borrow_mut().set_serial_sink(serial.open()?);
+    if let Some(reference) = &config.reference {
+        core.cpu.borrow_mut().open_reference_log(reference)?;
+    }
+
+    loop {
+        core.run_frame()?;
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // Far more than any of the test ROMs need, a ROM still running after this is stuck
+    const TEST_ROM_INSTRUCTION_LIMIT: usize = 200_000_000;
+
+    // Combined suites print a summary once every sub-test is done, each individual ROM just prints Passed
+    fn expected_pass_string(rom: &str) -> &'static str {
+        match rom {
+            "roms/cpu_instrs/cpu_instrs.gb" | "roms/mem_timing/mem_timing.gb" => "Passed all tests",
+            _ => "Passed",
+        }
+    }
+
     fn run_test_rom(rom: &str) {
-        let rainier = Rc::new(RefCell::new(Rainier::new().unwrap()));
-        rainier.borrow_mut().boot(Path::new(rom)).unwrap();
-
-        loop {
-            let m_cycles = rainier.borrow_mut().cpu.borrow_mut().emulation_loop().unwrap();
-            //rainier.borrow_mut().ppu.emulation_loop(cycles)?;
-
-            let serial_log = rainier.borrow().cpu.borrow().serial_log.clone();
-            if serial_log.contains("Failed") {
-                println!("{}", serial_log.trim());
-                assert!(false);
-                break;
-            } else if serial_log.contains("Passed") {
-                println!("{}", serial_log.trim());
-                assert!(true);
-                break;
+        if !Path::new(rom).exists() {
+            println!("Skipping, {} not found", rom);
+            return;
+        }
+
+        let mut rainier = Rainier::new().unwrap();
+        rainier.boot(Path::new(rom), Model::Dmg).unwrap();
+
+        run_until_verdict(&mut rainier, rom, TEST_ROM_INSTRUCTION_LIMIT);
+    }
+
+    // Run until the ROM reports its pass string or Failed over serial, panicking on failure or once the instruction budget runs out
+    fn run_until_verdict(rainier: &mut Rainier, rom: &str, instruction_limit: usize) {
+        let expected = expected_pass_string(rom);
+        for _ in 0..instruction_limit {
+            if let Err(error) = rainier.step() {
+                panic!("{} failed: {:#}\n{}", rom, error, rainier.cpu.borrow().serial_log.trim());
+            }
+
+            let cpu = rainier.cpu.borrow();
+            if cpu.serial_log.contains("Failed") {
+                panic!("{} failed:\n{}", rom, cpu.serial_log.trim());
+            } else if cpu.serial_log.contains(expected) {
+                println!("{}", cpu.serial_log.trim());
+                return;
             }
         }
+
+        let cpu = rainier.cpu.borrow();
+        panic!("{} did not finish within {} instructions, last PC={:#06X}\n{}", rom, instruction_limit, cpu.registers.pc(), cpu.serial_log.trim());
+    }
+
+    // Rainier running a DMG cartridge that is just JR -2 at the entry point, which keeps the CPU spinning in place
+    fn spinning_rainier() -> Rainier {
+        let mut rom = vec![0; 0x8000];
+        rom[0x100] = 0x18;
+        rom[0x101] = 0xFE;
+
+        let mut rainier = Rainier::new().unwrap();
+        rainier.load_rom(rom, Model::Dmg).unwrap();
+        rainier
     }
 
     #[test]
-    fn cpu_instrs_01() {
-        run_test_rom("roms/cpu_instrs/individual/01-special.gb");
+    #[should_panic(expected = "did not finish within 10000 instructions, last PC=0x0100")]
+    fn stuck_test_rom_times_out() {
+        // Spinning forever, without ever printing a verdict
+        let mut rainier = spinning_rainier();
+
+        run_until_verdict(&mut rainier, "stub", 10_000);
     }
 
     #[test]
-    fn cpu_instrs_02() {
-        run_test_rom("roms/cpu_instrs/individual/02-interrupts.gb");
+    fn headless_core_runs_frame() {
+        let mut core: Core = spinning_rainier();
+
+        core.set_button(Button::Start, true);
+        core.run_frame().unwrap();
+
+        assert_eq!(core.cpu.borrow().registers.pc(), 0x100);
+        assert!(core.mmu.borrow().is_button_pressed(Button::Start));
+        assert_eq!(core.framebuffer().len(), ppu::LCD_WIDTH * ppu::LCD_HEIGHT);
     }
 
     #[test]
-    fn cpu_instrs_03() {
-        run_test_rom("roms/cpu_instrs/individual/03-op sp,hl.gb");
+    fn post_boot_state_follows_model() {
+        // CGB compatible header
+        let mut rom = vec![0; 0x8000];
+        rom[0x143] = 0x80;
+
+        let mut cgb = Rainier::new().unwrap();
+        cgb.load_rom(rom.clone(), Model::Cgb).unwrap();
+        let registers = cgb.cpu.borrow().registers.clone();
+        assert_eq!((registers.af(), registers.bc(), registers.de(), registers.hl()), (0x1180, 0x0000, 0xFF56, 0x000D));
+        let mmu = cgb.mmu.borrow();
+        assert!(mmu.is_cgb_mode());
+        assert_eq!(mmu.dma(), 0x00);
+        assert_eq!(mmu.sc(), 0x7F);
+
+        let mut dmg = Rainier::new().unwrap();
+        dmg.load_rom(rom.clone(), Model::Dmg).unwrap();
+        let registers = dmg.cpu.borrow().registers.clone();
+        assert_eq!((registers.af(), registers.bc(), registers.de(), registers.hl()), (0x01B0, 0x0013, 0x00D8, 0x014D));
+        let mmu = dmg.mmu.borrow();
+        assert!(!mmu.is_cgb_mode());
+        assert_eq!(mmu.dma(), 0xFF);
+        assert_eq!(mmu.sc(), 0x7E);
+
+        // DMG cartridges on the CGB start in compatibility mode, with their own register values
+        rom[0x143] = 0x00;
+        let mut compatibility = Rainier::new().unwrap();
+        compatibility.load_rom(rom.clone(), Model::Cgb).unwrap();
+        assert_eq!(compatibility.cpu.borrow().registers.hl(), 0x007C);
+        assert!(compatibility.mmu.borrow().is_dmg_compatibility_mode());
+
+        let mut sgb = Rainier::new().unwrap();
+        sgb.load_rom(rom, Model::Sgb).unwrap();
+        let registers = sgb.cpu.borrow().registers.clone();
+        assert_eq!((registers.af(), registers.bc(), registers.de(), registers.hl()), (0x0100, 0x0014, 0x0000, 0xC060));
     }
 
     #[test]
-    fn cpu_instrs_04() {
-        run_test_rom("roms/cpu_instrs/individual/04-op r,imm.gb");
+    fn run_one_frame_advances_frame_count() {
+        let mut rainier = spinning_rainier();
+
+        rainier.run_frames(2).unwrap();
+        let frame_count = rainier.frame_count();
+        let completed_frames = rainier.ppu.completed_frames();
+        rainier.run_frames(1).unwrap();
+
+        // The PPU has to have drawn the frame, not just the counter moved on
+        assert_eq!(rainier.frame_count(), frame_count + 1);
+        assert_eq!(rainier.ppu.completed_frames(), completed_frames + 1);
     }
 
     #[test]
-    fn cpu_instrs_05() {
-        run_test_rom("roms/cpu_instrs/individual/05-op rp.gb");
+    fn framebuffer_holds_last_completed_frame() {
+        let mut rainier = spinning_rainier();
+
+        rainier.run_frame().unwrap();
+        let frame = rainier.framebuffer().to_vec();
+        assert_eq!(frame.len(), ppu::LCD_WIDTH * ppu::LCD_HEIGHT);
+
+        // Halfway through the next frame, drawn with every colour black
+        rainier.poke(0xFF47, 0xFF);
+        while rainier.peek(0xFF44) != 72 {
+            rainier.step().unwrap();
+        }
+        assert_eq!(rainier.framebuffer(), frame);
+
+        rainier.run_frame().unwrap();
+        assert!(rainier.framebuffer().iter().all(|shade| *shade == 3));
     }
 
     #[test]
-    fn cpu_instrs_06() {
-        run_test_rom("roms/cpu_instrs/individual/06-ld r,r.gb");
+    fn run_frame_reaches_vblank() {
+        let mut rainier = spinning_rainier();
+        rainier.mmu.borrow_mut().set_iflag(0);
+
+        rainier.run_frame().unwrap();
+
+        assert_ne!(rainier.mmu.borrow().iflag() & (1 << interrupts::Interrupt::VBlank as u8), 0);
     }
 
     #[test]
-    fn cpu_instrs_07() {
-        run_test_rom("roms/cpu_instrs/individual/07-jr,jp,call,ret,rst.gb");
+    fn replay_reproduces_frames() {
+        let mut rainier = Rainier::new().unwrap();
+        // Count in A, store it at C000 and loop
+        let mut rom = vec![0; 0x8000];
+        rom[0x100..0x106].copy_from_slice(&[0x3C, 0xEA, 0x00, 0xC0, 0x18, 0xFA]);
+        rainier.load_rom(rom, Model::Dmg).unwrap();
+        rainier.enable_rewind(1);
+
+        let mut frames = Vec::new();
+        for frame in 0..25 {
+            rainier.set_button(Button::A, frame >= 12);
+            rainier.run_frame().unwrap();
+            frames.push((rainier.framebuffer().to_vec(), rainier.peek(0xC000), rainier.mmu.borrow().pressed_buttons()));
+        }
+        let state = (rainier.pc(), rainier.peek(0xC000), rainier.frame_count());
+
+        let mut replayed = Vec::new();
+        let frame_count = rainier.replay_frames(|rainier| {
+            replayed.push((rainier.framebuffer().to_vec(), rainier.peek(0xC000), rainier.mmu.borrow().pressed_buttons()));
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(frame_count, 25);
+        assert!(replayed == frames);
+        assert_eq!((rainier.pc(), rainier.peek(0xC000), rainier.frame_count()), state);
     }
 
     #[test]
-    fn cpu_instrs_08() {
-        run_test_rom("roms/cpu_instrs/individual/08-misc instrs.gb");
+    fn poke_peek_and_search() {
+        let mut rainier = Rainier::new().unwrap();
+        rainier.load_rom(vec![0; 0x8000], Model::Dmg).unwrap();
+
+        rainier.poke(0xC123, 0xA5);
+        assert_eq!(rainier.peek(0xC123), 0xA5);
+        // Echo RAM mirrors work RAM
+        assert_eq!(rainier.search_memory(0xA5), vec![0xC123, 0xE123]);
+
+        rainier.apply_patch(&[(0x0150, 0x3C), (0xD000, 0x3C)]);
+        assert_eq!(rainier.peek(0x0150), 0x3C);
+        assert_eq!(rainier.peek(0xD000), 0x3C);
     }
 
     #[test]
-    fn cpu_instrs_09() {
-        run_test_rom("roms/cpu_instrs/individual/09-op r,r.gb");
+    fn peek_and_poke_ignore_ppu_lock() {
+        let mut rainier = Rainier::new().unwrap();
+        rainier.load_rom(vec![0; 0x8000], Model::Dmg).unwrap();
+
+        // Display on and the PPU drawing, VRAM and OAM are locked for the CPU
+        rainier.mmu.borrow_mut().set_lcdc(0x91);
+        rainier.mmu.borrow_mut().set_stat(0x83);
+
+        rainier.poke(0x8010, 0x5A);
+        rainier.poke(0xFE00, 0x42);
+        assert_eq!(rainier.peek(0x8010), 0x5A);
+        assert_eq!(rainier.peek(0xFE00), 0x42);
+        assert_eq!(rainier.mmu.borrow().read_byte(0x8010).unwrap(), 0xFF);
     }
 
     #[test]
-    fn cpu_instrs_10() {
-        run_test_rom("roms/cpu_instrs/individual/10-bit ops.gb");
+    fn boot_deterministic_is_reproducible() {
+        let mut rom = vec![0; 0x8000];
+        rom[0x104] = 0xCE;
+        rom[0x105] = 0xED;
+
+        let mut first = Rainier::new().unwrap();
+        first.boot_deterministic(rom.clone()).unwrap();
+
+        // Memory left over from a previous game is overwritten as well
+        let mut second = Rainier::new().unwrap();
+        second.load_rom(vec![0; 0x8000], Model::Dmg).unwrap();
+        second.apply_patch(&[(0x8000, 0x42), (0xC123, 0x42), (0xFE00, 0x42), (0xFF80, 0x42)]);
+        second.boot_deterministic(rom).unwrap();
+
+        assert_eq!(first.mmu.borrow().to_vec(), second.mmu.borrow().to_vec());
+
+        // 0xCE becomes rows 0xF0 (1100) and 0xFC (1110) of tile 1
+        assert_eq!(first.peek(0x8010), 0xF0);
+        assert_eq!(first.peek(0x8012), 0xF0);
+        assert_eq!(first.peek(0x8014), 0xFC);
+        assert_eq!(first.peek(0x8018), 0xFC);
+        assert_eq!(first.peek(0x9904), 0x01);
+        assert_eq!(first.peek(0x9910), 0x19);
     }
 
     #[test]
-    fn cpu_instrs_11() {
-        run_test_rom("roms/cpu_instrs/individual/11-op a,(hl).gb");
+    fn game_shark_code_applied_each_frame() {
+        let mut rainier = spinning_rainier();
+
+        rainier.add_cheat("010238CD").unwrap();
+        rainier.run_frame().unwrap();
+        assert_eq!(rainier.peek(0xCD38), 0x02);
+
+        rainier.poke(0xCD38, 0x00);
+        rainier.run_frame().unwrap();
+        assert_eq!(rainier.peek(0xCD38), 0x02);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn screenshot_after_frame() {
+        let mut rainier = spinning_rainier();
+
+        let path = std::env::temp_dir().join(format!("rainier_screenshot_{}.png", std::process::id()));
+        assert!(rainier.screenshot(&path).is_err());
+
+        rainier.run_frame().unwrap();
+        rainier.screenshot(&path).unwrap();
+
+        let screenshot = image::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((screenshot.width(), screenshot.height()), (160, 144));
+    }
+
+    #[test]
+    fn record_gif() {
+        let mut rainier = spinning_rainier();
+
+        let path = std::env::temp_dir().join(format!("rainier_recording_{}.gif", std::process::id()));
+        rainier.start_recording(&path).unwrap();
+        for _ in 0..7 {
+            rainier.run_frame().unwrap();
+        }
+        assert_eq!(rainier.stop_recording().unwrap(), 7);
+        assert!(rainier.stop_recording().is_err());
+
+        let mut decoder = gif::DecodeOptions::new().read_info(std::io::BufReader::new(std::fs::File::open(&path).unwrap())).unwrap();
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), (160, 144));
+            delays.push(frame.delay);
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        // Every other frame, each shown for two emulated frames
+        assert_eq!(delays, vec![3, 4, 3, 3]);
+    }
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_to_config() {
+        let rom = env::current_exe().unwrap();
+        let rom_str = rom.to_str().unwrap();
+
+        assert_eq!(parse_args(&args(&["rainier", rom_str])).unwrap(), Config { rom: rom.clone(), emulation_mode: EmulationMode::Debug(1), trace: None, scale: DEFAULT_SCALE, serial: None, reference: None, audio: AudioConfig::default(), model: Model::Dmg, boot_rom: None, grayscale: false });
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--normal"])).unwrap().emulation_mode, EmulationMode::Normal);
+        assert_eq!(parse_args(&args(&["rainier", "--debug=20", rom_str])).unwrap().emulation_mode, EmulationMode::Debug(20));
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--trace"])).unwrap().trace, Some(TraceFormat::Text));
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--trace=json"])).unwrap().trace, Some(TraceFormat::Json));
+
+        assert!(parse_args(&args(&["rainier"])).is_err());
+        assert!(parse_args(&args(&["rainier", "does/not/exist.gb"])).is_err());
+        assert!(parse_args(&args(&["rainier", rom_str, "--debug=many"])).is_err());
+        assert!(parse_args(&args(&["rainier", rom_str, "--fast"])).is_err());
+        assert!(parse_args(&args(&["rainier", rom_str, "--trace=xml"])).is_err());
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--scale=4"])).unwrap().scale, 4);
+        assert!(parse_args(&args(&["rainier", rom_str, "--scale=7"])).is_err());
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--normal", "--serial"])).unwrap().serial, Some(SerialOutput::Stdout));
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--serial"])).is_err(), cfg!(feature = "frontend"));
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--serial=out.txt"])).unwrap().serial, Some(SerialOutput::File(PathBuf::from("out.txt"))));
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--reference=doctor.log"])).unwrap().reference, Some(PathBuf::from("doctor.log")));
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--sample-rate=44100", "--audio-buffer=1024"])).unwrap().audio, AudioConfig { sample_rate: 44100, buffer_frames: 1024 });
+        assert!(parse_args(&args(&["rainier", rom_str, "--sample-rate=fast"])).is_err());
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--model=cgb"])).unwrap().model, Model::Cgb);
+        assert!(parse_args(&args(&["rainier", rom_str, "--model=gba"])).is_err());
+        assert_eq!(parse_args(&args(&["rainier", rom_str, "--boot-rom=dmg_boot.bin"])).unwrap().boot_rom, Some(PathBuf::from("dmg_boot.bin")));
+        assert!(parse_args(&args(&["rainier", rom_str, "--grayscale"])).unwrap().grayscale);
+    }
+
+    #[test]
+    fn boot_rom_runs_until_unmapped() {
+        // Turn the LCD on, wait for LY to reach 0x90 and unmap the boot ROM, like the end of the DMG one
+        let mut boot_rom = vec![0; 0x100];
+        boot_rom[..0x0E].copy_from_slice(&[
+            0x3E, 0x91, 0xE0, 0x40, // LD A,0x91; LDH (0x40),A
+            0xF0, 0x44, 0xFE, 0x90, 0x20, 0xFA, // LDH A,(0x44); CP 0x90; JR NZ,-6
+            0x3E, 0x01, 0xE0, 0x50, // LD A,0x01; LDH (0x50),A
+        ]);
+        let mut rom = vec![0; 0x8000];
+        rom[0x0E] = 0x42;
+
+        let directory = std::env::temp_dir();
+        let boot_rom_path = directory.join(format!("rainier_boot_rom_{}.bin", std::process::id()));
+        let rom_path = directory.join(format!("rainier_boot_rom_cartridge_{}.gb", std::process::id()));
+        std::fs::write(&boot_rom_path, boot_rom).unwrap();
+        std::fs::write(&rom_path, rom).unwrap();
+
+        let mut rainier = spinning_rainier();
+        rainier.run_frame().unwrap();
+        let result = rainier.boot_with_rom(&boot_rom_path, &rom_path, Model::Sgb);
+        std::fs::remove_file(&boot_rom_path).unwrap();
+        std::fs::remove_file(&rom_path).unwrap();
+        result.unwrap();
+
+        assert!(!rainier.mmu.borrow().is_boot_rom_mapped());
+        assert_eq!((rainier.pc(), rainier.peek(0xFF44)), (0x0E, 0x90));
+        // The cartridge shows through once the boot ROM is gone
+        assert_eq!(rainier.peek(0x0E), 0x42);
+        assert_eq!(rainier.frame_count(), 0);
+    }
+
+    // Declares the blargg test ROM list and generates one test per entry
+    macro_rules! test_roms {
+        ($($name:ident => $path:literal,)*) => {
+            const TEST_ROMS: &[&str] = &[$($path),*];
+
+            $(
+                #[test]
+                fn $name() {
+                    run_test_rom($path);
+                }
+            )*
+        };
+    }
+
+    test_roms! {
+        cpu_instrs_01 => "roms/cpu_instrs/individual/01-special.gb",
+        cpu_instrs_02 => "roms/cpu_instrs/individual/02-interrupts.gb",
+        cpu_instrs_03 => "roms/cpu_instrs/individual/03-op sp,hl.gb",
+        cpu_instrs_04 => "roms/cpu_instrs/individual/04-op r,imm.gb",
+        cpu_instrs_05 => "roms/cpu_instrs/individual/05-op rp.gb",
+        cpu_instrs_06 => "roms/cpu_instrs/individual/06-ld r,r.gb",
+        cpu_instrs_07 => "roms/cpu_instrs/individual/07-jr,jp,call,ret,rst.gb",
+        cpu_instrs_08 => "roms/cpu_instrs/individual/08-misc instrs.gb",
+        cpu_instrs_09 => "roms/cpu_instrs/individual/09-op r,r.gb",
+        cpu_instrs_10 => "roms/cpu_instrs/individual/10-bit ops.gb",
+        cpu_instrs_11 => "roms/cpu_instrs/individual/11-op a,(hl).gb",
+        cpu_instrs => "roms/cpu_instrs/cpu_instrs.gb",
+        instr_timing => "roms/instr_timing/instr_timing.gb",
+        mem_timing => "roms/mem_timing/mem_timing.gb",
+        halt_bug => "roms/halt_bug.gb",
+    }
+
+    #[test]
+    fn expected_pass_strings() {
+        assert_eq!(expected_pass_string("roms/cpu_instrs/individual/01-special.gb"), "Passed");
+        assert_eq!(expected_pass_string("roms/cpu_instrs/cpu_instrs.gb"), "Passed all tests");
+        assert_eq!(expected_pass_string("roms/mem_timing/mem_timing.gb"), "Passed all tests");
+        assert!(TEST_ROMS.iter().all(|rom| rom.starts_with("roms/") && rom.ends_with(".gb")));
+    }
+}
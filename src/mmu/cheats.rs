@@ -0,0 +1,103 @@
+use anyhow::Result;
+use crate::mmu::Mmu;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Cheat {
+    // Replaces a ROM byte when it is read, optionally only when it holds the compare value
+    GameGenie { address: u16, value: u8, compare: Option<u8> },
+    // Writes a RAM byte every frame
+    GameShark { bank: u8, address: u16, value: u8 },
+}
+
+impl Cheat {
+    // Game Genie codes are ABC-DEF or ABC-DEF-GHI, Game Shark codes are 8 hex digits TTVVLLHH
+    pub fn parse(code: &str) -> Result<Self> {
+        let digits: Vec<u8> = code.chars()
+            .filter(|character| *character != '-')
+            .map(|character| character.to_digit(16).map(|digit| digit as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| anyhow::anyhow!("Invalid cheat code '{}'", code))?;
+
+        match digits.len() {
+            6 | 9 => {
+                let value = digits[0] << 4 | digits[1];
+                let address = ((digits[5] ^ 0xF) as u16) << 12 | (digits[2] as u16) << 8 | (digits[3] as u16) << 4 | digits[4] as u16;
+                // The compare byte is stored as G and I (H is unused), rotated left by 2 and XORed with 0xBA
+                let compare = (digits.len() == 9).then(|| (digits[6] << 4 | digits[8]).rotate_right(2) ^ 0xBA);
+
+                Ok(Cheat::GameGenie { address, value, compare })
+            }
+            8 if !code.contains('-') => {
+                let byte = |i: usize| digits[2 * i] << 4 | digits[2 * i + 1];
+                let address = (byte(3) as u16) << 8 | byte(2) as u16;
+
+                Ok(Cheat::GameShark { bank: byte(0), address, value: byte(1) })
+            }
+            _ => Err(anyhow::anyhow!("Invalid cheat code '{}'", code)),
+        }
+    }
+}
+
+impl Mmu {
+    pub fn add_cheat(&mut self, cheat: Cheat) {
+        self.cheats.push(cheat);
+    }
+
+    pub fn clear_cheats(&mut self) {
+        self.cheats.clear();
+    }
+
+    // Value to return instead of the ROM byte at the given address, if a Game Genie code applies
+    pub(super) fn game_genie_value(&self, address: usize, original: u8) -> Option<u8> {
+        self.cheats.iter().find_map(|cheat| match *cheat {
+            Cheat::GameGenie { address: cheat_address, value, compare }
+                if cheat_address as usize == address && compare.is_none_or(|compare| compare == original) => Some(value),
+            _ => None,
+        })
+    }
+
+    // Game Shark codes are reapplied once per frame
+    pub fn apply_game_shark_codes(&mut self) {
+        for i in 0..self.cheats.len() {
+            if let Cheat::GameShark { address, value, .. } = self.cheats[i] {
+                let _ = self.write_byte(address as usize, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_game_genie() {
+        assert_eq!(Cheat::parse("00A-17B-C49").unwrap(), Cheat::GameGenie { address: 0x4A17, value: 0x00, compare: Some(0xC8) });
+        assert_eq!(Cheat::parse("3EA-17B").unwrap(), Cheat::GameGenie { address: 0x4A17, value: 0x3E, compare: None });
+        assert!(Cheat::parse("00A-17B-C4").is_err());
+        assert!(Cheat::parse("00A-17B-X49").is_err());
+    }
+
+    #[test]
+    fn parse_game_shark() {
+        assert_eq!(Cheat::parse("010238CD").unwrap(), Cheat::GameShark { bank: 0x01, address: 0xCD38, value: 0x02 });
+    }
+
+    #[test]
+    fn game_genie_patches_rom_reads() {
+        let mut mmu = Mmu::new().unwrap();
+        let mut rom = vec![0; 0x8000];
+        rom[0x4A17] = 0xC8;
+        rom[0x0150] = 0x12;
+        mmu.load_cartridge_data(rom).unwrap();
+
+        mmu.add_cheat(Cheat::parse("00A-17B-C49").unwrap());
+        // Only applies while the original byte matches the compare value
+        mmu.add_cheat(Cheat::GameGenie { address: 0x0150, value: 0x34, compare: Some(0x99) });
+        assert_eq!(mmu.read_byte(0x4A17).unwrap(), 0x00);
+        assert_eq!(mmu.read_byte(0x0150).unwrap(), 0x12);
+
+        mmu.clear_cheats();
+        assert_eq!(mmu.read_byte(0x4A17).unwrap(), 0xC8);
+    }
+}
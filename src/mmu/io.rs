@@ -1,4 +1,6 @@
-use crate::mmu::Mmu;
+use crate::cpu::interrupts::Interrupt;
+use crate::mmu::{Mmu, DMA_ADDRESS, STAT_ADDRESS};
+use crate::mmu::MemoryRegion::IO;
 
 impl Mmu {
     // --- Joypad $FF00 (Mixed) ---
@@ -29,6 +31,12 @@ impl Mmu {
     pub fn iflag(&self) -> u8 { self.read_byte(0xFF0F).unwrap() }
     pub fn set_iflag(&mut self, val: u8) { self.write_byte(0xFF0F, val).unwrap() }
 
+    // Set the IF bit of an interrupt, the CPU services it once it is enabled in IE
+    pub fn request_interrupt(&mut self, interrupt: Interrupt) {
+        let iflag = self.iflag();
+        self.set_iflag(iflag | (1 << interrupt as u8));
+    }
+
     pub fn ie(&self) -> u8 { self.read_byte(0xFFFF).unwrap() }
     pub fn set_ie(&mut self, val: u8) { self.write_byte(0xFFFF, val).unwrap() }
 
@@ -106,7 +114,8 @@ impl Mmu {
     pub fn set_lcdc(&mut self, val: u8) { self.write_byte(0xFF40, val).unwrap() }
 
     pub fn stat(&self) -> u8 { self.read_byte(0xFF41).unwrap() }
-    pub fn set_stat(&mut self, val: u8) { self.write_byte(0xFF41, val).unwrap() }
+    // Sets the PPU owned mode and coincidence bits too, CPU writes go through write_byte
+    pub fn set_stat(&mut self, val: u8) { self.io[STAT_ADDRESS - IO as usize] = val }
 
     pub fn scy(&self) -> u8 { self.read_byte(0xFF42).unwrap() }
     pub fn set_scy(&mut self, val: u8) { self.write_byte(0xFF42, val).unwrap() }
@@ -122,7 +131,8 @@ impl Mmu {
     pub fn set_lyc(&mut self, val: u8) { self.write_byte(0xFF45, val).unwrap() }
 
     pub fn dma(&self) -> u8 { self.read_byte(0xFF46).unwrap() }
-    pub fn set_dma(&mut self, val: u8) { self.write_byte(0xFF46, val).unwrap() }
+    // Doesn't start a transfer, CPU writes go through write_byte
+    pub fn set_dma(&mut self, val: u8) { self.io[DMA_ADDRESS - IO as usize] = val }
 
     pub fn bgp(&self) -> u8 { self.read_byte(0xFF47).unwrap() }
     pub fn set_bgp(&mut self, val: u8) { self.write_byte(0xFF47, val).unwrap() }
@@ -0,0 +1,90 @@
+use crate::cpu::interrupts::Interrupt;
+use crate::mmu::Mmu;
+
+// P1 bit 4 low selects the d-pad, bit 5 low selects the action buttons
+const DIRECTIONS_SELECT_BIT: u8 = 1 << 4;
+const ACTIONS_SELECT_BIT: u8 = 1 << 5;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Button {
+    Right,
+    Left,
+    Up,
+    Down,
+    A,
+    B,
+    Select,
+    Start,
+}
+
+impl Button {
+    pub const VALUES: [Self; 8] = [Self::Right, Self::Left, Self::Up, Self::Down, Self::A, Self::B, Self::Select, Self::Start];
+
+    // Bit of the button in the internal state, the d-pad uses the lower nibble and the action buttons the upper one
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl Mmu {
+    pub fn press_button(&mut self, button: Button) {
+        if !self.is_button_pressed(button) {
+            self.pressed_buttons |= button.mask();
+
+            self.request_interrupt(Interrupt::Joypad);
+        }
+    }
+
+    pub fn release_button(&mut self, button: Button) {
+        self.pressed_buttons &= !button.mask();
+    }
+
+    pub fn is_button_pressed(&self, button: Button) -> bool {
+        self.pressed_buttons & button.mask() != 0
+    }
+
+    // Every held button as a bit mask, for recording and replaying input
+    pub fn pressed_buttons(&self) -> u8 { self.pressed_buttons }
+    pub fn set_pressed_buttons(&mut self, val: u8) { self.pressed_buttons = val }
+
+    // Value read back from P1, the lower nibble is active low for every selected group
+    pub(super) fn joypad_state(&self, select: u8) -> u8 {
+        let mut pressed = 0;
+        if select & DIRECTIONS_SELECT_BIT == 0 {
+            pressed |= self.pressed_buttons & 0x0F;
+        }
+        if select & ACTIONS_SELECT_BIT == 0 {
+            pressed |= self.pressed_buttons >> 4;
+        }
+
+        0xC0 | (select & (DIRECTIONS_SELECT_BIT | ACTIONS_SELECT_BIT)) | (!pressed & 0x0F)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joypad_reads_selected_group() {
+        let mut mmu = Mmu::new().unwrap();
+
+        mmu.press_button(Button::Down);
+        mmu.press_button(Button::A);
+        assert_ne!(mmu.iflag() & (1 << Interrupt::Joypad as u8), 0);
+
+        // Only the d-pad selected
+        mmu.set_p1(ACTIONS_SELECT_BIT);
+        assert_eq!(mmu.p1(), 0xE7);
+
+        // Only the action buttons selected
+        mmu.set_p1(DIRECTIONS_SELECT_BIT);
+        assert_eq!(mmu.p1(), 0xDE);
+
+        mmu.release_button(Button::A);
+        assert_eq!(mmu.p1(), 0xDF);
+
+        mmu.set_p1(DIRECTIONS_SELECT_BIT | ACTIONS_SELECT_BIT);
+        assert_eq!(mmu.p1(), 0xFF);
+    }
+}
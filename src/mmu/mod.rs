@@ -15,10 +15,18 @@ FF80	FFFE	High RAM (HRAM)
 FFFF	FFFF	Interrupt Enable register (IE)
 */
 mod io;
+pub mod joypad;
+pub mod cheats;
+pub mod sgb;
 
-use std::{fs, path};
+use std::{fmt, fs, path};
+use std::rc::Rc;
 use anyhow::{Context, Result};
 use crate::mmu::MemoryRegion::*;
+use crate::mmu::cheats::Cheat;
+use crate::mmu::sgb::PacketReceiver;
+use crate::cpu::interrupts::Interrupt;
+use crate::apu::{self, Apu, AudioConfig};
 
 const MEMORY_BANK_SIZE: usize = 0xFFFF;
 const ROM_BANK_SIZE: usize = 0x4000;
@@ -30,7 +38,78 @@ const SPRITE_ATTRIBUTION_TABLE_SIZE: usize = 0xA0;
 const UNUSABLE_MEMORY_SIZE: usize = 0x60;
 const IO_SIZE: usize = 0x80;
 const HIGH_RAM_SIZE: usize = 0x7f;
+const PALETTE_RAM_SIZE: usize = 0x40;
 
+const BCPS_ADDRESS: usize = 0xFF68;
+const BCPD_ADDRESS: usize = 0xFF69;
+const OCPS_ADDRESS: usize = 0xFF6A;
+const OCPD_ADDRESS: usize = 0xFF6B;
+const BANK_ADDRESS: usize = 0xFF50;
+const JOYPAD_ADDRESS: usize = 0xFF00;
+const DIV_ADDRESS: usize = 0xFF04;
+const LCDC_ADDRESS: usize = 0xFF40;
+const STAT_ADDRESS: usize = 0xFF41;
+const SB_ADDRESS: usize = 0xFF01;
+const SC_ADDRESS: usize = 0xFF02;
+const LY_ADDRESS: usize = 0xFF44;
+const DMA_ADDRESS: usize = 0xFF46;
+
+// Bits 7 and 0 of SC, a transfer started with the internal clock
+const SERIAL_START: u8 = 0x81;
+
+const CGB_FLAG_ADDRESS: usize = 0x143;
+const CARTRIDGE_TYPE_ADDRESS: usize = 0x147;
+
+// Bits of each IO register that always read as 1, either unused or write-only. The sound registers are masked by the APU
+const IO_READ_MASKS: [u8; IO_SIZE] = [
+    // P1, SB, SC, -, DIV, TIMA, TMA, TAC, -, -, -, -, -, -, -, IF
+    0xC0, 0x00, 0x7E, 0xFF, 0x00, 0x00, 0x00, 0xF8, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xE0,
+    // Sound registers and wave RAM
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // LCDC, STAT, SCY, SCX, LY, LYC, DMA, BGP, OBP0, OBP1, WY, WX, KEY0, KEY1, -, VBK
+    0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x7E, 0xFF, 0xFE,
+    // BANK, HDMA1-HDMA5, RP
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x3C, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+    // BCPS, BCPD, OCPS, OCPD, OPRI
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x40, 0x00, 0x40, 0x00, 0xFE, 0xFF, 0xFF, 0xFF,
+    // SVBK, -, undocumented FF72-FF75, PCM12, PCM34
+    0xF8, 0xFF, 0x00, 0x00, 0x00, 0x8F, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+// The cartridge header sits between the two halves of the CGB boot ROM
+const BOOT_ROM_HEADER_GAP: std::ops::Range<usize> = 0x100..0x200;
+
+// Errors on the memory access path, kept small and Copy since every read and write returns one
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MmuError {
+    IllegalAddress(usize),
+    // The address maps to a region, but lands past the end of the memory backing it
+    OutOfRegion(MemoryRegion, usize),
+}
+
+impl fmt::Display for MmuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MmuError::IllegalAddress(address) => write!(f, "Illegal address {:X}", address),
+            MmuError::OutOfRegion(region, address) => write!(f, "Address {:X} is outside of the memory backing {}", address, region.as_str()),
+        }
+    }
+}
+
+impl std::error::Error for MmuError {}
+
+// A CPU write held back until the dispatcher has clocked the timer up to the cycle it lands on
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct DeferredWrite {
+    pub address: usize,
+    pub value: u8,
+    // Read-modify-writes read the old value a cycle before writing the new one
+    pub read_first: bool,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum MemoryRegion {
     RomBankZero = 0x0000,
     RomBankSwap = 0x4000,
@@ -46,7 +125,13 @@ pub enum MemoryRegion {
 }
 
 impl MemoryRegion {
-    pub fn from_address(address: usize) -> Result<Self> {
+    pub const VALUES: [Self; 11] = [
+        RomBankZero, RomBankSwap, VideoRam, ExternalRam, WorkRam, EchoRam,
+        SpriteAttributionTable, Unusable, IO, HighRam, InterruptEnableRegister,
+    ];
+
+    // A plain range match compiles down to a handful of comparisons, a per-page lookup table measured slower
+    pub fn from_address(address: usize) -> Result<Self, MmuError> {
         match address {
             0x0000..=0x3FFF => Ok(RomBankZero),
             0x4000..=0x7FFF => Ok(RomBankSwap),
@@ -59,79 +144,203 @@ impl MemoryRegion {
             0xFF00..=0xFF7F => Ok(IO),
             0xFF80..=0xFFFE => Ok(HighRam),
             0xFFFF => Ok(InterruptEnableRegister),
-            add => Err(anyhow::anyhow!("Illegal address {:X}", add))
+            add => Err(MmuError::IllegalAddress(add))
         }
     }
 
-    pub fn as_str(&self) -> String {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            RomBankZero => String::from("ROM0"),
-            RomBankSwap => String::from("ROM1"),
-            VideoRam => String::from("VRA0"),
-            ExternalRam => String::from("SRA0"),
-            WorkRam => String::from("WRA0"),
-            EchoRam => String::from("ECHO"),
-            SpriteAttributionTable => String::from("OAM"),
-            Unusable => String::from("----"),
-            IO => String::from("I/O "),
-            HighRam => String::from("HRAM"),
-            InterruptEnableRegister => String::from("IER ")
+            RomBankZero => "ROM0",
+            RomBankSwap => "ROM1",
+            VideoRam => "VRA0",
+            ExternalRam => "SRA0",
+            WorkRam => "WRA0",
+            EchoRam => "ECHO",
+            SpriteAttributionTable => "OAM",
+            Unusable => "----",
+            IO => "I/O ",
+            HighRam => "HRAM",
+            InterruptEnableRegister => "IER "
         }
     }
 }
 
+// CGB support declared by the cartridge header
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum CgbMode {
+    #[default]
+    Dmg,
+    CgbCompatible,
+    CgbOnly,
+}
+
+impl CgbMode {
+    pub fn from_header_flag(flag: u8) -> Self {
+        match flag {
+            0xC0 => CgbMode::CgbOnly,
+            0x80 => CgbMode::CgbCompatible,
+            _ => CgbMode::Dmg,
+        }
+    }
+}
+
+// Cloning is cheap enough for snapshots, the cartridge data is shared
+#[derive(Clone)]
 pub struct Mmu {
     rom_bank_zero: [u8; ROM_BANK_SIZE],
     rom_bank_swap: [u8; ROM_BANK_SIZE],
+    // Cartridge bank mapped at 0x4000-0x7FFF
+    rom_bank: usize,
 
     video_ram: [u8; VIDEO_RAM_SIZE],
     external_ram: [u8; EXTERNAL_RAM_SIZE],
     work_ram: [u8; WORK_RAM_SIZE],
-    echo_ram: [u8; ECHO_RAM_SIZE],
 
     sprite_attribution_table: [u8; SPRITE_ATTRIBUTION_TABLE_SIZE],
-    unusable: [u8; UNUSABLE_MEMORY_SIZE],
+    // Scratch byte handed out by get_byte_ref so writes to the unusable region are discarded
+    unusable: u8,
     io: [u8; IO_SIZE],
     high_ram: [u8; HIGH_RAM_SIZE],
 
     interrupt_enable_register: u8,
 
-    cartridge_data: Vec<u8>,
+    // Owns the sound registers, so that writes to them take effect immediately
+    apu: Apu,
+
+    // CGB colour palettes, accessed through BCPS/BCPD and OCPS/OCPD
+    background_palette_ram: [u8; PALETTE_RAM_SIZE],
+    object_palette_ram: [u8; PALETTE_RAM_SIZE],
+
+    // Mode of the loaded cartridge, and whether the emulated hardware is a CGB
+    cgb_mode: CgbMode,
+    cgb_core: bool,
+
+    // Whether the emulated hardware is an SGB, which listens for command packets on P1
+    sgb_core: bool,
+    sgb_packets: PacketReceiver,
+
+    // System counter incremented every T-cycle, DIV is its upper byte
+    div_counter: u16,
+
+    // Currently held buttons, d-pad in the lower nibble and action buttons in the upper one
+    pressed_buttons: u8,
+
+    cheats: Vec<Cheat>,
+
+    // Mapped over the start of the cartridge until 0xFF50 is written to
+    boot_rom: Option<Vec<u8>>,
+
+    // Bytes sent over serial since the last take_serial_output
+    serial_output: Vec<u8>,
+
+    // Gameboy Doctor logs are taken with LY stuck at 0x90, instead of the value driven by the PPU
+    fixed_ly: bool,
+
+    // Writes made by the instruction being executed, while the CPU is timing its memory accesses
+    deferred_writes: Option<Vec<DeferredWrite>>,
+    // Address and value handed out by get_byte_ref while writes are deferred
+    read_modify_write: Option<(usize, u8)>,
+
+    cartridge_data: Rc<Vec<u8>>,
 }
 
 impl Mmu {
     pub fn new() -> Result<Self> {
-        let work_ram: [u8; WORK_RAM_SIZE] = rand::random();
         Ok(Self {
             rom_bank_zero: [0; ROM_BANK_SIZE],
             rom_bank_swap: [0; ROM_BANK_SIZE],
+            rom_bank: 1,
 
             video_ram: [0; VIDEO_RAM_SIZE],
             external_ram: [0xFF; EXTERNAL_RAM_SIZE],
             work_ram: [0; WORK_RAM_SIZE],
-            echo_ram: [0; ECHO_RAM_SIZE],
 
             sprite_attribution_table: [0; SPRITE_ATTRIBUTION_TABLE_SIZE],
-            unusable: [0; UNUSABLE_MEMORY_SIZE],
+            unusable: 0,
             io: [0; IO_SIZE],
             high_ram: [0; HIGH_RAM_SIZE],
 
             interrupt_enable_register: 0,
 
-            cartridge_data: Vec::new(),
+            apu: Apu::new(AudioConfig::default()),
+
+            background_palette_ram: [0; PALETTE_RAM_SIZE],
+            object_palette_ram: [0; PALETTE_RAM_SIZE],
+
+            cgb_mode: CgbMode::Dmg,
+            cgb_core: false,
+
+            sgb_core: false,
+            sgb_packets: PacketReceiver::default(),
+
+            div_counter: 0,
+
+            pressed_buttons: 0,
+
+            cheats: Vec::new(),
+
+            boot_rom: None,
+
+            serial_output: Vec::new(),
+
+            fixed_ly: false,
+
+            deferred_writes: None,
+            read_modify_write: None,
+
+            cartridge_data: Rc::new(Vec::new()),
         })
     }
 
     pub fn load_cartridge(&mut self, path: &path::Path) -> Result<()> {
         let data: Vec<u8> = fs::read(path).context("Failed to read ROM")?;
 
-        self.cartridge_data = data;
+        self.load_cartridge_data(data)
+    }
+
+    pub fn load_cartridge_data(&mut self, data: Vec<u8>) -> Result<()> {
+        if data.len() < 2 * ROM_BANK_SIZE {
+            return Err(anyhow::anyhow!("ROM is too small ({} bytes)", data.len()));
+        }
+
+        self.cgb_mode = CgbMode::from_header_flag(data[CGB_FLAG_ADDRESS]);
+        self.cartridge_data = Rc::new(data);
         self.load_rom_bank(0);
         self.load_rom_bank(1);
 
         Ok(())
     }
 
+    pub fn load_boot_rom(&mut self, path: &path::Path) -> Result<()> {
+        let data: Vec<u8> = fs::read(path).context("Failed to read boot ROM")?;
+
+        self.boot_rom = Some(data);
+
+        Ok(())
+    }
+
+    pub fn is_boot_rom_mapped(&self) -> bool { self.boot_rom.is_some() }
+
+    pub fn div_counter(&self) -> u16 { self.div_counter }
+    pub fn set_div_counter(&mut self, val: u16) { self.div_counter = val }
+    pub fn increment_div_counter(&mut self, t_cycles: u16) { self.div_counter = self.div_counter.wrapping_add(t_cycles) }
+
+    pub fn apu(&self) -> &Apu { &self.apu }
+    pub fn apu_mut(&mut self) -> &mut Apu { &mut self.apu }
+
+    pub fn set_fixed_ly(&mut self, val: bool) { self.fixed_ly = val }
+
+    pub fn cgb_mode(&self) -> CgbMode { self.cgb_mode }
+    pub fn set_cgb_core(&mut self, val: bool) { self.cgb_core = val }
+    pub fn set_sgb_core(&mut self, val: bool) { self.sgb_core = val }
+
+    // CGB features (colour palettes, unusable region reads...) are only active for CGB cartridges on a CGB,
+    // DMG cartridges always get DMG behaviour
+    pub fn is_cgb_mode(&self) -> bool { self.cgb_core && self.cgb_mode != CgbMode::Dmg }
+
+    // A DMG cartridge on a CGB, coloured through the compatibility palettes
+    pub fn is_dmg_compatibility_mode(&self) -> bool { self.cgb_core && self.cgb_mode == CgbMode::Dmg }
+
     fn load_rom_bank(&mut self, bank_id: usize) {
         let start_address = bank_id * ROM_BANK_SIZE;
         let end_address = start_address + ROM_BANK_SIZE;
@@ -145,112 +354,182 @@ impl Mmu {
             },
             _ => {
                 self.rom_bank_swap.clone_from_slice(rom_bank);
+                self.rom_bank = bank_id;
             }
         }
     }
 
-    pub fn read_byte(&self, address: usize) -> Result<u8> {
-        if address == 0xFF44 {
+    pub fn current_rom_bank(&self) -> usize { self.rom_bank }
+
+    // External RAM isn't banked yet, so it is always bank 0
+    pub fn current_ram_bank(&self) -> usize { 0 }
+
+    // Region name for the debugger, with the mapped bank for the switchable regions (e.g. "ROM1:03")
+    pub fn region_label(&self, region: MemoryRegion) -> String {
+        match region {
+            RomBankSwap => format!("{}:{:02X}", region.as_str(), self.current_rom_bank()),
+            ExternalRam => format!("{}:{:02X}", region.as_str(), self.current_ram_bank()),
+            _ => region.as_str().to_string(),
+        }
+    }
+
+    // Only MBC1 ROM banking is emulated, cartridge types 0x01-0x03
+    fn is_mbc1(&self) -> bool {
+        matches!(self.cartridge_data.get(CARTRIDGE_TYPE_ADDRESS), Some(0x01..=0x03))
+    }
+
+    // MBC1 maps the bank in the lower 5 bits of the value, bank 0 selects bank 1 instead
+    fn select_rom_bank(&mut self, value: u8) {
+        let bank_count = self.cartridge_data.len() / ROM_BANK_SIZE;
+        let bank_id = ((value & 0x1F) as usize % bank_count).max(1);
+
+        self.load_rom_bank(bank_id);
+    }
+
+    // The CPU can't access VRAM while the PPU is drawing, nor OAM while it scans or draws, unless the display is off
+    fn is_locked(&self, address: usize) -> bool {
+        let display_enabled = self.io[LCDC_ADDRESS - IO as usize] & (1 << 7) != 0;
+        let mode = self.io[STAT_ADDRESS - IO as usize] & 0b11;
+
+        display_enabled && match address {
+            0x8000..=0x9FFF => mode == 3,
+            0xFE00..=0xFE9F => mode == 2 || mode == 3,
+            _ => false,
+        }
+    }
+
+    // Locked VRAM and OAM read back as 0xFF
+    pub fn read_byte(&self, address: usize) -> Result<u8, MmuError> {
+        if self.is_locked(address) {
+            return Ok(0xFF);
+        }
+
+        self.ppu_read_byte(address)
+    }
+
+    // Byte of a region's backing memory, checked so that a region layout that doesn't match its memory can't panic
+    fn region_byte(memory: &[u8], region: MemoryRegion, address: usize) -> Result<u8, MmuError> {
+        memory.get(address - region as usize).copied().ok_or(MmuError::OutOfRegion(region, address))
+    }
+
+    fn region_byte_mut(memory: &mut [u8], region: MemoryRegion, address: usize) -> Result<&mut u8, MmuError> {
+        memory.get_mut(address - region as usize).ok_or(MmuError::OutOfRegion(region, address))
+    }
+
+    // Same as read_byte, but never blocked by the PPU mode since the PPU itself is the one accessing the memory
+    pub fn ppu_read_byte(&self, address: usize) -> Result<u8, MmuError> {
+        if self.fixed_ly && address == 0xFF44 {
             return Ok(0x90);
         }
 
+        if let Some(boot_rom) = &self.boot_rom
+            && address < boot_rom.len() && !BOOT_ROM_HEADER_GAP.contains(&address)
+        {
+            return Ok(boot_rom[address]);
+        }
+
         Ok(match MemoryRegion::from_address(address)? {
             RomBankZero => {
-                let relative_address = address - RomBankZero as usize;
-                self.rom_bank_zero[relative_address]
+                let value = Self::region_byte(&self.rom_bank_zero, RomBankZero, address)?;
+
+                if self.cheats.is_empty() { value } else { self.game_genie_value(address, value).unwrap_or(value) }
             }
             RomBankSwap => {
-                let relative_address = address - RomBankSwap as usize;
-                self.rom_bank_swap[relative_address]
-            }
-            VideoRam => {
-                let relative_address = address - VideoRam as usize;
-                self.video_ram[relative_address]
-            }
-            ExternalRam => {
-                let relative_address = address - ExternalRam as usize;
-                self.external_ram[relative_address]
-            }
-            WorkRam => {
-                let relative_address = address - WorkRam as usize;
-                self.work_ram[relative_address]
-            }
-            EchoRam => {
-                let relative_address = address - EchoRam as usize;
-                self.work_ram[relative_address]
-            }
-            SpriteAttributionTable => {
-                let relative_address = address - SpriteAttributionTable as usize;
-                self.sprite_attribution_table[relative_address]
-            }
-            Unusable => {
-                let relative_address = address - Unusable as usize;
-                self.unusable[relative_address]
+                let value = Self::region_byte(&self.rom_bank_swap, RomBankSwap, address)?;
+
+                if self.cheats.is_empty() { value } else { self.game_genie_value(address, value).unwrap_or(value) }
             }
+            VideoRam => Self::region_byte(&self.video_ram, VideoRam, address)?,
+            ExternalRam => Self::region_byte(&self.external_ram, ExternalRam, address)?,
+            WorkRam => Self::region_byte(&self.work_ram, WorkRam, address)?,
+            // Echo RAM mirrors C000-DDFF
+            EchoRam => Self::region_byte(&self.work_ram, EchoRam, address)?,
+            SpriteAttributionTable => Self::region_byte(&self.sprite_attribution_table, SpriteAttributionTable, address)?,
+            Unusable => self.unusable_value(),
             IO => {
-                let relative_address = address - IO as usize;
-                self.io[relative_address]
-            }
-            HighRam => {
-                let relative_address = address - HighRam as usize;
-                self.high_ram[relative_address]
+                match address {
+                    JOYPAD_ADDRESS => self.joypad_state(self.io[0]),
+                    DIV_ADDRESS => (self.div_counter >> 8) as u8,
+                    BCPD_ADDRESS => self.background_palette_ram[(self.bcps() & 0x3F) as usize],
+                    OCPD_ADDRESS => self.object_palette_ram[(self.ocps() & 0x3F) as usize],
+                    apu::REGISTERS_START..=apu::REGISTERS_END => self.apu.read_register(address),
+                    // Bit 1 selects the fast serial clock on the CGB
+                    SC_ADDRESS if self.is_cgb_mode() => self.io[SC_ADDRESS - IO as usize] | 0x7C,
+                    _ => Self::region_byte(&self.io, IO, address)? | IO_READ_MASKS[address - IO as usize],
+                }
             }
+            HighRam => Self::region_byte(&self.high_ram, HighRam, address)?,
             InterruptEnableRegister => {
                 self.interrupt_enable_register
             }
         })
     }
 
-    pub fn write_byte(&mut self, address: usize, value: u8) -> Result<()> {
+    // Hold back writes until take_deferred_writes, so the CPU can commit each one on its own machine cycle
+    pub fn defer_writes(&mut self) {
+        self.deferred_writes = Some(Vec::new());
+    }
+
+    // Stop deferring writes and return the ones made since defer_writes, in order
+    pub fn take_deferred_writes(&mut self) -> Vec<DeferredWrite> {
+        let mut writes = self.deferred_writes.take().unwrap_or_default();
+        if let Some((address, value)) = self.read_modify_write.take() {
+            writes.push(DeferredWrite { address, value, read_first: true });
+        }
+
+        writes
+    }
+
+    pub fn write_byte(&mut self, address: usize, value: u8) -> Result<(), MmuError> {
+        if let Some(writes) = &mut self.deferred_writes {
+            MemoryRegion::from_address(address)?;
+            writes.push(DeferredWrite { address, value, read_first: false });
+
+            return Ok(());
+        }
+
+        if self.is_locked(address) {
+            return Ok(());
+        }
+
         match MemoryRegion::from_address(address)? {
-            RomBankZero | RomBankSwap => { Ok(()) },
-                // Err(anyhow::anyhow!("Attempted to write into illegal memory region ROM"))
-            // },
+            RomBankZero | RomBankSwap => {
+                if (0x2000..0x4000).contains(&address) && self.is_mbc1() {
+                    self.select_rom_bank(value);
+                }
+
+                Ok(())
+            },
             VideoRam => {
-                let relative_address = address - VideoRam as usize;
-                self.video_ram[relative_address] = value;
+                *Self::region_byte_mut(&mut self.video_ram, VideoRam, address)? = value;
 
                 Ok(())
             }
             ExternalRam => {
-                let relative_address = address - ExternalRam as usize;
-                self.external_ram[relative_address] = value;
+                *Self::region_byte_mut(&mut self.external_ram, ExternalRam, address)? = value;
 
                 Ok(())
             }
             WorkRam => {
-                let relative_address = address - WorkRam as usize;
-                self.work_ram[relative_address] = value;
+                *Self::region_byte_mut(&mut self.work_ram, WorkRam, address)? = value;
 
                 Ok(())
             }
             EchoRam => {
-                let relative_address = address - EchoRam as usize;
-                self.work_ram[relative_address] = value;
+                *Self::region_byte_mut(&mut self.work_ram, EchoRam, address)? = value;
 
                 Ok(())
             }
             SpriteAttributionTable => {
-                let relative_address = address - SpriteAttributionTable as usize;
-                self.sprite_attribution_table[relative_address] = value;
-
-                Ok(())
-            }
-            Unusable => {
-                let relative_address = address - Unusable as usize;
-                self.unusable[relative_address] = value;
-
-                Ok(())
-            }
-            IO => {
-                let relative_address = address - IO as usize;
-                self.io[relative_address] = value;
+                *Self::region_byte_mut(&mut self.sprite_attribution_table, SpriteAttributionTable, address)? = value;
 
                 Ok(())
             }
+            // Writes to the unusable region are ignored
+            Unusable => { Ok(()) },
+            IO => self.on_io_write(address, value),
             HighRam => {
-                let relative_address = address - HighRam as usize;
-                self.high_ram[relative_address] = value;
+                *Self::region_byte_mut(&mut self.high_ram, HighRam, address)? = value;
 
                 Ok(())
             }
@@ -262,48 +541,209 @@ impl Mmu {
         }
     }
 
-    pub fn get_byte_ref(&mut self, address: usize) -> Result<&mut u8> {
-        Ok(match MemoryRegion::from_address(address)? {
-            RomBankZero => {
-                let relative_address = address - RomBankZero as usize;
-                &mut self.rom_bank_zero[relative_address]
-            }
-            RomBankSwap => {
-                let relative_address = address - RomBankSwap as usize;
-                &mut self.rom_bank_swap[relative_address]
-            }
-            VideoRam => {
-                let relative_address = address - VideoRam as usize;
-                &mut self.video_ram[relative_address]
+    // Write consecutive bytes from start, going through write_byte so each lands in whichever region it falls in
+    // Nothing is written if the slice runs past the end of the address space
+    pub fn write_slice(&mut self, start: usize, data: &[u8]) -> Result<(), MmuError> {
+        if let Some(last) = data.len().checked_sub(1) {
+            MemoryRegion::from_address(start + last)?;
+        }
+
+        for (i, value) in data.iter().enumerate() {
+            self.write_byte(start + i, *value)?;
+        }
+
+        Ok(())
+    }
+
+    // Same as write_byte, but the write also goes through in the ROM banks so that they can be patched, and in VRAM and OAM
+    // whatever the PPU mode. Patched ROM bytes are lost whenever the bank is loaded again from the cartridge
+    pub fn poke(&mut self, address: usize, value: u8) -> Result<(), MmuError> {
+        match MemoryRegion::from_address(address)? {
+            RomBankZero | RomBankSwap | VideoRam | SpriteAttributionTable => {
+                *self.unlocked_byte_ref(address)? = value;
+
+                Ok(())
             }
-            ExternalRam => {
-                let relative_address = address - ExternalRam as usize;
-                &mut self.external_ram[relative_address]
+            _ => self.write_byte(address, value),
+        }
+    }
+
+    // Every write to an IO register goes through here, so that its side effects reach the part of the system that owns it
+    fn on_io_write(&mut self, address: usize, value: u8) -> Result<(), MmuError> {
+        match address {
+            // Only the group select bits of P1 are writable
+            JOYPAD_ADDRESS => {
+                self.io[0] = value & 0x30;
+                if self.sgb_core {
+                    self.sgb_packets.write_p1(value);
+                }
             }
-            WorkRam => {
-                let relative_address = address - WorkRam as usize;
-                &mut self.work_ram[relative_address]
+            SC_ADDRESS => self.write_serial_control(value),
+            // Writing any value to DIV resets the whole system counter
+            DIV_ADDRESS => self.div_counter = 0,
+            LCDC_ADDRESS => self.write_lcdc(value),
+            STAT_ADDRESS => self.write_stat(value),
+            DMA_ADDRESS => self.start_dma(value)?,
+            BCPD_ADDRESS => self.write_palette_data(BCPS_ADDRESS, value, false),
+            OCPD_ADDRESS => self.write_palette_data(OCPS_ADDRESS, value, true),
+            BANK_ADDRESS => {
+                // Any non-zero write unmaps the boot ROM until the next reset
+                if value != 0 {
+                    self.boot_rom = None;
+                }
+                self.io[address - IO as usize] = value;
             }
-            EchoRam => {
-                let relative_address = address - EchoRam as usize;
-                &mut self.work_ram[relative_address]
+            apu::REGISTERS_START..=apu::REGISTERS_END => {
+                self.io[address - IO as usize] = value;
+                self.apu.write_register(address, value);
             }
-            SpriteAttributionTable => {
-                let relative_address = address - SpriteAttributionTable as usize;
-                &mut self.sprite_attribution_table[relative_address]
+            _ => {
+                *Self::region_byte_mut(&mut self.io, IO, address)? = value;
             }
+        }
+
+        Ok(())
+    }
+
+    // Nothing is connected to the link port, so a transfer on the internal clock completes straight away
+    fn write_serial_control(&mut self, value: u8) {
+        let relative_address = SC_ADDRESS - IO as usize;
+
+        if value & SERIAL_START == SERIAL_START {
+            self.serial_output.push(self.io[SB_ADDRESS - IO as usize]);
+            self.io[relative_address] = value & !(1 << 7);
+            self.request_interrupt(Interrupt::Serial);
+        }
+        else {
+            self.io[relative_address] = value;
+        }
+    }
+
+    // Bytes sent over serial since the last call, oldest first
+    pub fn take_serial_output(&mut self) -> Vec<u8> { std::mem::take(&mut self.serial_output) }
+    pub fn has_serial_output(&self) -> bool { !self.serial_output.is_empty() }
+
+    // Turning the display off drops LY and the STAT mode to 0 right away, the PPU stops on its next step
+    fn write_lcdc(&mut self, value: u8) {
+        let relative_address = LCDC_ADDRESS - IO as usize;
+        let was_enabled = self.io[relative_address] & (1 << 7) != 0;
+        self.io[relative_address] = value;
+
+        if was_enabled && value & (1 << 7) == 0 {
+            self.io[LY_ADDRESS - IO as usize] = 0;
+            self.io[STAT_ADDRESS - IO as usize] &= !0b11;
+        }
+    }
+
+    // OAM DMA copies the 160 bytes at value * 0x100 into OAM, done all at once rather than over 160 M-cycles
+    // Sources from 0xE000 up read the work RAM below them
+    fn start_dma(&mut self, value: u8) -> Result<(), MmuError> {
+        self.io[DMA_ADDRESS - IO as usize] = value;
+
+        let source = match (value as usize) << 8 {
+            source @ 0xE000.. => source - 0x2000,
+            source => source,
+        };
+        for i in 0..SPRITE_ATTRIBUTION_TABLE_SIZE {
+            self.sprite_attribution_table[i] = self.ppu_read_byte(source + i)?;
+        }
+
+        Ok(())
+    }
+
+    // Value read back from the unusable region, the DMG returns 0x00 while the CGB returns 0xFF
+    fn unusable_value(&self) -> u8 {
+        if self.is_cgb_mode() { 0xFF } else { 0x00 }
+    }
+
+    // Bits 0-2 of STAT are driven by the PPU, the CPU only writes the interrupt sources
+    // On DMG the write enables every source for a cycle first, so a rising STAT line in HBlank, VBlank or on LY=LYC requests an LCD interrupt
+    fn write_stat(&mut self, value: u8) {
+        let relative_address = STAT_ADDRESS - IO as usize;
+        let stat = self.io[relative_address];
+        let display_enabled = self.io[LCDC_ADDRESS - IO as usize] & (1 << 7) != 0;
+
+        let spurious_line = matches!(stat & 0b11, 0 | 1) || stat & (1 << 2) != 0;
+        if !self.cgb_core && display_enabled && spurious_line && !Self::stat_line(stat) {
+            self.request_interrupt(Interrupt::LCD);
+        }
+
+        self.io[relative_address] = (value & !0b111) | (stat & 0b111);
+    }
+
+    // OR of every enabled STAT interrupt source
+    fn stat_line(stat: u8) -> bool {
+        let mode_source = match stat & 0b11 {
+            0 => stat & (1 << 3) != 0,
+            1 => stat & (1 << 4) != 0,
+            2 => stat & (1 << 5) != 0,
+            _ => false,
+        };
+
+        mode_source || (stat & (1 << 6) != 0 && stat & (1 << 2) != 0)
+    }
+
+    // Write a byte into palette RAM at the index held by the given specification register (BCPS/OCPS)
+    // If bit 7 of the specification register is set, the index is incremented after the write
+    fn write_palette_data(&mut self, specification_address: usize, value: u8, is_object: bool) {
+        let specification = self.io[specification_address - IO as usize];
+        let index = (specification & 0x3F) as usize;
+
+        if is_object {
+            self.object_palette_ram[index] = value;
+        } else {
+            self.background_palette_ram[index] = value;
+        }
+
+        if specification & 0x80 != 0 {
+            let next_index = (index as u8 + 1) & 0x3F;
+            self.io[specification_address - IO as usize] = 0x80 | next_index;
+        }
+    }
+
+    // Get the raw RGB555 value of a colour in CGB palette RAM
+    // Each palette holds 4 colours of 2 bytes each, stored little-endian
+    pub fn cgb_palette_color(&self, is_object: bool, palette: u8, color_id: u8) -> u16 {
+        let palette_ram = if is_object { &self.object_palette_ram } else { &self.background_palette_ram };
+        let index = (palette as usize & 0x7) * 8 + (color_id as usize & 0x3) * 2;
+
+        palette_ram[index] as u16 | ((palette_ram[index + 1] as u16) << 8)
+    }
+
+    pub fn get_byte_ref(&mut self, address: usize) -> Result<&mut u8, MmuError> {
+        // The new value is committed through write_byte once the CPU reaches the write cycle
+        if self.deferred_writes.is_some() {
+            let value = self.read_byte(address)?;
+
+            return Ok(&mut self.read_modify_write.insert((address, value)).1);
+        }
+
+        // Writes through the scratch byte are discarded, like write_byte does for locked memory
+        if self.is_locked(address) {
+            MemoryRegion::from_address(address)?;
+            self.unusable = 0xFF;
+            return Ok(&mut self.unusable);
+        }
+
+        self.unlocked_byte_ref(address)
+    }
+
+    // Byte backing an address, whatever the PPU mode
+    fn unlocked_byte_ref(&mut self, address: usize) -> Result<&mut u8, MmuError> {
+        Ok(match MemoryRegion::from_address(address)? {
+            RomBankZero => Self::region_byte_mut(&mut self.rom_bank_zero, RomBankZero, address)?,
+            RomBankSwap => Self::region_byte_mut(&mut self.rom_bank_swap, RomBankSwap, address)?,
+            VideoRam => Self::region_byte_mut(&mut self.video_ram, VideoRam, address)?,
+            ExternalRam => Self::region_byte_mut(&mut self.external_ram, ExternalRam, address)?,
+            WorkRam => Self::region_byte_mut(&mut self.work_ram, WorkRam, address)?,
+            EchoRam => Self::region_byte_mut(&mut self.work_ram, EchoRam, address)?,
+            SpriteAttributionTable => Self::region_byte_mut(&mut self.sprite_attribution_table, SpriteAttributionTable, address)?,
             Unusable => {
-                let relative_address = address - Unusable as usize;
-                &mut self.unusable[relative_address]
-            }
-            IO => {
-                let relative_address = address - IO as usize;
-                &mut self.io[relative_address]
-            }
-            HighRam => {
-                let relative_address = address - HighRam as usize;
-                &mut self.high_ram[relative_address]
+                self.unusable = self.unusable_value();
+                &mut self.unusable
             }
+            IO => Self::region_byte_mut(&mut self.io, IO, address)?,
+            HighRam => Self::region_byte_mut(&mut self.high_ram, HighRam, address)?,
             InterruptEnableRegister => {
                 &mut self.interrupt_enable_register
             }
@@ -317,15 +757,62 @@ impl Mmu {
             VideoRam => self.video_ram.to_vec(),
             ExternalRam => self.external_ram.to_vec(),
             WorkRam => self.work_ram.to_vec(),
-            EchoRam => self.echo_ram.to_vec(),
+            EchoRam => self.work_ram[..ECHO_RAM_SIZE].to_vec(),
             SpriteAttributionTable => self.sprite_attribution_table.to_vec(),
-            Unusable => self.unusable.to_vec(),
-            IO => self.io.to_vec(),
+            Unusable => vec![self.unusable_value(); UNUSABLE_MEMORY_SIZE],
+            // IO registers such as DIV are computed on read, so take their read-back values
+            IO => (IO as usize..HighRam as usize).map(|address| self.read_byte(address).unwrap()).collect(),
             HighRam => self.high_ram.to_vec(),
             InterruptEnableRegister => vec![self.interrupt_enable_register]
         }
     }
 
+    // Restore a region from a dump, the counterpart of dump_memory_region. ROM is left to load_cartridge_data
+    pub fn load_memory_region(&mut self, region: MemoryRegion, data: &[u8]) -> Result<()> {
+        let destination: &mut [u8] = match region {
+            RomBankZero | RomBankSwap | Unusable => {
+                return Err(anyhow::anyhow!("Cannot load a dump into {}", region.as_str()));
+            }
+            VideoRam => &mut self.video_ram,
+            ExternalRam => &mut self.external_ram,
+            WorkRam => &mut self.work_ram,
+            EchoRam => &mut self.work_ram[..ECHO_RAM_SIZE],
+            SpriteAttributionTable => &mut self.sprite_attribution_table,
+            IO => return self.load_io(data),
+            HighRam => &mut self.high_ram,
+            InterruptEnableRegister => std::slice::from_mut(&mut self.interrupt_enable_register),
+        };
+
+        if destination.len() != data.len() {
+            return Err(anyhow::anyhow!("Dump is {} bytes but {} is {} bytes", data.len(), region.as_str(), destination.len()));
+        }
+
+        destination.copy_from_slice(data);
+        Ok(())
+    }
+
+    // IO goes through the register writes, so the APU, timers and PPU pick up the loaded values
+    fn load_io(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() != IO_SIZE {
+            return Err(anyhow::anyhow!("Dump is {} bytes but {} is {} bytes", data.len(), IO.as_str(), IO_SIZE));
+        }
+
+        // Power the APU first, its other registers are ignored while it's off
+        self.write_byte(apu::NR52, data[apu::NR52 - IO as usize])?;
+        for (address, &value) in (IO as usize..).zip(data) {
+            self.write_byte(address, value)?;
+        }
+        Ok(())
+    }
+
+    // Write a region, or the whole address space when no region is given, to a binary file
+    pub fn dump_memory_region_to_file(&self, region: Option<MemoryRegion>, path: &path::Path) -> Result<()> {
+        let data = region.map_or_else(|| self.to_vec(), |region| self.dump_memory_region(region));
+
+        fs::write(path, data).context("Failed to write memory dump")
+    }
+
+    // Full 0x0000-0xFFFF image of the address space, so that an address can be used directly as an index
     pub fn to_vec(&self) -> Vec<u8> {
         [
             &self.rom_bank_zero[..],
@@ -333,11 +820,416 @@ impl Mmu {
             &self.video_ram[..],
             &self.external_ram[..],
             &self.work_ram[..],
-            &self.echo_ram[..],
+            &self.work_ram[..ECHO_RAM_SIZE],
             &self.sprite_attribution_table[..],
-            &self.io[..],
+            &self.dump_memory_region(Unusable)[..],
+            &self.dump_memory_region(IO)[..],
             &self.high_ram[..],
             std::slice::from_ref(&self.interrupt_enable_register),
         ].concat()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn div_write_resets_system_counter() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.set_div_counter(0xABCD);
+
+        mmu.write_byte(0xFF04, 0x12).unwrap();
+        assert_eq!(mmu.div_counter(), 0);
+        assert_eq!(mmu.read_byte(0xFF04).unwrap(), 0);
+    }
+
+    #[test]
+    fn dma_write_copies_into_oam() {
+        let mut mmu = Mmu::new().unwrap();
+        (0..0xA0).for_each(|i| mmu.write_byte(0xC100 + i, i as u8).unwrap());
+
+        mmu.write_byte(0xFF46, 0xC1).unwrap();
+        assert_eq!(mmu.read_byte(0xFE00).unwrap(), 0x00);
+        assert_eq!(mmu.read_byte(0xFE9F).unwrap(), 0x9F);
+
+        // 0xE1 reads the same work RAM through echo RAM
+        mmu.write_slice(0xFE00, &[0; 0xA0]).unwrap();
+        mmu.write_byte(0xFF46, 0xE1).unwrap();
+        assert_eq!(mmu.read_byte(0xFE10).unwrap(), 0x10);
+    }
+
+    #[test]
+    fn serial_transfer_completes_at_once() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.write_byte(0xFF01, b'A').unwrap();
+        mmu.write_byte(0xFF02, 0x81).unwrap();
+
+        assert_eq!(mmu.sc() & (1 << 7), 0);
+        assert_ne!(mmu.iflag() & (1 << Interrupt::Serial as u8), 0);
+        assert_eq!(mmu.take_serial_output(), b"A");
+        assert!(!mmu.has_serial_output());
+
+        // An external clock waits for the other side, which never comes
+        mmu.write_byte(0xFF02, 0x80).unwrap();
+        assert!(!mmu.has_serial_output());
+    }
+
+    #[test]
+    fn lcd_off_resets_ly() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.set_lcdc(0x91);
+        mmu.set_ly(0x42);
+        mmu.set_stat(0x83);
+
+        mmu.write_byte(0xFF40, 0x11).unwrap();
+        assert_eq!(mmu.ly(), 0);
+        assert_eq!(mmu.stat() & 0b11, 0);
+    }
+
+    #[test]
+    fn unused_io_bits_read_as_1() {
+        let mut mmu = Mmu::new().unwrap();
+
+        mmu.write_byte(0xFF41, 0x00).unwrap();
+        assert_eq!(mmu.read_byte(0xFF41).unwrap(), 0x80);
+
+        mmu.write_byte(0xFF07, 0x00).unwrap();
+        assert_eq!(mmu.read_byte(0xFF07).unwrap(), 0xF8);
+        mmu.write_byte(0xFF07, 0x05).unwrap();
+        assert_eq!(mmu.read_byte(0xFF07).unwrap(), 0xFD);
+
+        mmu.write_byte(0xFF0F, 0x00).unwrap();
+        assert_eq!(mmu.read_byte(0xFF0F).unwrap(), 0xE0);
+        mmu.write_byte(0xFF02, 0x80).unwrap();
+        assert_eq!(mmu.read_byte(0xFF02).unwrap(), 0xFE);
+        assert_eq!(mmu.read_byte(0xFF03).unwrap(), 0xFF);
+
+        // Registers without unused bits read back as written
+        mmu.write_byte(0xFF42, 0x00).unwrap();
+        assert_eq!(mmu.read_byte(0xFF42).unwrap(), 0x00);
+    }
+
+    #[test]
+    fn bcps_auto_increment_round_trip() {
+        let mut mmu = Mmu::new().unwrap();
+
+        // Palette 1, colour 0 with auto-increment
+        mmu.set_bcps(0x80 | 0x08);
+        mmu.set_bcpd(0x1F);
+        mmu.set_bcpd(0x7C);
+        // Bit 6 is unused and reads as 1
+        assert_eq!(mmu.bcps(), 0xC0 | 0x0A);
+
+        mmu.set_bcps(0x08);
+        assert_eq!(mmu.bcpd(), 0x1F);
+        mmu.set_bcps(0x09);
+        assert_eq!(mmu.bcpd(), 0x7C);
+
+        assert_eq!(mmu.cgb_palette_color(false, 1, 0), 0x7C1F);
+        assert_eq!(mmu.cgb_palette_color(true, 1, 0), 0);
+    }
+
+    #[test]
+    fn echo_ram_mirrors_work_ram() {
+        let mut mmu = Mmu::new().unwrap();
+
+        mmu.write_byte(0xC005, 0x42).unwrap();
+        assert_eq!(mmu.read_byte(0xE005).unwrap(), 0x42);
+
+        mmu.write_byte(0xFDFF, 0x24).unwrap();
+        assert_eq!(mmu.read_byte(0xDDFF).unwrap(), 0x24);
+
+        assert_eq!(mmu.dump_memory_region(EchoRam)[0x05], 0x42);
+    }
+
+    #[test]
+    fn display_off_unlocks_vram() {
+        let mut mmu = Mmu::new().unwrap();
+        // Draw mode
+        mmu.set_stat(0x83);
+
+        mmu.set_lcdc(0x91);
+        mmu.write_byte(0x8000, 0x42).unwrap();
+        assert_eq!(mmu.read_byte(0x8000).unwrap(), 0xFF);
+        assert_eq!(mmu.ppu_read_byte(0x8000).unwrap(), 0x00);
+
+        mmu.set_lcdc(0x11);
+        mmu.write_byte(0x8000, 0x42).unwrap();
+        assert_eq!(mmu.read_byte(0x8000).unwrap(), 0x42);
+        mmu.write_byte(0xFE00, 0x24).unwrap();
+        assert_eq!(mmu.read_byte(0xFE00).unwrap(), 0x24);
+    }
+
+    #[test]
+    fn stat_write_requests_spurious_interrupt_on_dmg() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.set_lcdc(0x91);
+        // HBlank with every source disabled
+        mmu.set_stat(0x80);
+
+        mmu.write_byte(0xFF41, 0x00).unwrap();
+        assert_eq!(mmu.iflag() & (1 << Interrupt::LCD as u8), 1 << Interrupt::LCD as u8);
+
+        // Draw mode has no source to raise
+        mmu.set_iflag(0);
+        mmu.set_stat(0x83);
+        mmu.write_byte(0xFF41, 0x08).unwrap();
+        assert_eq!(mmu.iflag(), 0xE0);
+        assert_eq!(mmu.stat(), 0x8B);
+
+        // The line is already high from the HBlank source
+        mmu.set_stat(0x88);
+        mmu.write_byte(0xFF41, 0x08).unwrap();
+        assert_eq!(mmu.iflag(), 0xE0);
+
+        mmu.set_cgb_core(true);
+        mmu.set_stat(0x80);
+        mmu.write_byte(0xFF41, 0x00).unwrap();
+        assert_eq!(mmu.iflag(), 0xE0);
+    }
+
+    #[test]
+    fn mbc1_rom_bank_switch() {
+        let mut mmu = Mmu::new().unwrap();
+        let mut rom = vec![0; 4 * ROM_BANK_SIZE];
+        rom[CARTRIDGE_TYPE_ADDRESS] = 0x01;
+        rom[3 * ROM_BANK_SIZE] = 0x33;
+        mmu.load_cartridge_data(rom).unwrap();
+        assert_eq!(mmu.current_rom_bank(), 1);
+
+        mmu.write_byte(0x2000, 0x03).unwrap();
+        assert_eq!(mmu.current_rom_bank(), 3);
+        assert_eq!(mmu.read_byte(0x4000).unwrap(), 0x33);
+        assert_eq!(mmu.region_label(RomBankSwap), "ROM1:03");
+
+        mmu.write_byte(0x3FFF, 0x00).unwrap();
+        assert_eq!(mmu.current_rom_bank(), 1);
+        assert_eq!(mmu.current_ram_bank(), 0);
+    }
+
+    #[test]
+    fn rom_only_cartridge_ignores_bank_writes() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.load_cartridge_data(vec![0; 4 * ROM_BANK_SIZE]).unwrap();
+
+        mmu.write_byte(0x2000, 0x03).unwrap();
+        assert_eq!(mmu.current_rom_bank(), 1);
+    }
+
+    #[test]
+    fn last_high_ram_byte_is_addressable() {
+        let mut mmu = Mmu::new().unwrap();
+
+        mmu.write_byte(0xFFFE, 0x42).unwrap();
+        assert_eq!(mmu.read_byte(0xFFFE).unwrap(), 0x42);
+        assert_eq!(mmu.dump_memory_region(HighRam)[HIGH_RAM_SIZE - 1], 0x42);
+    }
+
+    #[test]
+    fn region_overflow_is_an_error() {
+        let mut memory = [0; 2];
+
+        assert_eq!(Mmu::region_byte(&memory, HighRam, 0xFF81), Ok(0));
+        assert_eq!(Mmu::region_byte(&memory, HighRam, 0xFF82), Err(MmuError::OutOfRegion(HighRam, 0xFF82)));
+        assert_eq!(Mmu::region_byte_mut(&mut memory, HighRam, 0xFF82), Err(MmuError::OutOfRegion(HighRam, 0xFF82)));
+        assert_eq!(MmuError::OutOfRegion(HighRam, 0xFF82).to_string(), "Address FF82 is outside of the memory backing HRAM");
+    }
+
+    #[test]
+    fn to_vec_matches_address_layout() {
+        let mut mmu = Mmu::new().unwrap();
+
+        mmu.write_byte(0xFF80, 0x42).unwrap();
+        mmu.write_byte(0xFFFF, 0x1F).unwrap();
+        mmu.set_div_counter(0xAB00);
+
+        let memory = mmu.to_vec();
+        assert_eq!(memory.len(), 0x10000);
+        assert_eq!(memory[0xFF80], mmu.read_byte(0xFF80).unwrap());
+        assert_eq!(memory[0xFF04], 0xAB);
+        assert_eq!(memory[0xFFFF], 0x1F);
+    }
+
+    #[test]
+    fn write_slice_spans_regions() {
+        let mut mmu = Mmu::new().unwrap();
+        let tile: Vec<u8> = (0..16).collect();
+
+        mmu.write_slice(0x8000, &tile).unwrap();
+        let written: Vec<u8> = (0x8000..0x8010).map(|address| mmu.read_byte(address).unwrap()).collect();
+        assert_eq!(written, tile);
+
+        // End of VRAM into external RAM
+        mmu.write_slice(0x9FFF, &[0x11, 0x22]).unwrap();
+        assert_eq!(mmu.read_byte(0x9FFF).unwrap(), 0x11);
+        assert_eq!(mmu.read_byte(0xA000).unwrap(), 0x22);
+
+        assert_eq!(mmu.write_slice(0xFFFF, &[0x01, 0x02]), Err(MmuError::IllegalAddress(0x10000)));
+        assert_eq!(mmu.ie(), 0);
+    }
+
+    #[test]
+    fn illegal_address_error() {
+        let mut mmu = Mmu::new().unwrap();
+
+        assert_eq!(mmu.read_byte(0x10000), Err(MmuError::IllegalAddress(0x10000)));
+        assert_eq!(mmu.write_byte(0x12345, 0), Err(MmuError::IllegalAddress(0x12345)));
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture read_throughput`
+    #[test]
+    #[ignore]
+    fn read_throughput_benchmark() {
+        const READS: usize = 50_000_000;
+
+        let mmu = Mmu::new().unwrap();
+        let memory = mmu.to_vec();
+
+        let start = std::time::Instant::now();
+        let mut checksum = 0u8;
+        for i in 0..READS {
+            checksum = checksum.wrapping_add(mmu.read_byte(std::hint::black_box(i & 0xFFFF)).unwrap());
+        }
+        let read_byte_time = start.elapsed();
+
+        let start = std::time::Instant::now();
+        let mut raw_checksum = 0u8;
+        for i in 0..READS {
+            raw_checksum = raw_checksum.wrapping_add(memory[std::hint::black_box(i & 0xFFFF)]);
+        }
+        let raw_time = start.elapsed();
+
+        println!("read_byte: {:.2} ns/read, raw indexing: {:.2} ns/read",
+                 read_byte_time.as_nanos() as f64 / READS as f64, raw_time.as_nanos() as f64 / READS as f64);
+        assert_eq!(checksum, raw_checksum);
+    }
+
+    #[test]
+    fn from_address_region_boundaries() {
+        let boundaries = [
+            (0x0000, RomBankZero), (0x3FFF, RomBankZero), (0x4000, RomBankSwap), (0x7FFF, RomBankSwap),
+            (0x8000, VideoRam), (0x9FFF, VideoRam), (0xA000, ExternalRam), (0xBFFF, ExternalRam),
+            (0xC000, WorkRam), (0xDFFF, WorkRam), (0xE000, EchoRam), (0xFDFF, EchoRam),
+            (0xFE00, SpriteAttributionTable), (0xFE9F, SpriteAttributionTable), (0xFEA0, Unusable), (0xFEFF, Unusable),
+            (0xFF00, IO), (0xFF7F, IO), (0xFF80, HighRam), (0xFFFE, HighRam), (0xFFFF, InterruptEnableRegister),
+        ];
+
+        for (address, region) in boundaries {
+            assert_eq!(MemoryRegion::from_address(address), Ok(region), "{:#06X}", address);
+        }
+        assert_eq!(MemoryRegion::from_address(0x10000), Err(MmuError::IllegalAddress(0x10000)));
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture region_lookup`
+    #[test]
+    #[ignore]
+    fn region_lookup_benchmark() {
+        const LOOKUPS: usize = 50_000_000;
+
+        let start = std::time::Instant::now();
+        let mut high_ram_count = 0;
+        for i in 0..LOOKUPS {
+            if MemoryRegion::from_address(std::hint::black_box(i & 0xFFFF)) == Ok(HighRam) {
+                high_ram_count += 1;
+            }
+        }
+
+        println!("from_address: {:.2} ns/lookup", start.elapsed().as_nanos() as f64 / LOOKUPS as f64);
+        assert_eq!(high_ram_count, LOOKUPS / 0x10000 * HIGH_RAM_SIZE);
+    }
+
+    #[test]
+    fn dump_work_ram_to_file() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.write_byte(0xC000, 0x12).unwrap();
+        mmu.write_byte(0xDFFF, 0x34).unwrap();
+
+        let path = std::env::temp_dir().join(format!("rainier_dump_{}.bin", std::process::id()));
+        mmu.dump_memory_region_to_file(Some(WorkRam), &path).unwrap();
+
+        let dump = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(dump.len(), 0x2000);
+        assert_eq!(dump, mmu.dump_memory_region(WorkRam));
+    }
+
+    #[test]
+    fn load_memory_region_restores_dump() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.write_byte(0xC123, 0xAB).unwrap();
+        let dump = mmu.dump_memory_region(WorkRam);
+
+        mmu.write_byte(0xC123, 0x00).unwrap();
+        mmu.write_byte(0xD000, 0xFF).unwrap();
+        mmu.load_memory_region(WorkRam, &dump).unwrap();
+
+        assert_eq!(mmu.dump_memory_region(WorkRam), dump);
+        assert!(mmu.load_memory_region(WorkRam, &dump[1..]).is_err());
+        assert!(mmu.load_memory_region(RomBankZero, &[0; ROM_BANK_SIZE]).is_err());
+    }
+
+    #[test]
+    fn io_dump_matches_to_vec_and_loads_into_apu() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.write_byte(0xFF26, 0x80).unwrap();
+        mmu.write_byte(0xFF11, 0x80).unwrap();
+        mmu.set_div_counter(0xAB00);
+
+        let dump = mmu.dump_memory_region(IO);
+        assert_eq!(dump[..], mmu.to_vec()[IO as usize..HighRam as usize]);
+
+        let mut loaded = Mmu::new().unwrap();
+        loaded.load_memory_region(IO, &dump).unwrap();
+        assert_eq!(loaded.read_byte(0xFF26).unwrap() & 0x80, 0x80);
+        assert_eq!(loaded.read_byte(0xFF11).unwrap(), mmu.read_byte(0xFF11).unwrap());
+        assert!(loaded.load_memory_region(IO, &dump[1..]).is_err());
+    }
+
+    #[test]
+    fn cgb_mode_from_header() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.set_cgb_core(true);
+
+        let mut rom = vec![0; 0x8000];
+        rom[CGB_FLAG_ADDRESS] = 0xC0;
+        mmu.load_cartridge_data(rom.clone()).unwrap();
+        assert_eq!(mmu.cgb_mode(), CgbMode::CgbOnly);
+        assert!(mmu.is_cgb_mode());
+
+        rom[CGB_FLAG_ADDRESS] = 0x00;
+        mmu.load_cartridge_data(rom).unwrap();
+        assert_eq!(mmu.cgb_mode(), CgbMode::Dmg);
+        assert!(!mmu.is_cgb_mode());
+    }
+
+    #[test]
+    fn unusable_region_reads_constant() {
+        let mut mmu = Mmu::new().unwrap();
+
+        mmu.write_byte(0xFEA0, 0x42).unwrap();
+        assert_eq!(mmu.read_byte(0xFEA0).unwrap(), 0x00);
+
+        mmu.set_cgb_core(true);
+        mmu.cgb_mode = CgbMode::CgbOnly;
+        mmu.write_byte(0xFEFF, 0x42).unwrap();
+        assert_eq!(mmu.read_byte(0xFEA0).unwrap(), 0xFF);
+        assert_eq!(mmu.read_byte(0xFEFF).unwrap(), 0xFF);
+    }
+
+    #[test]
+    fn boot_rom_unmapped_by_bank_register() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.rom_bank_zero[0x00] = 0x12;
+        mmu.rom_bank_zero[0x100] = 0x34;
+        mmu.boot_rom = Some(vec![0xAB; 0x900]);
+
+        assert_eq!(mmu.read_byte(0x00).unwrap(), 0xAB);
+        // The cartridge header stays visible through the CGB boot ROM
+        assert_eq!(mmu.read_byte(0x100).unwrap(), 0x34);
+
+        mmu.set_bank(0x01);
+        assert!(!mmu.is_boot_rom_mapped());
+        assert_eq!(mmu.read_byte(0x00).unwrap(), 0x12);
+    }
+}
@@ -0,0 +1,127 @@
+use std::collections::VecDeque;
+use crate::mmu::Mmu;
+
+pub const PACKET_SIZE: usize = 16;
+
+// Packets kept until take_sgb_packet, the oldest ones are dropped past this
+const MAX_QUEUED_PACKETS: usize = 64;
+
+// Super Game Boy command packets, sent by the game one bit at a time through P1 bits 4 and 5
+// Both lines low resets the transfer, then every bit is one line pulsed low (bit 4 for a 0, bit 5 for a 1) and both
+// released again. The 128 bits come least significant first and are followed by a 0 stop bit
+#[derive(Clone, Debug, Default)]
+pub struct PacketReceiver {
+    // Bits received since the reset pulse, None outside of a transfer
+    bit_count: Option<usize>,
+    // Set once the lines are released, so that a long pulse only counts once
+    released: bool,
+    packet: [u8; PACKET_SIZE],
+    packets: VecDeque<[u8; PACKET_SIZE]>,
+}
+
+impl PacketReceiver {
+    // Only the P1 select lines matter
+    pub fn write_p1(&mut self, value: u8) {
+        match value & 0x30 {
+            0x00 => {
+                self.bit_count = Some(0);
+                self.released = false;
+                self.packet = [0; PACKET_SIZE];
+            }
+            0x30 => self.released = true,
+            lines => {
+                let Some(bit_count) = self.bit_count.filter(|_| self.released) else {
+                    return;
+                };
+                self.released = false;
+                let bit = lines == 0x10;
+
+                if bit_count < PACKET_SIZE * 8 {
+                    self.packet[bit_count / 8] |= (bit as u8) << (bit_count % 8);
+                    self.bit_count = Some(bit_count + 1);
+                    return;
+                }
+
+                // A 1 where the stop bit should be drops the packet
+                self.bit_count = None;
+                if !bit {
+                    if self.packets.len() == MAX_QUEUED_PACKETS {
+                        self.packets.pop_front();
+                    }
+                    self.packets.push_back(self.packet);
+                }
+            }
+        }
+    }
+
+    pub fn take_packet(&mut self) -> Option<[u8; PACKET_SIZE]> {
+        self.packets.pop_front()
+    }
+}
+
+impl Mmu {
+    // Oldest packet received over P1 that hasn't been taken, only looked for on the SGB (see set_sgb_core)
+    pub fn take_sgb_packet(&mut self) -> Option<[u8; PACKET_SIZE]> {
+        self.sgb_packets.take_packet()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Write a packet to P1 as the games do
+    fn send_packet(mmu: &mut Mmu, packet: &[u8; PACKET_SIZE], stop_bit: bool) {
+        let mut write = |value| mmu.write_byte(0xFF00, value).unwrap();
+        write(0x00);
+        write(0x30);
+
+        let bits = packet.iter().flat_map(|byte| (0..8).map(move |bit| byte & (1 << bit) != 0));
+        for bit in bits.chain([stop_bit]) {
+            write(if bit { 0x10 } else { 0x20 });
+            write(0x30);
+        }
+    }
+
+    #[test]
+    fn pulses_decode_to_packet() {
+        let mut mmu = Mmu::new().unwrap();
+        mmu.set_sgb_core(true);
+
+        // MLT_REQ asking for two players
+        let mlt_req = [0x89, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        send_packet(&mut mmu, &mlt_req, false);
+        // PAL01 with a handful of colours
+        let pal01 = [0x01, 0xFF, 0x7F, 0x1F, 0x00, 0xE0, 0x03, 0x00, 0x7C, 0x10, 0x42, 0x08, 0x21, 0x00, 0x00, 0x00];
+        send_packet(&mut mmu, &pal01, false);
+
+        assert_eq!(mmu.take_sgb_packet(), Some(mlt_req));
+        assert_eq!(mmu.take_sgb_packet(), Some(pal01));
+        assert_eq!(mmu.take_sgb_packet(), None);
+
+        // A missing stop bit, and a packet sent to a DMG
+        send_packet(&mut mmu, &mlt_req, true);
+        assert_eq!(mmu.take_sgb_packet(), None);
+        mmu.set_sgb_core(false);
+        send_packet(&mut mmu, &mlt_req, false);
+        assert_eq!(mmu.take_sgb_packet(), None);
+    }
+
+    #[test]
+    fn held_line_is_a_single_bit() {
+        let mut receiver = PacketReceiver::default();
+        receiver.write_p1(0x00);
+        receiver.write_p1(0x30);
+
+        // Bit 5 held low over three writes, then zeros up to the stop bit
+        [0x10, 0x10, 0x10, 0x30].into_iter().for_each(|value| receiver.write_p1(value));
+        for _ in 1..=PACKET_SIZE * 8 {
+            receiver.write_p1(0x20);
+            receiver.write_p1(0x30);
+        }
+
+        let mut expected = [0; PACKET_SIZE];
+        expected[0] = 0x01;
+        assert_eq!(receiver.take_packet(), Some(expected));
+    }
+}
@@ -0,0 +1,110 @@
+use crate::ppu::Ppu;
+
+// Colours a CGB gives a DMG game in place of the four DMG shades, one set for the background and one per object palette
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CompatibilityPalette {
+    pub background: [[u8; 3]; 4],
+    pub object0: [[u8; 3]; 4],
+    pub object1: [[u8; 3]; 4],
+}
+
+// Palette register a pixel's shade went through, which picks its set of compatibility colours
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum PaletteSource {
+    #[default]
+    Background,
+    Object0,
+    Object1,
+}
+
+impl CompatibilityPalette {
+    pub fn colors(&self, source: PaletteSource) -> &[[u8; 3]; 4] {
+        match source {
+            PaletteSource::Background => &self.background,
+            PaletteSource::Object0 => &self.object0,
+            PaletteSource::Object1 => &self.object1,
+        }
+    }
+}
+
+// What the CGB boot ROM picks for games it has no specific palette for
+pub const DEFAULT_PALETTE: CompatibilityPalette = CompatibilityPalette {
+    background: [[0xFF, 0xFF, 0xFF], [0x7B, 0xFF, 0x31], [0x00, 0x63, 0xC5], [0x00, 0x00, 0x00]],
+    object0: [[0xFF, 0xFF, 0xFF], [0xFF, 0x84, 0x84], [0x94, 0x3A, 0x3A], [0x00, 0x00, 0x00]],
+    object1: [[0xFF, 0xFF, 0xFF], [0xFF, 0x84, 0x84], [0x94, 0x3A, 0x3A], [0x00, 0x00, 0x00]],
+};
+
+// Same shades as a DMG, for players who'd rather not have the colours
+pub const GRAYSCALE_PALETTE: CompatibilityPalette = CompatibilityPalette {
+    background: [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0x00, 0x00, 0x00]],
+    object0: [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0x00, 0x00, 0x00]],
+    object1: [[0xFF, 0xFF, 0xFF], [0xAA, 0xAA, 0xAA], [0x55, 0x55, 0x55], [0x00, 0x00, 0x00]],
+};
+
+impl Ppu {
+    // DEFAULT_PALETTE unless changed, GRAYSCALE_PALETTE keeps the DMG look
+    pub fn set_compatibility_palette(&mut self, palette: CompatibilityPalette) { self.compatibility_palette = palette }
+
+    // Get the RGB colour of a colour id for a DMG game running on a CGB
+    // The id still goes through BGP/OBP0/OBP1, the resulting shade picks the colour from the compatibility palette
+    // Returns None unless a DMG cartridge is running on a CGB
+    pub fn dmg_compatibility_color(&self, is_object: bool, palette: u8, color_id: u8) -> Option<[u8; 3]> {
+        let mmu = self.mmu.borrow();
+
+        if !mmu.is_dmg_compatibility_mode() {
+            return None;
+        }
+
+        let (palette_register, source) = match (is_object, palette) {
+            (false, _) => (mmu.bgp(), PaletteSource::Background),
+            (true, 0) => (mmu.obp0(), PaletteSource::Object0),
+            (true, _) => (mmu.obp1(), PaletteSource::Object1),
+        };
+        let shade = Ppu::apply_palette(palette_register, color_id);
+
+        Some(self.compatibility_palette.colors(source)[shade as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::mmu::Mmu;
+    use super::*;
+
+    #[test]
+    fn dmg_game_on_cgb_uses_default_palette() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().load_cartridge_data(vec![0; 0x8000]).unwrap();
+        mmu.borrow_mut().set_bgp(0xE4);
+        mmu.borrow_mut().set_obp1(0xE4);
+
+        // Still DMG hardware, colours come from the DMG shades
+        assert_eq!(ppu.dmg_compatibility_color(false, 0, 0), None);
+
+        mmu.borrow_mut().set_cgb_core(true);
+        assert_eq!(ppu.dmg_compatibility_color(false, 0, 0), Some(DEFAULT_PALETTE.background[0]));
+        assert_eq!(ppu.dmg_compatibility_color(false, 0, 1), Some([0x7B, 0xFF, 0x31]));
+        assert_eq!(ppu.dmg_compatibility_color(true, 1, 2), Some(DEFAULT_PALETTE.object1[2]));
+    }
+
+    #[test]
+    fn framebuffer_rgba_uses_compatibility_palette() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().load_cartridge_data(vec![0; 0x8000]).unwrap();
+        ppu.completed_frame[0] = 1;
+        ppu.completed_frame[1] = 2;
+        ppu.completed_palettes[1] = PaletteSource::Object0;
+
+        assert_eq!(ppu.framebuffer_rgba()[..8], [0xAA, 0xAA, 0xAA, 0xFF, 0x55, 0x55, 0x55, 0xFF]);
+
+        mmu.borrow_mut().set_cgb_core(true);
+        assert_eq!(ppu.framebuffer_rgba()[..8], [0x7B, 0xFF, 0x31, 0xFF, 0x94, 0x3A, 0x3A, 0xFF]);
+
+        ppu.set_compatibility_palette(GRAYSCALE_PALETTE);
+        assert_eq!(ppu.framebuffer_rgba()[..8], [0xAA, 0xAA, 0xAA, 0xFF, 0x55, 0x55, 0x55, 0xFF]);
+    }
+}
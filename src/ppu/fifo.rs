@@ -0,0 +1,401 @@
+use std::collections::VecDeque;
+use anyhow::Result;
+use crate::ppu::{OAMEntry, Ppu, LCD_WIDTH};
+use crate::ppu::compatibility::PaletteSource;
+use crate::ppu::registers::Register;
+
+// The fetcher reads the tile number, then the low and high bytes of the tile row, taking two dots for each
+const FETCH_DOTS: u8 = 6;
+const SPRITE_FETCH_DOTS: u8 = 6;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+struct SpritePixel {
+    color_id: u8,
+    // OBP0 or OBP1
+    palette: u8,
+    background_priority: bool,
+}
+
+// State of the pixel pipeline for the scanline being drawn
+// The fetcher fills the background FIFO 8 pixels at a time, and one pixel is shifted out to the LCD every dot
+#[derive(Clone, Default)]
+pub(super) struct PixelPipeline {
+    background_fifo: VecDeque<u8>,
+    sprite_fifo: VecDeque<SpritePixel>,
+
+    // Dots spent on the current background fetch, and what it has read so far
+    fetch_dots: u8,
+    tile_address: usize,
+    tile_low: u8,
+    tile_high: u8,
+    // Tile column of the next fetch, counted from SCX for the background and from the left edge of the window
+    fetch_x: u8,
+    // The first tile of a line is fetched twice, the first fetch is thrown away
+    first_fetch: bool,
+
+    // Dots spent in Draw on the current line
+    pub(super) draw_dots: u32,
+    // Pixels still to be dropped from the start of the line for the SCX fine scroll
+    discarded_pixels: u8,
+    lcd_x: u8,
+
+    // Sprites of the line not fetched yet, in drawing order
+    pending_sprites: VecDeque<OAMEntry>,
+    // Dots left on the sprite fetch stalling the LCD
+    sprite_fetch_dots: Option<u8>,
+
+    in_window: bool,
+    // Set once LY matched WY during the frame, the window is only drawn from then on
+    window_y_triggered: bool,
+    // Line of the window drawn next, it only moves on lines where the window was visible
+    window_line_counter: u8,
+}
+
+impl PixelPipeline {
+    // The window starts over from its first line every frame
+    pub(super) fn start_frame(&mut self) {
+        self.window_y_triggered = false;
+        self.window_line_counter = 0;
+    }
+}
+
+impl Ppu {
+    // Reset the pipeline at the start of Draw
+    pub(super) fn start_drawing(&mut self) {
+        let (scx, ly, wy) = {
+            let mmu = self.mmu.borrow();
+            (mmu.scx(), mmu.ly(), mmu.wy())
+        };
+
+        // On DMG the sprite with the lowest X wins, ties going to the one first in OAM (i.e. first in the buffer)
+        // A fetched sprite only fills the transparent pixels of the sprite FIFO, so fetching in this order is what applies it
+        let mut sprites: Vec<OAMEntry> = self.sprite_buffer.iter().flatten().map(|(_, sprite)| *sprite).collect();
+        // Stable, so OAM order is kept between sprites at the same X
+        sprites.sort_by_key(|sprite| sprite.x_position);
+
+        let pipeline = &mut self.pixel_pipeline;
+        pipeline.background_fifo.clear();
+        pipeline.sprite_fifo.clear();
+        pipeline.fetch_dots = 0;
+        pipeline.fetch_x = 0;
+        pipeline.first_fetch = true;
+        pipeline.draw_dots = 0;
+        pipeline.discarded_pixels = scx % 8;
+        pipeline.lcd_x = 0;
+        pipeline.pending_sprites = sprites.into();
+        pipeline.sprite_fetch_dots = None;
+        pipeline.in_window = false;
+        pipeline.window_y_triggered |= ly == wy;
+    }
+
+    // Run the pipeline for a dot, returns true once the last pixel of the line was sent to the LCD
+    pub(super) fn draw_dot(&mut self) -> Result<bool> {
+        self.pixel_pipeline.draw_dots += 1;
+        self.check_window();
+        self.fetch_background()?;
+
+        if self.fetch_sprite()? {
+            return Ok(false);
+        }
+
+        let pipeline = &mut self.pixel_pipeline;
+        let Some(color_id) = pipeline.background_fifo.pop_front() else {
+            return Ok(false);
+        };
+
+        if pipeline.discarded_pixels > 0 {
+            pipeline.discarded_pixels -= 1;
+            return Ok(false);
+        }
+
+        let sprite_pixel = pipeline.sprite_fifo.pop_front().unwrap_or_default();
+        let (shade, source) = self.mix_pixel(color_id, sprite_pixel);
+
+        let ly = self.mmu.borrow().ly() as usize;
+        let pipeline = &mut self.pixel_pipeline;
+        let index = ly * LCD_WIDTH + pipeline.lcd_x as usize;
+        self.framebuffer[index] = shade;
+        self.palettes[index] = source;
+        pipeline.lcd_x += 1;
+
+        let line_done = pipeline.lcd_x as usize == LCD_WIDTH;
+        if line_done && pipeline.in_window {
+            pipeline.window_line_counter += 1;
+        }
+
+        Ok(line_done)
+    }
+
+    // Once the LCD reaches WX-7 on a line below WY, the background is dropped and the fetcher starts over on the window
+    fn check_window(&mut self) {
+        let pipeline = &self.pixel_pipeline;
+        if pipeline.in_window || !pipeline.window_y_triggered || !self.check_register(Register::WindowDisplayEnable) {
+            return;
+        }
+
+        if (pipeline.lcd_x as u16 + 7) < self.mmu.borrow().wx() as u16 {
+            return;
+        }
+
+        let pipeline = &mut self.pixel_pipeline;
+        pipeline.in_window = true;
+        pipeline.background_fifo.clear();
+        pipeline.fetch_dots = 0;
+        pipeline.fetch_x = 0;
+    }
+
+    // Advance the background fetcher by a dot, a fetched row waits until the FIFO is empty to be pushed
+    fn fetch_background(&mut self) -> Result<()> {
+        if self.pixel_pipeline.fetch_dots < FETCH_DOTS {
+            self.pixel_pipeline.fetch_dots += 1;
+
+            match self.pixel_pipeline.fetch_dots {
+                2 => self.pixel_pipeline.tile_address = self.tile_row_address()?,
+                4 => self.pixel_pipeline.tile_low = self.mmu.borrow().ppu_read_byte(self.pixel_pipeline.tile_address)?,
+                FETCH_DOTS => {
+                    self.pixel_pipeline.tile_high = self.mmu.borrow().ppu_read_byte(self.pixel_pipeline.tile_address + 1)?;
+
+                    if std::mem::take(&mut self.pixel_pipeline.first_fetch) {
+                        self.pixel_pipeline.fetch_dots = 0;
+                    }
+                }
+                _ => {}
+            }
+
+            return Ok(());
+        }
+
+        let pipeline = &mut self.pixel_pipeline;
+        if pipeline.background_fifo.is_empty() {
+            pipeline.background_fifo.extend(Ppu::decode_tile_row(pipeline.tile_low, pipeline.tile_high));
+            pipeline.fetch_x = pipeline.fetch_x.wrapping_add(1);
+            pipeline.fetch_dots = 0;
+        }
+
+        Ok(())
+    }
+
+    // Address of the row of the tile under the fetcher, in the background or window tile map
+    fn tile_row_address(&self) -> Result<usize> {
+        let mmu = self.mmu.borrow();
+        let pipeline = &self.pixel_pipeline;
+
+        let (tile_map_select, column, line) = if pipeline.in_window {
+            (Register::WindowTileMapSelect, pipeline.fetch_x, pipeline.window_line_counter)
+        }
+        else {
+            (Register::BGTileMapSelect, (mmu.scx() / 8).wrapping_add(pipeline.fetch_x), mmu.ly().wrapping_add(mmu.scy()))
+        };
+
+        let tile_map = if self.check_register(tile_map_select) { 0x9C00 } else { 0x9800 };
+        let tile_index = mmu.ppu_read_byte(tile_map + (line as usize / 8) * 32 + (column as usize & 0x1F))?;
+
+        Ok(Ppu::tile_data_address(tile_index, self.check_register(Register::TileDataSelect)) + (line as usize % 8) * 2)
+    }
+
+    // Fetch the next sprite once the LCD reaches it, returns true while the LCD is stalled
+    // The fetch waits for the background fetcher to reach its last dot, so a sprite costs 11 - min(5, (x + SCX) % 8) dots,
+    // or 6 when the fetcher is already done, e.g. for the other sprites on the same tile
+    fn fetch_sprite(&mut self) -> Result<bool> {
+        let pipeline = &self.pixel_pipeline;
+        let dots = match pipeline.sprite_fetch_dots {
+            Some(dots) => dots,
+            None => {
+                let Some(sprite) = pipeline.pending_sprites.front() else {
+                    return Ok(false);
+                };
+
+                // Sprite X is offset by 8, so that X=0 hides it off the left edge
+                let reached = pipeline.lcd_x as u16 + 8 >= sprite.x_position as u16;
+                if pipeline.discarded_pixels > 0 || !reached || !self.check_register(Register::SpriteEnable) {
+                    return Ok(false);
+                }
+                if pipeline.fetch_dots < FETCH_DOTS - 1 || pipeline.background_fifo.is_empty() {
+                    return Ok(true);
+                }
+
+                SPRITE_FETCH_DOTS
+            }
+        };
+
+        if dots > 1 {
+            self.pixel_pipeline.sprite_fetch_dots = Some(dots - 1);
+            return Ok(true);
+        }
+
+        self.pixel_pipeline.sprite_fetch_dots = None;
+        if let Some(sprite) = self.pixel_pipeline.pending_sprites.pop_front() {
+            self.load_sprite_row(sprite)?;
+        }
+
+        Ok(true)
+    }
+
+    // Mix the row of a sprite into the sprite FIFO, over the pixels left transparent by the sprites fetched before it
+    fn load_sprite_row(&mut self, sprite: OAMEntry) -> Result<()> {
+        let height = if self.check_register(Register::SpriteSize) { 16 } else { 8 };
+        let attributes = sprite.decode_attributes();
+
+        let (low, high) = {
+            let mmu = self.mmu.borrow();
+
+            let mut line = (mmu.ly() as u16 + 16).wrapping_sub(sprite.y_position as u16) % height;
+            if attributes.y_flip {
+                line = height - 1 - line;
+            }
+
+            // 8x16 sprites ignore the lowest bit of the tile index
+            let tile_index = if height == 16 { sprite.tile_index & 0xFE } else { sprite.tile_index };
+            let address = Ppu::tile_data_address(tile_index, true) + line as usize * 2;
+
+            (mmu.ppu_read_byte(address)?, mmu.ppu_read_byte(address + 1)?)
+        };
+
+        let mut row = Ppu::decode_tile_row(low, high);
+        if attributes.x_flip {
+            row.reverse();
+        }
+
+        let pipeline = &mut self.pixel_pipeline;
+        for (i, color_id) in row.into_iter().enumerate() {
+            // Pixels left of the LCD are already gone
+            let Some(index) = (sprite.x_position as usize + i).checked_sub(pipeline.lcd_x as usize + 8) else {
+                continue;
+            };
+
+            if pipeline.sprite_fifo.len() <= index {
+                pipeline.sprite_fifo.resize(index + 1, SpritePixel::default());
+            }
+            if pipeline.sprite_fifo[index].color_id == 0 {
+                pipeline.sprite_fifo[index] = SpritePixel { color_id, palette: attributes.dmg_palette, background_priority: attributes.background_priority };
+            }
+        }
+
+        Ok(())
+    }
+
+    // Shade of the pixel sent to the LCD and the palette it went through, sprites are hidden by background colours 1-3
+    // when they have the priority bit
+    fn mix_pixel(&self, color_id: u8, sprite: SpritePixel) -> (u8, PaletteSource) {
+        let mmu = self.mmu.borrow();
+        let color_id = if self.check_register(Register::BGEnable) { color_id } else { 0 };
+
+        if sprite.color_id != 0 && !(sprite.background_priority && color_id != 0) {
+            let (palette, source) = if sprite.palette == 0 {
+                (mmu.obp0(), PaletteSource::Object0)
+            }
+            else {
+                (mmu.obp1(), PaletteSource::Object1)
+            };
+            return (Ppu::apply_palette(palette, sprite.color_id), source);
+        }
+
+        (Ppu::apply_palette(mmu.bgp(), color_id), PaletteSource::Background)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::mmu::Mmu;
+    use super::*;
+
+    // PPU at the start of a line's Draw, with a background tile whose pixels are colours 0, 1, 2, 3, 0, 1, 2, 3
+    fn setup() -> (Rc<RefCell<Mmu>>, Ppu) {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        {
+            let mut mmu = mmu.borrow_mut();
+            mmu.set_lcdc(0x91);
+            mmu.set_bgp(0b11_10_01_00);
+            mmu.set_obp0(0b11_10_01_00);
+            mmu.write_slice(0x8000, &[0x55, 0x33].repeat(8)).unwrap();
+        }
+
+        (mmu.clone(), Ppu::new(mmu))
+    }
+
+    // Run Draw to the end of the line, returns the dots it took
+    fn draw_line(ppu: &mut Ppu) -> u32 {
+        ppu.start_drawing();
+        (1..).find(|_| ppu.draw_dot().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn draws_background_tiles() {
+        let (_, mut ppu) = setup();
+
+        assert_eq!(draw_line(&mut ppu), 172);
+        assert_eq!(ppu.framebuffer[..8], [0, 1, 2, 3, 0, 1, 2, 3]);
+        assert_eq!(ppu.framebuffer[152..160], [0, 1, 2, 3, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn scx_discards_first_pixels() {
+        let (mmu, mut ppu) = setup();
+        mmu.borrow_mut().set_scx(3);
+
+        assert_eq!(draw_line(&mut ppu), 175);
+        assert_eq!(ppu.framebuffer[..8], [3, 0, 1, 2, 3, 0, 1, 2]);
+    }
+
+    #[test]
+    fn sprite_stalls_and_covers_background() {
+        let (mmu, mut ppu) = setup();
+        {
+            let mut mmu = mmu.borrow_mut();
+            mmu.set_lcdc(0x93);
+            // Solid colour 3 tile
+            mmu.write_slice(0x8010, &[0xFF; 16]).unwrap();
+        }
+        ppu.sprite_buffer[0] = Some((0, OAMEntry { y_position: 16, x_position: 12, tile_index: 1, attributes: 0 }));
+
+        // Waits for the background fetcher 1 dot on top of the sprite's 6
+        assert_eq!(draw_line(&mut ppu), 179);
+        assert_eq!(ppu.framebuffer[..12], [0, 1, 2, 3, 3, 3, 3, 3, 3, 3, 3, 3]);
+
+        // With priority, only background colour 0 lets the sprite through
+        ppu.sprite_buffer[0] = Some((0, OAMEntry { y_position: 16, x_position: 12, tile_index: 1, attributes: 0x80 }));
+        draw_line(&mut ppu);
+        assert_eq!(ppu.framebuffer[..12], [0, 1, 2, 3, 3, 1, 2, 3, 3, 1, 2, 3]);
+    }
+
+    #[test]
+    fn lower_x_sprite_has_priority() {
+        let (mmu, mut ppu) = setup();
+        {
+            let mut mmu = mmu.borrow_mut();
+            mmu.set_lcdc(0x93);
+            // Solid colour 1, 2 and 3 tiles
+            mmu.write_slice(0x8010, &[0xFF, 0x00].repeat(8)).unwrap();
+            mmu.write_slice(0x8020, &[0x00, 0xFF].repeat(8)).unwrap();
+            mmu.write_slice(0x8030, &[0xFF; 16]).unwrap();
+        }
+
+        // Earlier in OAM but further right, overlapping the other two on screen pixels 4-7
+        ppu.sprite_buffer[0] = Some((0, OAMEntry { y_position: 16, x_position: 12, tile_index: 1, attributes: 0 }));
+        ppu.sprite_buffer[1] = Some((1, OAMEntry { y_position: 16, x_position: 8, tile_index: 2, attributes: 0 }));
+        ppu.sprite_buffer[2] = Some((2, OAMEntry { y_position: 16, x_position: 8, tile_index: 3, attributes: 0 }));
+
+        draw_line(&mut ppu);
+        assert_eq!(ppu.framebuffer[..14], [2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn window_replaces_background() {
+        let (mmu, mut ppu) = setup();
+        {
+            let mut mmu = mmu.borrow_mut();
+            // Window on, using the 0x9C00 tile map filled with the solid tile
+            mmu.set_lcdc(0xF1);
+            mmu.write_slice(0x8010, &[0xFF; 16]).unwrap();
+            mmu.write_slice(0x9C00, &[1; 32]).unwrap();
+            mmu.set_wx(7 + 80);
+            mmu.set_wy(0);
+        }
+
+        assert_eq!(draw_line(&mut ppu), 178);
+        assert_eq!(ppu.framebuffer[76..84], [0, 1, 2, 3, 3, 3, 3, 3]);
+        assert_eq!(ppu.pixel_pipeline.window_line_counter, 1);
+    }
+}
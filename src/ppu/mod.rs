@@ -1,148 +1,644 @@
 mod registers;
+mod fifo;
+pub mod compatibility;
 
 use std::cell::RefCell;
 use std::rc::Rc;
 use anyhow::Result;
-use crate::cpu::Cpu;
+use crate::cpu::interrupts::Interrupt;
 use crate::mmu::{MemoryRegion, Mmu};
+use crate::ppu::compatibility::{CompatibilityPalette, PaletteSource, DEFAULT_PALETTE};
+use crate::ppu::fifo::PixelPipeline;
 use crate::ppu::registers::Register;
 
-#[derive(Copy, Clone, Default, Eq, PartialEq)]
-struct OAMEntry {
-    y_position: u8,
-    x_position: u8,
-    tile_index: u8,
-    attributes: u8,
+pub const LCD_WIDTH: usize = 160;
+pub const LCD_HEIGHT: usize = 144;
+
+// RGBA colours of the four DMG shades, from lightest to darkest
+const DMG_SHADES: [[u8; 4]; 4] = [
+    [0xFF, 0xFF, 0xFF, 0xFF],
+    [0xAA, 0xAA, 0xAA, 0xFF],
+    [0x55, 0x55, 0x55, 0xFF],
+    [0x00, 0x00, 0x00, 0xFF],
+];
+
+pub const OAM_ENTRY_COUNT: u8 = 40;
+
+const SCANLINE_T_CYCLES: u32 = 456;
+
+const STAT_MODE_MASK: u8 = 0b11;
+const STAT_COINCIDENCE: u8 = 1 << 2;
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct OAMEntry {
+    pub y_position: u8,
+    pub x_position: u8,
+    pub tile_index: u8,
+    pub attributes: u8,
 }
 
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct OAMAttributes {
+    // When set, background colours 1-3 are drawn over the sprite
+    pub background_priority: bool,
+    pub y_flip: bool,
+    pub x_flip: bool,
+    // OBP0 or OBP1
+    pub dmg_palette: u8,
+    pub cgb_bank: u8,
+    pub cgb_palette: u8,
+}
+
+impl OAMEntry {
+    pub fn decode_attributes(&self) -> OAMAttributes {
+        let attributes = self.attributes;
+
+        OAMAttributes {
+            background_priority: attributes & (1 << 7) != 0,
+            y_flip: attributes & (1 << 6) != 0,
+            x_flip: attributes & (1 << 5) != 0,
+            dmg_palette: (attributes >> 4) & 1,
+            cgb_bank: (attributes >> 3) & 1,
+            cgb_palette: attributes & 0x7,
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
 enum PPUMode {
     HBlank,
     VBlank,
-    OAMScan(u8), // The u8 corresponds to the current sprite id that is being retrieved (0-9)
-    Draw(DrawStep),
+    OAMScan(u8), // The u8 corresponds to the current sprite id that is being retrieved (0-39)
+    Draw,
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
-enum DrawStep {
-    Fetch {
-        x_pos: u8,
-        window_line_counter: u8,
-        is_window: bool,
+impl PPUMode {
+    // Value of the mode in the lower two bits of STAT
+    fn id(&self) -> u8 {
+        match self {
+            PPUMode::HBlank => 0,
+            PPUMode::VBlank => 1,
+            PPUMode::OAMScan(_) => 2,
+            PPUMode::Draw => 3,
+        }
     }
 }
 
+#[derive(Clone)]
 pub struct Ppu {
     mmu: Rc<RefCell<Mmu>>,
-    cpu: Rc<RefCell<Cpu>>,
 
-    sprite_buffer: [Option<OAMEntry>; 10],
+    // OAM index and entry of the sprites selected for the current scanline, in OAM order
+    sprite_buffer: [Option<(u8, OAMEntry)>; 10],
+
+    // One palette index (0-3) per pixel
+    framebuffer: [u8; LCD_WIDTH * LCD_HEIGHT],
+    // Copy of the framebuffer taken when entering VBlank, so that frontends never see a half drawn frame
+    completed_frame: [u8; LCD_WIDTH * LCD_HEIGHT],
+    // Palette register each pixel went through, for the colours of DMG games on a CGB
+    palettes: [PaletteSource; LCD_WIDTH * LCD_HEIGHT],
+    completed_palettes: [PaletteSource; LCD_WIDTH * LCD_HEIGHT],
 
     current_mode: PPUMode,
+    pixel_pipeline: PixelPipeline,
+    // T-cycles elapsed since the start of the current scanline
     current_t_cycles_count: u32,
+    // Set when entering VBlank, cleared by take_frame_ready
+    frame_ready: bool,
+    completed_frames: u64,
+    // LCDC bit 7 as of the last emulation_loop call
+    display_enabled: bool,
+    // Last state of the STAT interrupt line, the interrupt is only requested when it goes high
+    stat_line: bool,
+
+    // Colours used for DMG games when running as a CGB
+    compatibility_palette: CompatibilityPalette,
 }
 
 impl Ppu {
-    pub fn new(mmu: Rc<RefCell<Mmu>>, cpu: Rc<RefCell<Cpu>>) -> Self {
+    pub fn new(mmu: Rc<RefCell<Mmu>>) -> Self {
         Self {
             mmu,
-            cpu,
             sprite_buffer: [None; 10],
+            framebuffer: [0; LCD_WIDTH * LCD_HEIGHT],
+            completed_frame: [0; LCD_WIDTH * LCD_HEIGHT],
+            palettes: [PaletteSource::Background; LCD_WIDTH * LCD_HEIGHT],
+            completed_palettes: [PaletteSource::Background; LCD_WIDTH * LCD_HEIGHT],
             current_mode: PPUMode::OAMScan(0),
+            pixel_pipeline: PixelPipeline::default(),
             current_t_cycles_count: 0,
+            frame_ready: false,
+            completed_frames: 0,
+            display_enabled: true,
+            stat_line: false,
+            compatibility_palette: DEFAULT_PALETTE,
+        }
+    }
+
+    // Take over the state of a snapshot, while still drawing from this PPU's MMU
+    pub fn restore(&mut self, snapshot: &Ppu) {
+        *self = Ppu { mmu: self.mmu.clone(), ..snapshot.clone() };
+    }
+
+    // Last completed frame
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.completed_frame
+    }
+
+    // Frame being drawn, lines above LY are from the current frame and the others still from the previous one
+    pub fn frame_in_progress(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    // T-cycles spent in Draw on the current or last line, 172 plus the SCX, window and sprite penalties
+    // HBlank takes the rest of the 456 T-cycle scanline
+    pub fn draw_t_cycles(&self) -> u32 {
+        self.pixel_pipeline.draw_dots
+    }
+
+    // Line the PPU is on, the next one to be completed in frame_in_progress
+    pub fn current_line(&self) -> u8 {
+        self.mmu.borrow().ly()
+    }
+
+    // The last completed frame with every shade turned into its RGBA colour
+    // DMG games on a CGB get the colours of the compatibility palette instead of the DMG shades
+    pub fn framebuffer_rgba(&self) -> Vec<u8> {
+        if !self.mmu.borrow().is_dmg_compatibility_mode() {
+            return self.completed_frame.iter().flat_map(|shade| Self::shade_rgba(*shade)).collect();
         }
+
+        self.completed_frame.iter().zip(&self.completed_palettes).flat_map(|(shade, source)| {
+            let [r, g, b] = self.compatibility_palette.colors(*source)[*shade as usize & 0x3];
+            [r, g, b, 0xFF]
+        }).collect()
     }
 
+    pub fn shade_rgba(shade: u8) -> [u8; 4] {
+        DMG_SHADES[shade as usize & 0x3]
+    }
+
+    // Turn the two bytes of a tile row into the colour id (0-3) of each of its pixels, leftmost first
+    // The first byte holds the low bit of every colour id and the second byte the high bit
+    pub fn decode_tile_row(low: u8, high: u8) -> [u8; 8] {
+        std::array::from_fn(|x| {
+            let bit = 7 - x;
+            (((high >> bit) & 1) << 1) | ((low >> bit) & 1)
+        })
+    }
+
+    // Shade (0-3) a colour id is drawn with through BGP, OBP0 or OBP1, colour id n takes bits 2n+1 and 2n of the palette
+    pub fn apply_palette(palette: u8, color_id: u8) -> u8 {
+        (palette >> ((color_id & 0x3) * 2)) & 0x3
+    }
+
+    // Address of a background/window tile, LCDC bit 4 switches between 0x8000 unsigned and 0x9000 signed addressing
+    pub fn tile_data_address(tile_index: u8, unsigned_addressing: bool) -> usize {
+        if unsigned_addressing {
+            0x8000 + tile_index as usize * 16
+        }
+        else {
+            (0x9000 + tile_index as i8 as isize * 16) as usize
+        }
+    }
+
+    // Advance the PPU by the T-cycles (dots) taken by the last CPU step
     pub fn emulation_loop(&mut self, t_cycles: u8) -> Result<()> {
+        let display_enabled = self.check_register(Register::DisplayEnable);
+        if display_enabled != self.display_enabled {
+            self.display_enabled = display_enabled;
+
+            if display_enabled {
+                self.turn_on();
+            }
+            else {
+                self.turn_off();
+            }
+        }
+
+        // The PPU is stopped while the display is off
+        if !display_enabled {
+            return Ok(());
+        }
+
+        for _ in 0..t_cycles {
+            self.tick()?;
+        }
+
+        Ok(())
+    }
+
+    fn tick(&mut self) -> Result<()> {
+        self.current_t_cycles_count += 1;
+
         match self.current_mode {
             PPUMode::OAMScan(sprite_id) => {
-                self.oam_scan(sprite_id)?;
+                // Each sprite takes 2 TCycles to check, for a total of 80 TCycles
+                if self.current_t_cycles_count.is_multiple_of(2) {
+                    self.oam_scan(sprite_id)?;
 
-                // The OAMScan mode takes 80 TCycles
-                self.current_t_cycles_count += 2;
-                if self.current_t_cycles_count >= 80 {
-                    self.current_mode = PPUMode::Draw{ 0: DrawStep::Fetch { x_pos: 0, window_line_counter: 0, is_window: false } };
+                    if sprite_id + 1 == OAM_ENTRY_COUNT {
+                        self.start_drawing();
+                        self.set_mode(PPUMode::Draw);
+                    }
+                    else {
+                        self.current_mode = PPUMode::OAMScan(sprite_id + 1);
+                    }
                 }
-                else {
-                    self.current_mode = PPUMode::OAMScan(sprite_id + 1);
+            },
+            // Draw lasts until the pixel pipeline has sent the whole line to the LCD
+            PPUMode::Draw => {
+                if self.draw_dot()? {
+                    self.set_mode(PPUMode::HBlank);
                 }
-
-                Ok(())
             },
-            PPUMode::Draw(step) => {
-                match step {
-                    DrawStep::Fetch { x_pos, window_line_counter, is_window } => {
-                        let tile_number = self.fetch_tile_number(x_pos, window_line_counter, is_window);
-
-                    }
+            PPUMode::HBlank | PPUMode::VBlank => {
+                if self.current_t_cycles_count >= SCANLINE_T_CYCLES {
+                    self.next_scanline();
                 }
+            },
+        }
+
+        Ok(())
+    }
+
+    // The screen goes blank, LY is held at 0 and STAT reports HBlank until the display is turned back on
+    fn turn_off(&mut self) {
+        self.current_t_cycles_count = 0;
+        self.framebuffer = [0; LCD_WIDTH * LCD_HEIGHT];
+        self.completed_frame = self.framebuffer;
+        self.palettes = [PaletteSource::Background; LCD_WIDTH * LCD_HEIGHT];
+        self.completed_palettes = self.palettes;
+
+        self.set_ly(0);
+        self.set_mode(PPUMode::HBlank);
+        self.pixel_pipeline.start_frame();
+        self.stat_line = false;
+    }
+
+    // Restart from the top of a frame, the first scanline after turning the display on is 4 T-cycles shorter
+    fn turn_on(&mut self) {
+        self.current_t_cycles_count = 4;
+        self.start_oam_scan();
+    }
+
+    fn next_scanline(&mut self) {
+        self.current_t_cycles_count = 0;
 
-                Ok(())
+        let ly = self.mmu.borrow().ly() + 1;
+        match ly {
+            144 => {
+                self.set_ly(ly);
+                self.enter_vblank();
+            },
+            145..=153 => self.set_ly(ly),
+            _ => {
+                self.set_ly(if ly > 153 { 0 } else { ly });
+                self.start_oam_scan();
             },
-            _ => { todo!() }
         }
     }
 
+    fn start_oam_scan(&mut self) {
+        self.sprite_buffer = [None; 10];
+        self.set_mode(PPUMode::OAMScan(0));
+    }
+
+    fn set_ly(&mut self, ly: u8) {
+        {
+            let mut mmu = self.mmu.borrow_mut();
+            mmu.set_ly(ly);
+
+            let coincidence = if ly == mmu.lyc() { STAT_COINCIDENCE } else { 0 };
+            let stat = mmu.stat();
+            mmu.set_stat((stat & !STAT_COINCIDENCE) | coincidence);
+        }
+
+        self.update_stat_interrupt();
+    }
+
+    // Switch mode and mirror it in the lower two bits of STAT
+    fn set_mode(&mut self, mode: PPUMode) {
+        self.current_mode = mode;
+
+        {
+            let mut mmu = self.mmu.borrow_mut();
+            let stat = mmu.stat();
+            mmu.set_stat((stat & !STAT_MODE_MASK) | self.current_mode.id());
+        }
+
+        self.update_stat_interrupt();
+    }
+
+    // The LCD interrupt fires on the rising edge of the OR of every enabled STAT source
+    fn update_stat_interrupt(&mut self) {
+        let mut mmu = self.mmu.borrow_mut();
+        let stat = mmu.stat();
+
+        let line = match self.current_mode {
+            PPUMode::HBlank => stat & (1 << 3) != 0,
+            PPUMode::VBlank => stat & (1 << 4) != 0,
+            PPUMode::OAMScan(_) => stat & (1 << 5) != 0,
+            PPUMode::Draw => false,
+        } || (stat & (1 << 6) != 0 && stat & STAT_COINCIDENCE != 0);
+
+        if line && !self.stat_line && self.display_enabled {
+            mmu.request_interrupt(Interrupt::LCD);
+        }
+        self.stat_line = line;
+    }
+
+    // Interrupts are raised through IF in the MMU, the PPU never talks to the CPU directly
+    fn enter_vblank(&mut self) {
+        self.set_mode(PPUMode::VBlank);
+        self.pixel_pipeline.start_frame();
+        self.completed_frame = self.framebuffer;
+        self.completed_palettes = self.palettes;
+        self.mmu.borrow_mut().request_interrupt(Interrupt::VBlank);
+
+        self.frame_ready = true;
+        self.completed_frames += 1;
+    }
+
+    // Whether a frame was completed since the last call, for frontends to present it exactly once
+    pub fn take_frame_ready(&mut self) -> bool {
+        std::mem::take(&mut self.frame_ready)
+    }
+
+    // Number of times the PPU entered VBlank
+    pub fn completed_frames(&self) -> u64 { self.completed_frames }
+
     // Fetch a single sprite in OAM and add it to the buffer if it meets the requirements
     // Each call takes 2 cycles
     fn oam_scan(&mut self, sprite_id: u8) -> Result<()> {
         let sprite = self.fetch_oam_entry(sprite_id)?;
         if self.oam_entry_check(&sprite) {
             let i = self.sprite_buffer.iter().position(|x| x.is_none()).unwrap();
-            self.sprite_buffer[i] = Some(sprite);
+            self.sprite_buffer[i] = Some((sprite_id, sprite));
         }
 
         Ok(())
     }
 
-    fn fetch_oam_entry(&self, id: u8) -> Result<OAMEntry> {
+    // Whether the sprite at this OAM index was selected for the current scanline during the last OAM scan
+    // Looked up by index, since identical entries (e.g. a cleared OAM) aren't necessarily all selected
+    pub fn is_sprite_buffered(&self, id: u8) -> bool {
+        self.sprite_buffer.iter().flatten().any(|(buffered_id, _)| *buffered_id == id)
+    }
+
+    pub fn fetch_oam_entry(&self, id: u8) -> Result<OAMEntry> {
         let mmu = self.mmu.borrow();
 
         let address = MemoryRegion::SpriteAttributionTable as usize + (4 * id as usize);
-        let y_position = mmu.read_byte(address)?;
-        let x_position = mmu.read_byte(address + 1)?;
-        let tile_index = mmu.read_byte(address + 2)?;
-        let attributes = mmu.read_byte(address + 3)?;
+        let y_position = mmu.ppu_read_byte(address)?;
+        let x_position = mmu.ppu_read_byte(address + 1)?;
+        let tile_index = mmu.ppu_read_byte(address + 2)?;
+        let attributes = mmu.ppu_read_byte(address + 3)?;
 
         Ok(OAMEntry { y_position, x_position, tile_index, attributes })
     }
 
+    // A sprite goes in the buffer when the current line crosses it, X doesn't matter (sprites at X=0 still take a slot)
+    // OAM Y is offset by 16, so that Y=0 hides even a 16 pixel tall sprite above the screen
     fn oam_entry_check(&self, entry: &OAMEntry) -> bool {
+        let height = if self.check_register(Register::SpriteSize) { 16 } else { 8 };
+        let line = self.mmu.borrow().ly() as u16 + 16;
+        let top = entry.y_position as u16;
+
+        self.sprite_buffer.contains(&None) && (top..top + height).contains(&line)
+    }
+
+    // Get the RGB colour of a colour id from the CGB palette RAM
+    // Returns None outside of CGB mode, where colours go through BGP/OBP0/OBP1 instead
+    pub fn cgb_color(&self, is_object: bool, palette: u8, color_id: u8) -> Option<[u8; 3]> {
         let mmu = self.mmu.borrow();
 
-        if !self.sprite_buffer.contains(&None) ||
-            entry.x_position < 0 ||
-            mmu.ly() + 16 < entry.y_position ||
-            mmu.ly() + 16 >= entry.y_position + if self.check_register(Register::SpriteSize) { 16 } else { 8 }
-        {
-            return false;
+        if !mmu.is_cgb_mode() {
+            return None;
         }
 
-        true
+        Some(Self::decode_rgb555(mmu.cgb_palette_color(is_object, palette, color_id)))
     }
 
-    fn fetch_tile_number(&self, x_pos: u8, window_line_counter: u8, is_window: bool) -> usize {
-        let mmu = self.mmu.borrow();
+    // Convert a 15-bit colour (0bXBBBBBGGGGGRRRRR) into 8-bit RGB components
+    fn decode_rgb555(color: u16) -> [u8; 3] {
+        let scale = |component: u16| -> u8 {
+            let component = (component & 0x1F) as u8;
+            (component << 3) | (component >> 2)
+        };
+
+        [scale(color), scale(color >> 5), scale(color >> 10)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vblank_requested_without_cpu() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+
+        ppu.enter_vblank();
+
+        assert!(matches!(ppu.current_mode, PPUMode::VBlank));
+        assert_ne!(mmu.borrow().iflag() & (1 << Interrupt::VBlank as u8), 0);
+    }
+
+    #[test]
+    fn scanline_timing() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().set_lcdc(0x91);
+
+        ppu.emulation_loop(80).unwrap();
+        assert_eq!(mmu.borrow().stat() & STAT_MODE_MASK, 3);
+        ppu.emulation_loop(172).unwrap();
+        assert_eq!(mmu.borrow().stat() & STAT_MODE_MASK, 0);
+        ppu.emulation_loop(204).unwrap();
+        assert_eq!(mmu.borrow().stat() & STAT_MODE_MASK, 2);
+        assert_eq!(mmu.borrow().ly(), 1);
+    }
 
-        let background_tilemap_base = if self.check_register(Register::BGTileMapSelect) { 0x9C00 } else { 0x9800 };
-        let mut offset: usize = x_pos as usize;
+    #[test]
+    fn sprites_lengthen_draw_and_shorten_hblank() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().set_lcdc(0x93);
+        // Two sprites on the first tile, then one aligned with the fourth
+        mmu.borrow_mut().write_slice(0xFE00, &[16, 8, 0, 0, 16, 8, 0, 0, 16, 32, 0, 0]).unwrap();
 
-        // if not window
-        if !is_window {
-            offset += mmu.scx() as usize / 8;
+        while mmu.borrow().ly() == 0 {
+            ppu.emulation_loop(1).unwrap();
         }
 
-        offset &= 0x1f;
+        // T-cycles spent in each mode on the second line, which the sprites also cover
+        let mut durations = [0; 4];
+        while mmu.borrow().ly() == 1 {
+            durations[(mmu.borrow().stat() & STAT_MODE_MASK) as usize] += 1;
+            ppu.emulation_loop(1).unwrap();
+        }
+
+        assert_eq!(durations[2], 80);
+        assert_eq!(durations[3], 172 + 11 + 6 + 11);
+        assert_eq!(durations[0], 456 - 80 - durations[3]);
+        assert_eq!(ppu.draw_t_cycles(), durations[3]);
+    }
 
-        // If background
-        if !is_window {
-            offset += 32 * (((mmu.ly() + mmu.scy()) & 0xFF) / 8) as usize;
+    #[test]
+    fn display_disable_holds_ly_at_zero() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().set_lcdc(0x91);
+
+        for _ in 0..100 {
+            ppu.emulation_loop(200).unwrap();
         }
-        else {
-            offset += 32 * (window_line_counter / 8) as usize;
+        assert_ne!(mmu.borrow().ly(), 0);
+
+        mmu.borrow_mut().set_lcdc(0x11);
+        for _ in 0..1000 {
+            ppu.emulation_loop(200).unwrap();
+            assert_eq!(mmu.borrow().ly(), 0);
         }
+        assert_eq!(mmu.borrow().stat() & STAT_MODE_MASK, 0);
+
+        mmu.borrow_mut().set_lcdc(0x91);
+        ppu.emulation_loop(4).unwrap();
+        assert_eq!(mmu.borrow().stat() & STAT_MODE_MASK, 2);
+    }
 
-        offset &= 0x3FF;
+    #[test]
+    fn one_frame_ready_per_frame() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().set_lcdc(0x91);
+
+        let mut frames = 0;
+        for _ in 0..3 * SCANLINE_T_CYCLES * 154 / 4 {
+            ppu.emulation_loop(4).unwrap();
+            if ppu.take_frame_ready() {
+                frames += 1;
+            }
+        }
 
-        offset
+        assert_eq!(frames, 3);
+        assert!(!ppu.take_frame_ready());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn frame_in_progress_drawn_up_to_current_line() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        mmu.borrow_mut().set_lcdc(0x91);
+        let run_lines = |ppu: &mut Ppu, lines: u32| (0..lines * 2).for_each(|_| ppu.emulation_loop(228).unwrap());
+
+        mmu.borrow_mut().set_bgp(0xFF);
+        run_lines(&mut ppu, 154);
+        mmu.borrow_mut().set_bgp(0x00);
+        run_lines(&mut ppu, 72);
+
+        assert_eq!(ppu.current_line(), 72);
+        let (drawn, previous) = ppu.frame_in_progress().split_at(72 * LCD_WIDTH);
+        assert!(drawn.iter().all(|shade| *shade == 0));
+        assert!(previous.iter().all(|shade| *shade == 3));
+        assert!(ppu.framebuffer().iter().all(|shade| *shade == 3));
+    }
+
+    #[test]
+    fn decode_tile_row_colour_ids() {
+        // Example from the Pan Docs tile data section
+        assert_eq!(Ppu::decode_tile_row(0x3C, 0x7E), [0, 2, 3, 3, 3, 3, 2, 0]);
+        assert_eq!(Ppu::decode_tile_row(0xFF, 0x00), [1; 8]);
+        assert_eq!(Ppu::decode_tile_row(0x00, 0x00), [0; 8]);
+    }
+
+    #[test]
+    fn apply_palette_shades() {
+        assert_eq!((0..=3).map(|id| Ppu::apply_palette(0b11_10_01_00, id)).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!((0..=3).map(|id| Ppu::apply_palette(0b00_01_10_11, id)).collect::<Vec<_>>(), [3, 2, 1, 0]);
+    }
+
+    #[test]
+    fn tile_data_addressing_modes() {
+        assert_eq!(Ppu::tile_data_address(0x01, true), 0x8010);
+        assert_eq!(Ppu::tile_data_address(0x80, true), 0x8800);
+        assert_eq!(Ppu::tile_data_address(0x00, false), 0x9000);
+        assert_eq!(Ppu::tile_data_address(0xFF, false), 0x8FF0);
+    }
+
+    #[test]
+    fn decode_oam_attributes() {
+        let entry = OAMEntry { attributes: 0b1110_0000, ..Default::default() };
+        assert_eq!(entry.decode_attributes(), OAMAttributes {
+            background_priority: true, y_flip: true, x_flip: true, dmg_palette: 0, cgb_bank: 0, cgb_palette: 0,
+        });
+
+        let entry = OAMEntry { attributes: 0b0001_1101, ..Default::default() };
+        assert_eq!(entry.decode_attributes(), OAMAttributes {
+            background_priority: false, y_flip: false, x_flip: false, dmg_palette: 1, cgb_bank: 1, cgb_palette: 5,
+        });
+    }
+
+    #[test]
+    fn decode_rgb555_components() {
+        assert_eq!(Ppu::decode_rgb555(0x0000), [0x00, 0x00, 0x00]);
+        assert_eq!(Ppu::decode_rgb555(0x7FFF), [0xFF, 0xFF, 0xFF]);
+        assert_eq!(Ppu::decode_rgb555(0x001F), [0xFF, 0x00, 0x00]);
+        assert_eq!(Ppu::decode_rgb555(0x03E0), [0x00, 0xFF, 0x00]);
+        assert_eq!(Ppu::decode_rgb555(0x7C00), [0x00, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn sprite_height_edges() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let ppu = Ppu::new(mmu.clone());
+        // Covers lines 10-17 when 8 pixels tall, 10-25 when 16
+        let sprite = OAMEntry { y_position: 26, ..Default::default() };
+        let visible_on = |line: u8| {
+            mmu.borrow_mut().set_ly(line);
+            ppu.oam_entry_check(&sprite)
+        };
+
+        mmu.borrow_mut().set_lcdc(0x91);
+        assert!(!visible_on(9));
+        assert!(visible_on(10));
+        assert!(visible_on(17));
+        assert!(!visible_on(18));
+
+        mmu.borrow_mut().set_lcdc(0x95);
+        assert!(!visible_on(9));
+        assert!(visible_on(10));
+        assert!(visible_on(25));
+        assert!(!visible_on(26));
+
+        // Near the bottom of the screen the sprite's end doesn't wrap around
+        let bottom = OAMEntry { y_position: 0xFF, ..Default::default() };
+        mmu.borrow_mut().set_ly(143);
+        assert!(!ppu.oam_entry_check(&bottom));
+    }
+
+    #[test]
+    fn full_sprite_buffer_rejects_sprites() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        let sprite = OAMEntry { y_position: 16, x_position: 0, ..Default::default() };
+
+        assert!(ppu.oam_entry_check(&sprite));
+        ppu.sprite_buffer = [Some((0, sprite)); 10];
+        assert!(!ppu.oam_entry_check(&sprite));
+    }
+
+    #[test]
+    fn buffered_sprites_are_found_by_index() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let mut ppu = Ppu::new(mmu.clone());
+        // Two identical sprites on line 0, only the second one is scanned
+        mmu.borrow_mut().write_slice(0xFE00, &[16, 8, 0, 0, 16, 8, 0, 0]).unwrap();
+
+        ppu.oam_scan(1).unwrap();
+        assert!(ppu.is_sprite_buffered(1));
+        assert!(!ppu.is_sprite_buffered(0));
+    }
+}
@@ -0,0 +1,72 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+use crate::ppu::{LCD_HEIGHT, LCD_WIDTH};
+
+// 70224 T-cycles at 4194304 Hz
+const FRAME_DELAY: Duration = Duration::from_nanos(16_742_706);
+
+// Browsers and most viewers play delays under 2 centiseconds at 10 centiseconds, so only every other frame is written
+// That leaves about 3.35 centiseconds per written frame, written as delays of 3 or 4 that add up to the emulated time
+const FRAMES_PER_WRITTEN_FRAME: usize = 2;
+
+// NeuQuant only runs on frames with more than 256 colours, which the at most 64 of the CGB palettes never reach
+// So the speed never actually matters, and the fastest one is picked
+const QUANTIZATION_SPEED: i32 = 30;
+
+// Streams frames to an animated GIF as they are added, so recording doesn't keep them in memory
+pub struct GifRecorder {
+    encoder: Encoder<BufWriter<File>>,
+    frame_count: usize,
+    // Total delay of the frames written so far, in centiseconds
+    written_delay: u64,
+}
+
+impl GifRecorder {
+    pub fn new(path: &Path) -> Result<Self> {
+        let file = File::create(path).context("Failed to create recording")?;
+
+        let mut encoder = Encoder::new(BufWriter::new(file), LCD_WIDTH as u16, LCD_HEIGHT as u16, &[])?;
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        Ok(Self { encoder, frame_count: 0, written_delay: 0 })
+    }
+
+    pub fn frame_count(&self) -> usize { self.frame_count }
+
+    // Add a frame of LCD_WIDTH * LCD_HEIGHT RGBA pixels, see FRAMES_PER_WRITTEN_FRAME for the ones that are dropped
+    pub fn add_frame(&mut self, mut rgba: Vec<u8>) -> Result<()> {
+        if rgba.len() != LCD_WIDTH * LCD_HEIGHT * 4 {
+            anyhow::bail!("Frame doesn't match the LCD size");
+        }
+
+        self.frame_count += 1;
+        if !(self.frame_count - 1).is_multiple_of(FRAMES_PER_WRITTEN_FRAME) {
+            return Ok(());
+        }
+
+        // The written frame stands in for the dropped ones after it, delays are rounded against the total so they don't drift
+        let shown_until = (self.frame_count - 1 + FRAMES_PER_WRITTEN_FRAME) as u128 * FRAME_DELAY.as_micros();
+        let delay = (shown_until + 5_000) / 10_000 - self.written_delay as u128;
+        self.written_delay += delay as u64;
+
+        let mut frame = Frame::from_rgba_speed(LCD_WIDTH as u16, LCD_HEIGHT as u16, &mut rgba, QUANTIZATION_SPEED);
+        frame.delay = delay as u16;
+        frame.dispose = DisposalMethod::Background;
+        self.encoder.write_frame(&frame)?;
+
+        Ok(())
+    }
+
+    // Write the end of the GIF and flush it to the file, which dropping the recorder would do without reporting errors
+    // Returns the number of recorded frames, including the ones that were dropped
+    pub fn finish(self) -> Result<usize> {
+        let writer = self.encoder.into_inner().context("Failed to finish recording")?;
+        writer.into_inner().map_err(|error| error.into_error()).context("Failed to write recording")?;
+
+        Ok(self.frame_count)
+    }
+}
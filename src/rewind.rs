@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use crate::cpu::CpuSnapshot;
+use crate::mmu::Mmu;
+use crate::ppu::Ppu;
+
+pub const REPLAY_SECONDS: usize = 10;
+const FRAMES_PER_SECOND: usize = 60;
+// Frames between two snapshots, the ones in between are rerun from the buttons held during each of them
+const SNAPSHOT_INTERVAL: usize = 10;
+
+// Whole emulator state at the start of a frame
+#[derive(Clone)]
+pub struct Snapshot {
+    pub mmu: Mmu,
+    pub cpu: CpuSnapshot,
+    pub ppu: Ppu,
+    pub frame_count: u64,
+}
+
+// Snapshots of the last few seconds, with the input of every frame since the oldest one
+pub struct RewindBuffer {
+    // Oldest first, each with the buttons held during the frames up to the next snapshot
+    entries: VecDeque<(Snapshot, Vec<u8>)>,
+    capacity: usize,
+}
+
+impl RewindBuffer {
+    pub fn new(seconds: usize) -> Self {
+        let capacity = (seconds * FRAMES_PER_SECOND).div_ceil(SNAPSHOT_INTERVAL).max(1);
+
+        Self { entries: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    // Whether the next record_frame call needs a snapshot
+    pub fn needs_snapshot(&self) -> bool {
+        self.entries.back().is_none_or(|(_, inputs)| inputs.len() == SNAPSHOT_INTERVAL)
+    }
+
+    // Called at the start of every frame, the snapshot is only taken when needs_snapshot says so
+    pub fn record_frame(&mut self, snapshot: Option<Snapshot>, buttons: u8) {
+        if let Some(snapshot) = snapshot {
+            if self.entries.len() == self.capacity {
+                self.entries.pop_front();
+            }
+            self.entries.push_back((snapshot, Vec::with_capacity(SNAPSHOT_INTERVAL)));
+        }
+
+        if let Some((_, inputs)) = self.entries.back_mut() {
+            inputs.push(buttons);
+        }
+    }
+
+    pub fn oldest(&self) -> Option<&Snapshot> {
+        self.entries.front().map(|(snapshot, _)| snapshot)
+    }
+
+    // Buttons held during each frame since the oldest snapshot
+    pub fn inputs(&self) -> impl Iterator<Item = u8> + '_ {
+        self.entries.iter().flat_map(|(_, inputs)| inputs.iter().copied())
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.entries.iter().map(|(_, inputs)| inputs.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::cpu::Cpu;
+    use super::*;
+
+    #[test]
+    fn oldest_snapshots_are_dropped() {
+        let mmu = Rc::new(RefCell::new(Mmu::new().unwrap()));
+        let cpu = Cpu::new(mmu.clone());
+        let ppu = Ppu::new(mmu.clone());
+        // 1 second is 6 snapshots of 10 frames
+        let mut rewind = RewindBuffer::new(1);
+
+        for frame in 0..100 {
+            let snapshot = rewind.needs_snapshot().then(|| Snapshot { mmu: mmu.borrow().clone(), cpu: cpu.snapshot(), ppu: ppu.clone(), frame_count: frame });
+            rewind.record_frame(snapshot, frame as u8);
+        }
+
+        assert_eq!(rewind.frame_count(), 60);
+        assert_eq!(rewind.oldest().unwrap().frame_count, 40);
+        assert_eq!(rewind.inputs().next(), Some(40));
+    }
+}
@@ -0,0 +1,84 @@
+use std::time::Duration;
+#[cfg(feature = "audio")]
+use anyhow::{Context, Result};
+#[cfg(feature = "audio")]
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+#[cfg(feature = "audio")]
+use crate::apu::AudioConfig;
+#[cfg(feature = "audio")]
+use crate::apu::ring_buffer::Consumer;
+
+// Fill levels of the audio buffer between which the frames keep to the frame limiter's schedule
+const LOW_WATERMARK: f32 = 0.25;
+const HIGH_WATERMARK: f32 = 0.75;
+
+// How long to wait before looking at the buffer again when it is too full
+pub const WAIT_DURATION: Duration = Duration::from_millis(2);
+
+// When the audio output is running, frames are paced by how full its buffer is instead of by the frame limiter alone,
+// so that the emulator can never drift away from the audio clock
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum AudioPacing {
+    // The buffer is draining, run the next frame right away
+    Hurry,
+    OnSchedule,
+    // The buffer is nearly full, hold the next frame back until some of it has been played
+    Wait,
+}
+
+pub fn audio_pacing(queued_frames: usize, buffer_frames: usize) -> AudioPacing {
+    let fill = queued_frames as f32 / buffer_frames.max(1) as f32;
+
+    if fill < LOW_WATERMARK {
+        AudioPacing::Hurry
+    }
+    else if fill > HIGH_WATERMARK {
+        AudioPacing::Wait
+    }
+    else {
+        AudioPacing::OnSchedule
+    }
+}
+
+// Stream playing the APU output on the default device, stopped when dropped
+#[cfg(feature = "audio")]
+pub struct AudioOutput {
+    _stream: cpal::Stream,
+}
+
+#[cfg(feature = "audio")]
+impl AudioOutput {
+    pub fn new(mut consumer: Consumer, config: AudioConfig) -> Result<Self> {
+        let device = cpal::default_host().default_output_device().context("No audio output device")?;
+        let stream_config = cpal::StreamConfig {
+            channels: 2,
+            sample_rate: cpal::SampleRate(config.sample_rate),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        let stream = device.build_output_stream(
+            &stream_config,
+            move |data: &mut [f32], _| { consumer.take_interleaved(data); },
+            |error| eprintln!("Audio output error: {}", error),
+            None,
+        ).context("Failed to open the audio output")?;
+        stream.play().context("Failed to start the audio output")?;
+
+        Ok(Self { _stream: stream })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pacing_follows_buffer_fill() {
+        assert_eq!(audio_pacing(0, 4096), AudioPacing::Hurry);
+        assert_eq!(audio_pacing(1000, 4096), AudioPacing::Hurry);
+        assert_eq!(audio_pacing(2048, 4096), AudioPacing::OnSchedule);
+        assert_eq!(audio_pacing(3072, 4096), AudioPacing::OnSchedule);
+        assert_eq!(audio_pacing(3500, 4096), AudioPacing::Wait);
+        assert_eq!(audio_pacing(4096, 4096), AudioPacing::Wait);
+    }
+}
@@ -0,0 +1,171 @@
+use anyhow::Result;
+use crate::cpu::registers::Registers;
+
+// Address breakpoint of the debugger, disabled ones stay listed but don't stop Run
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Breakpoint {
+    pub address: u16,
+    pub enabled: bool,
+    // Number of hits to let through before stopping
+    pub ignore_count: u32,
+    // Only hits where the condition is true are counted
+    pub condition: Option<Condition>,
+    pub hits: u32,
+}
+
+impl Breakpoint {
+    pub fn new(address: u16) -> Self {
+        Self { address, enabled: true, ignore_count: 0, condition: None, hits: 0 }
+    }
+
+    // Called when PC reaches the breakpoint, returns whether Run should stop
+    pub fn hit(&mut self, registers: &Registers) -> bool {
+        if !self.enabled || !self.condition.as_ref().is_none_or(|condition| condition.evaluate(registers)) {
+            return false;
+        }
+
+        self.hits += 1;
+        self.hits > self.ignore_count
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Operand {
+    A, F, B, C, D, E, H, L,
+    AF, BC, DE, HL, SP, PC,
+}
+
+impl Operand {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "A" => Operand::A,
+            "F" => Operand::F,
+            "B" => Operand::B,
+            "C" => Operand::C,
+            "D" => Operand::D,
+            "E" => Operand::E,
+            "H" => Operand::H,
+            "L" => Operand::L,
+            "AF" => Operand::AF,
+            "BC" => Operand::BC,
+            "DE" => Operand::DE,
+            "HL" => Operand::HL,
+            "SP" => Operand::SP,
+            "PC" => Operand::PC,
+            _ => return None,
+        })
+    }
+
+    fn value(self, registers: &Registers) -> u16 {
+        match self {
+            Operand::A => registers.a() as u16,
+            Operand::F => registers.f() as u16,
+            Operand::B => registers.b() as u16,
+            Operand::C => registers.c() as u16,
+            Operand::D => registers.d() as u16,
+            Operand::E => registers.e() as u16,
+            Operand::H => registers.h() as u16,
+            Operand::L => registers.l() as u16,
+            Operand::AF => registers.af(),
+            Operand::BC => registers.bc(),
+            Operand::DE => registers.de(),
+            Operand::HL => registers.hl(),
+            Operand::SP => registers.sp(),
+            Operand::PC => registers.pc(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    // Longest operators first so that <= isn't taken for <
+    const OPERATORS: [(&'static str, Self); 6] = [
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+}
+
+// `register op value`, e.g. `A == 0x10` or `HL >= C000`, values are always hexadecimal
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Condition {
+    operand: Operand,
+    comparison: Comparison,
+    value: u16,
+}
+
+impl Condition {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("Invalid condition '{}', expected e.g. A == 0x10", expression);
+
+        let (index, operator, comparison) = Comparison::OPERATORS.iter()
+            .filter_map(|(operator, comparison)| expression.find(operator).map(|index| (index, *operator, *comparison)))
+            .min_by_key(|(index, _, _)| *index)
+            .ok_or_else(invalid)?;
+
+        let operand = Operand::from_name(expression[..index].trim()).ok_or_else(invalid)?;
+
+        let value = expression[index + operator.len()..].trim();
+        let value = value.strip_prefix("0x").or_else(|| value.strip_prefix('$')).unwrap_or(value);
+        let value = u16::from_str_radix(value, 16).map_err(|_| invalid())?;
+
+        Ok(Self { operand, comparison, value })
+    }
+
+    pub fn evaluate(&self, registers: &Registers) -> bool {
+        let register = self.operand.value(registers);
+
+        match self.comparison {
+            Comparison::Equal => register == self.value,
+            Comparison::NotEqual => register != self.value,
+            Comparison::Less => register < self.value,
+            Comparison::LessOrEqual => register <= self.value,
+            Comparison::Greater => register > self.value,
+            Comparison::GreaterOrEqual => register >= self.value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluate_conditions() {
+        let mut registers = Registers::new();
+        registers.set_a(0x10);
+        registers.set_h(0xC0);
+        registers.set_l(0x00);
+
+        assert!(Condition::parse("A == 0x10").unwrap().evaluate(&registers));
+        assert!(!Condition::parse("a!=10").unwrap().evaluate(&registers));
+        assert!(Condition::parse("HL == $C000").unwrap().evaluate(&registers));
+        assert!(Condition::parse("HL <= C000").unwrap().evaluate(&registers));
+        assert!(!Condition::parse("HL < C000").unwrap().evaluate(&registers));
+        assert!(Condition::parse("A > F").unwrap().evaluate(&registers));
+        assert!(Condition::parse("A = 10").is_err());
+        assert!(Condition::parse("X == 10").is_err());
+    }
+
+    #[test]
+    fn ignore_count_skips_first_hits() {
+        let registers = Registers::new();
+        let mut breakpoint = Breakpoint { ignore_count: 2, ..Breakpoint::new(0x150) };
+
+        assert!(!breakpoint.hit(&registers));
+        assert!(!breakpoint.hit(&registers));
+        assert!(breakpoint.hit(&registers));
+    }
+}
@@ -0,0 +1,130 @@
+use std::time::{Duration, Instant};
+
+// Weight of the newest frame in the moving average
+const SMOOTHING: f64 = 0.1;
+
+// 3x5 glyphs, one row per byte with the leftmost pixel in bit 2
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const GLYPH_SCALE: usize = 2;
+
+fn glyph(character: char) -> [u8; GLYPH_HEIGHT] {
+    match character {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'P' => [0b111, 0b101, 0b111, 0b100, 0b100],
+        'S' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        _ => [0; GLYPH_HEIGHT],
+    }
+}
+
+// Frame rate measured from the wall-clock time between redraws
+#[derive(Default)]
+pub struct FpsCounter {
+    last_frame: Option<Instant>,
+    average_frame_time: Option<Duration>,
+}
+
+impl FpsCounter {
+    // Call on every redraw
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(last_frame) = self.last_frame {
+            self.add_frame_time(now.saturating_duration_since(last_frame));
+        }
+        self.last_frame = Some(now);
+    }
+
+    // Exponential moving average, the first frame seeds it
+    pub fn add_frame_time(&mut self, frame_time: Duration) {
+        self.average_frame_time = Some(match self.average_frame_time {
+            Some(average) => average.mul_f64(1.0 - SMOOTHING) + frame_time.mul_f64(SMOOTHING),
+            None => frame_time,
+        });
+    }
+
+    pub fn average_frame_time(&self) -> Option<Duration> { self.average_frame_time }
+
+    pub fn fps(&self) -> Option<f64> {
+        self.average_frame_time.filter(|average| !average.is_zero()).map(|average| 1.0 / average.as_secs_f64())
+    }
+
+    pub fn text(&self) -> String {
+        match (self.fps(), self.average_frame_time) {
+            (Some(fps), Some(average)) => format!("{:.1} FPS {:.1} MS", fps, average.as_secs_f64() * 1000.0),
+            _ => String::from("- FPS"),
+        }
+    }
+}
+
+// Draw white text on a black box in the top-left corner of an RGBA buffer
+pub fn draw_text(buffer: &mut [u8], buffer_width: usize, text: &str) {
+    let glyph_advance = (GLYPH_WIDTH + 1) * GLYPH_SCALE;
+    let box_width = (text.chars().count() * glyph_advance + GLYPH_SCALE).min(buffer_width);
+    let box_height = (GLYPH_HEIGHT + 2) * GLYPH_SCALE;
+
+    let mut set_pixel = |x: usize, y: usize, value: u8| {
+        if x < buffer_width {
+            let index = (y * buffer_width + x) * 4;
+            if let Some(pixel) = buffer.get_mut(index..index + 4) {
+                pixel.copy_from_slice(&[value, value, value, 0xFF]);
+            }
+        }
+    };
+
+    for y in 0..box_height {
+        for x in 0..box_width {
+            set_pixel(x, y, 0x00);
+        }
+    }
+
+    for (i, character) in text.chars().enumerate() {
+        for (row, bits) in glyph(character).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+
+                for dy in 0..GLYPH_SCALE {
+                    for dx in 0..GLYPH_SCALE {
+                        let x = GLYPH_SCALE + i * glyph_advance + column * GLYPH_SCALE + dx;
+                        let y = GLYPH_SCALE + row * GLYPH_SCALE + dy;
+                        set_pixel(x, y, 0xFF);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moving_average_of_frame_times() {
+        let mut counter = FpsCounter::default();
+        assert_eq!(counter.fps(), None);
+
+        counter.add_frame_time(Duration::from_millis(20));
+        assert_eq!(counter.average_frame_time(), Some(Duration::from_millis(20)));
+
+        // 0.9 * 20 + 0.1 * 10 = 19, then 0.9 * 19 + 0.1 * 29 = 20
+        counter.add_frame_time(Duration::from_millis(10));
+        assert_eq!(counter.average_frame_time().unwrap().as_micros(), 19_000);
+        counter.add_frame_time(Duration::from_millis(29));
+        assert_eq!(counter.average_frame_time().unwrap().as_micros(), 20_000);
+
+        assert!((counter.fps().unwrap() - 50.0).abs() < 0.01);
+    }
+}
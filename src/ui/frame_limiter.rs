@@ -0,0 +1,88 @@
+use std::time::{Duration, Instant};
+
+// A frame is 70224 T-cycles at 4194304 Hz, so the LCD refreshes at ~59.73 fps
+pub const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+
+pub struct FrameLimiter {
+    frame_duration: Duration,
+    frame_start: Instant,
+    turbo: bool,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self {
+            frame_duration: FRAME_DURATION,
+            frame_start: Instant::now(),
+            turbo: false,
+        }
+    }
+}
+
+impl FrameLimiter {
+    pub fn is_turbo(&self) -> bool { self.turbo }
+    pub fn set_turbo(&mut self, turbo: bool) { self.turbo = turbo }
+
+    // How long to wait at `now` before the next frame may start
+    pub fn remaining(&self, now: Instant) -> Duration {
+        sleep_duration(now.saturating_duration_since(self.frame_start), self.frame_duration, self.turbo)
+    }
+
+    // Start the next frame if its time has come, otherwise return the instant at which it will
+    pub fn try_start_frame(&mut self, now: Instant) -> Result<(), Instant> {
+        let remaining = self.remaining(now);
+        if !remaining.is_zero() {
+            return Err(now + remaining);
+        }
+
+        // Keep the frames on a fixed schedule, unless we have fallen more than a frame behind
+        let next_frame_start = self.frame_start + self.frame_duration;
+        self.frame_start = if self.turbo || now > next_frame_start + self.frame_duration { now } else { next_frame_start };
+
+        Ok(())
+    }
+}
+
+// Time left to sleep once `elapsed` has been spent on the current frame, turbo never sleeps
+pub fn sleep_duration(elapsed: Duration, frame_duration: Duration, turbo: bool) -> Duration {
+    if turbo {
+        return Duration::ZERO;
+    }
+
+    frame_duration.saturating_sub(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sleep_duration_pads_to_frame_duration() {
+        assert_eq!(sleep_duration(Duration::from_millis(10), FRAME_DURATION, false), FRAME_DURATION - Duration::from_millis(10));
+        assert_eq!(sleep_duration(Duration::ZERO, FRAME_DURATION, false), FRAME_DURATION);
+        assert_eq!(sleep_duration(Duration::from_millis(20), FRAME_DURATION, false), Duration::ZERO);
+    }
+
+    #[test]
+    fn turbo_never_sleeps() {
+        assert_eq!(sleep_duration(Duration::ZERO, FRAME_DURATION, true), Duration::ZERO);
+
+        let mut frame_limiter = FrameLimiter::default();
+        frame_limiter.set_turbo(true);
+        assert_eq!(frame_limiter.remaining(frame_limiter.frame_start), Duration::ZERO);
+        assert_eq!(frame_limiter.try_start_frame(frame_limiter.frame_start), Ok(()));
+    }
+
+    #[test]
+    fn frames_stay_on_schedule() {
+        let mut frame_limiter = FrameLimiter::default();
+        let start = frame_limiter.frame_start;
+
+        let too_early = start + Duration::from_millis(5);
+        assert_eq!(frame_limiter.try_start_frame(too_early), Err(start + FRAME_DURATION));
+
+        // Starting slightly late doesn't push back the following frames
+        assert_eq!(frame_limiter.try_start_frame(start + FRAME_DURATION + Duration::from_millis(1)), Ok(()));
+        assert_eq!(frame_limiter.frame_start, start + FRAME_DURATION);
+    }
+}
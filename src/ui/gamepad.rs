@@ -0,0 +1,177 @@
+#[cfg(feature = "gamepad")]
+use anyhow::Result;
+#[cfg(feature = "gamepad")]
+use gilrs::{Axis, EventType, Gilrs};
+use crate::mmu::joypad::Button;
+#[cfg(feature = "gamepad")]
+use crate::Rainier;
+
+// Analog stick values closer to the center than this are ignored
+pub const STICK_DEADZONE: f32 = 0.3;
+
+// Direction of an analog axis outside of the deadzone: -1 (left/down), 0 (centered) or 1 (right/up)
+pub fn axis_to_dpad(value: f32, deadzone: f32) -> i8 {
+    if value <= -deadzone {
+        -1
+    }
+    else if value >= deadzone {
+        1
+    }
+    else {
+        0
+    }
+}
+
+// Buttons held through the controllers, so that the joypad only sees changes and the keyboard keeps its own buttons
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ControllerState {
+    // One bit per button, set by the controller buttons including the d-pad
+    buttons: u8,
+    // Last direction of the left stick on each axis, see axis_to_dpad
+    stick_x: i8,
+    stick_y: i8,
+}
+
+impl ControllerState {
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.buttons |= 1 << button as u8;
+        }
+        else {
+            self.buttons &= !(1 << button as u8);
+        }
+    }
+
+    pub fn set_stick(&mut self, x: i8, y: i8) {
+        self.stick_x = x;
+        self.stick_y = y;
+    }
+
+    pub fn stick(&self) -> (i8, i8) {
+        (self.stick_x, self.stick_y)
+    }
+
+    // A direction is held by either the d-pad or the stick
+    pub fn is_held(&self, button: Button) -> bool {
+        let stick = match button {
+            Button::Left => self.stick_x < 0,
+            Button::Right => self.stick_x > 0,
+            Button::Down => self.stick_y < 0,
+            Button::Up => self.stick_y > 0,
+            _ => false,
+        };
+
+        stick || self.buttons & (1 << button as u8) != 0
+    }
+
+    // Buttons whose held state differs from the previous state, with their new state
+    pub fn changes_from(&self, previous: &Self) -> Vec<(Button, bool)> {
+        Button::VALUES.into_iter()
+            .filter(|&button| self.is_held(button) != previous.is_held(button))
+            .map(|button| (button, self.is_held(button)))
+            .collect()
+    }
+}
+
+// Connected controllers, polled once per frame. gilrs picks up controllers plugged in while running
+#[cfg(feature = "gamepad")]
+pub struct Gamepad {
+    gilrs: Gilrs,
+    state: ControllerState,
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepad {
+    pub fn new() -> Result<Self> {
+        let gilrs = Gilrs::new().map_err(|error| anyhow::anyhow!("Failed to initialise gamepad input: {}", error))?;
+
+        Ok(Self { gilrs, state: ControllerState::default() })
+    }
+
+    // Forward every pending controller event to the joypad, only for the buttons whose state changed
+    pub fn poll(&mut self, rainier: &mut Rainier) {
+        while let Some(event) = self.gilrs.next_event() {
+            let previous = self.state;
+            let (stick_x, stick_y) = self.state.stick();
+
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = Self::map_button(button) {
+                        self.state.set_button(button, true);
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = Self::map_button(button) {
+                        self.state.set_button(button, false);
+                    }
+                }
+                EventType::AxisChanged(Axis::LeftStickX, value, _) => {
+                    self.state.set_stick(axis_to_dpad(value, STICK_DEADZONE), stick_y);
+                }
+                // gilrs reports up as positive
+                EventType::AxisChanged(Axis::LeftStickY, value, _) => {
+                    self.state.set_stick(stick_x, axis_to_dpad(value, STICK_DEADZONE));
+                }
+                // Don't leave buttons stuck down when the controller is unplugged
+                EventType::Disconnected => self.state = ControllerState::default(),
+                _ => {}
+            }
+
+            for (button, pressed) in self.state.changes_from(&previous) {
+                rainier.set_button(button, pressed);
+            }
+        }
+    }
+
+    fn map_button(button: gilrs::Button) -> Option<Button> {
+        Some(match button {
+            gilrs::Button::DPadUp => Button::Up,
+            gilrs::Button::DPadDown => Button::Down,
+            gilrs::Button::DPadLeft => Button::Left,
+            gilrs::Button::DPadRight => Button::Right,
+            gilrs::Button::South => Button::A,
+            gilrs::Button::East => Button::B,
+            gilrs::Button::Select => Button::Select,
+            gilrs::Button::Start => Button::Start,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn axis_to_dpad_deadzone() {
+        assert_eq!(axis_to_dpad(0.0, STICK_DEADZONE), 0);
+        assert_eq!(axis_to_dpad(0.29, STICK_DEADZONE), 0);
+        assert_eq!(axis_to_dpad(-0.29, STICK_DEADZONE), 0);
+        assert_eq!(axis_to_dpad(0.3, STICK_DEADZONE), 1);
+        assert_eq!(axis_to_dpad(-1.0, STICK_DEADZONE), -1);
+        assert_eq!(axis_to_dpad(0.5, 0.8), 0);
+    }
+
+    #[test]
+    fn stick_and_dpad_share_directions() {
+        let mut state = ControllerState::default();
+        state.set_button(Button::Left, true);
+
+        // The stick moving within the same direction, or away from it, leaves the d-pad press alone
+        let previous = state;
+        state.set_stick(0, 0);
+        assert!(state.changes_from(&previous).is_empty());
+        let previous = state;
+        state.set_stick(-1, 0);
+        assert!(state.changes_from(&previous).is_empty());
+        let previous = state;
+        state.set_stick(1, 0);
+        assert_eq!(state.changes_from(&previous), vec![(Button::Right, true)]);
+
+        // Unplugging only releases what the controller held
+        let previous = state;
+        state = ControllerState::default();
+        assert_eq!(state.changes_from(&previous), vec![(Button::Right, false), (Button::Left, false)]);
+        assert!(ControllerState::default().changes_from(&ControllerState::default()).is_empty());
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use crate::mmu::joypad::Button;
+
+// Loaded from the working directory at startup if it exists
+pub const KEY_BINDINGS_FILE: &str = "keybindings.toml";
+
+// Keys are named after winit's physical key codes (KeyZ, ArrowUp, Enter...), the terminal keys are translated to these names
+const DEFAULT_BINDINGS: [(Button, &str); 8] = [
+    (Button::Right, "ArrowRight"),
+    (Button::Left, "ArrowLeft"),
+    (Button::Up, "ArrowUp"),
+    (Button::Down, "ArrowDown"),
+    (Button::A, "KeyX"),
+    (Button::B, "KeyZ"),
+    (Button::Select, "Backspace"),
+    (Button::Start, "Enter"),
+];
+
+fn button_from_name(name: &str) -> Option<Button> {
+    Some(match name {
+        "right" => Button::Right,
+        "left" => Button::Left,
+        "up" => Button::Up,
+        "down" => Button::Down,
+        "a" => Button::A,
+        "b" => Button::B,
+        "select" => Button::Select,
+        "start" => Button::Start,
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyBindings {
+    buttons: HashMap<String, Button>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self { buttons: DEFAULT_BINDINGS.iter().map(|(button, key)| (key.to_string(), *button)).collect() }
+    }
+}
+
+impl KeyBindings {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Self::parse(&contents).with_context(|| format!("Invalid key bindings in {}", path.display()))
+    }
+
+    // One `button = "Key"` per line, with # comments. Buttons left out keep their default key
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut keys: HashMap<Button, String> = DEFAULT_BINDINGS.iter().map(|(button, key)| (*button, key.to_string())).collect();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() || line.starts_with('[') {
+                continue;
+            }
+
+            let (button, key) = line.split_once('=').ok_or_else(|| anyhow::anyhow!("Line {}: expected button = \"Key\"", number + 1))?;
+            let button = button_from_name(button.trim()).ok_or_else(|| anyhow::anyhow!("Line {}: unknown button '{}'", number + 1, button.trim()))?;
+            let key = key.trim().strip_prefix('"').and_then(|key| key.strip_suffix('"'))
+                .ok_or_else(|| anyhow::anyhow!("Line {}: the key name must be quoted", number + 1))?;
+
+            keys.insert(button, key.to_string());
+        }
+
+        let mut buttons = HashMap::new();
+        for button in Button::VALUES {
+            let key = keys.remove(&button).ok_or_else(|| anyhow::anyhow!("{:?} is not bound", button))?;
+            if let Some(other) = buttons.insert(key.clone(), button) {
+                return Err(anyhow::anyhow!("{} is bound to both {:?} and {:?}", key, other, button));
+            }
+        }
+
+        Ok(Self { buttons })
+    }
+
+    pub fn button(&self, key: &str) -> Option<Button> {
+        self.buttons.get(key).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_key_bindings() {
+        let bindings = KeyBindings::parse("# WASD layout\nup = \"KeyW\"\nleft = \"KeyA\"\ndown = \"KeyS\"\nright = \"KeyD\"\na = \"KeyK\" # jump\n").unwrap();
+
+        assert_eq!(bindings.button("KeyW"), Some(Button::Up));
+        assert_eq!(bindings.button("KeyK"), Some(Button::A));
+        assert_eq!(bindings.button("ArrowUp"), None);
+        // Not in the file, so still on the default key
+        assert_eq!(bindings.button("KeyZ"), Some(Button::B));
+        assert_eq!(bindings.button("Enter"), Some(Button::Start));
+
+        assert!(KeyBindings::parse("turbo = \"Tab\"").is_err());
+        assert!(KeyBindings::parse("a = \"KeyZ\"").is_err());
+    }
+}
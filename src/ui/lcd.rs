@@ -1,38 +1,192 @@
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Instant;
+use anyhow::Result;
 use pixels::{Pixels, SurfaceTexture};
 use winit::{
     application::ApplicationHandler,
-    event::WindowEvent,
-    event_loop::ActiveEventLoop,
-    window::{Window, WindowId},
+    event::{ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow},
+    window::{Fullscreen, Window, WindowId},
 };
 use winit::dpi::LogicalSize;
+use winit::keyboard::{KeyCode, PhysicalKey};
+use crate::Rainier;
+use crate::mmu::joypad::Button;
+use crate::ui::audio::{self, AudioPacing};
+#[cfg(feature = "audio")]
+use crate::ui::audio::AudioOutput;
+use crate::ui::fps_overlay::{self, FpsCounter};
+use crate::ui::frame_limiter::FrameLimiter;
+use crate::ui::key_bindings::KeyBindings;
+#[cfg(feature = "gamepad")]
+use crate::ui::gamepad::Gamepad;
+use crate::ui::{replay_path, screenshot_path};
 
 pub const WIDTH: u32 = 160;
 pub const HEIGHT: u32 = 144;
-pub const SCALING_FACTOR: u32 = 2;
+pub const DEFAULT_SCALING_FACTOR: u32 = 2;
+pub const MAX_SCALING_FACTOR: u32 = 6;
 
 pub struct LCD {
+    rainier: Rc<RefCell<Rainier>>,
     pixels: Option<Pixels<'static>>,
     window: Option<Arc<Window>>,
-    pixel_buffer: [u8; (WIDTH * SCALING_FACTOR * HEIGHT * SCALING_FACTOR * 4) as usize],
+    // Window pixels per Game Boy pixel, cycled with F4
+    scale: u32,
+    pixel_buffer: Vec<u8>,
+    frame_limiter: FrameLimiter,
+    fps_counter: FpsCounter,
+    // Toggled with F3
+    show_fps: bool,
+    // Scale to go back to when leaving fullscreen (F11)
+    windowed_scale: Option<u32>,
+    key_bindings: KeyBindings,
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<Gamepad>,
+    // Paces the frames while it is playing, see start_audio
+    #[cfg(feature = "audio")]
+    audio: Option<AudioOutput>,
 }
 
-impl Default for LCD {
-    fn default() -> Self {
-        let pixel_buffer = [0xFF; (WIDTH * SCALING_FACTOR * HEIGHT * SCALING_FACTOR * 4) as usize];
+impl LCD {
+    pub fn new(rainier: Rc<RefCell<Rainier>>, scale: u32) -> Self {
+        let scale = scale.clamp(1, MAX_SCALING_FACTOR);
         Self {
-            pixels: None, window: None, pixel_buffer
+            rainier, pixels: None, window: None, scale, pixel_buffer: Self::blank_buffer(scale), frame_limiter: FrameLimiter::default(),
+            fps_counter: FpsCounter::default(), show_fps: false, windowed_scale: None,
+            key_bindings: KeyBindings::default(),
+            // Keep going with the keyboard only if gamepads aren't supported on this platform
+            #[cfg(feature = "gamepad")]
+            gamepad: Gamepad::new().inspect_err(|error| eprintln!("{:#}", error)).ok(),
+            #[cfg(feature = "audio")]
+            audio: None,
         }
     }
+
+    fn blank_buffer(scale: u32) -> Vec<u8> {
+        vec![0xFF; (WIDTH * scale * HEIGHT * scale * 4) as usize]
+    }
+
+    // Recreate the pixel buffer for the new scale and resize the window to match
+    pub fn set_scale(&mut self, scale: u32) {
+        self.resize_buffer(scale.clamp(1, MAX_SCALING_FACTOR));
+
+        if let Some(window) = &self.window {
+            let _ = window.request_inner_size(LogicalSize::new(WIDTH * self.scale, HEIGHT * self.scale));
+        }
+    }
+
+    fn resize_buffer(&mut self, scale: u32) {
+        self.scale = scale;
+        self.pixel_buffer = Self::blank_buffer(self.scale);
+
+        if let Some(pixels) = &mut self.pixels {
+            pixels.resize_buffer(WIDTH * self.scale, HEIGHT * self.scale).unwrap();
+        }
+    }
+
+    // Largest whole scale that fits a monitor of this size, past the windowed MAX_SCALING_FACTOR if it has to
+    fn fit_monitor(&mut self, width: u32, height: u32) {
+        let (scale, _, _) = letterbox(width, height);
+        self.resize_buffer(scale);
+    }
+
+    // Switch between borderless fullscreen, using the largest whole scale that fits the monitor, and the previous window
+    fn toggle_fullscreen(&mut self) {
+        let Some(window) = self.window.clone() else { return };
+
+        match self.windowed_scale.take() {
+            Some(scale) => {
+                window.set_fullscreen(None);
+                self.set_scale(scale);
+            }
+            None => {
+                self.windowed_scale = Some(self.scale);
+                window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+
+                if let Some(monitor) = window.current_monitor() {
+                    let size = monitor.size();
+                    self.fit_monitor(size.width, size.height);
+                }
+            }
+        }
+    }
+
+    pub fn set_key_bindings(&mut self, key_bindings: KeyBindings) { self.key_bindings = key_bindings }
+
+    // Press or release the joypad button bound to a key, returns false if the key isn't bound
+    fn set_key(&mut self, key: KeyCode, state: ElementState) -> bool {
+        let Some(button) = key_button(&self.key_bindings, key) else { return false };
+
+        self.rainier.borrow_mut().set_button(button, state == ElementState::Pressed);
+        true
+    }
+
+    // Emulate a frame, returns true when the PPU finished one and the pixel buffer was updated
+    // With the display off no frame is finished, and the last one stays on screen
+    pub fn step_emulator(&mut self) -> Result<bool> {
+        let rainier = self.rainier.clone();
+        let mut rainier = rainier.borrow_mut();
+
+        rainier.run_frame()?;
+        if !rainier.take_frame_ready() {
+            return Ok(false);
+        }
+
+        self.draw_frame(&rainier.framebuffer_rgba());
+        Ok(true)
+    }
+
+    // Scale the PPU's RGBA frame up into the pixel buffer
+    fn draw_frame(&mut self, frame: &[u8]) {
+        let scale = self.scale as usize;
+        let width = WIDTH as usize * scale;
+
+        for (i, pixel) in self.pixel_buffer.chunks_exact_mut(4).enumerate() {
+            let index = (i / width / scale * WIDTH as usize + i % width / scale) * 4;
+            pixel.copy_from_slice(&frame[index..index + 4]);
+        }
+    }
+
+    pub fn set_turbo(&mut self, turbo: bool) {
+        self.frame_limiter.set_turbo(turbo);
+    }
+
+    // Play the APU output on the default device, the emulation keeps going silently if it can't be opened
+    #[cfg(feature = "audio")]
+    pub fn start_audio(&mut self) {
+        let rainier = self.rainier.borrow();
+        let Some(consumer) = rainier.mmu.borrow().apu().take_consumer() else { return };
+        let config = rainier.mmu.borrow().apu().config();
+
+        self.audio = AudioOutput::new(consumer, config).inspect_err(|error| eprintln!("{:#}", error)).ok();
+    }
+
+    // How full the audio buffer is, None without audio output, where the frame limiter alone paces the frames
+    #[cfg(feature = "audio")]
+    fn audio_pacing(&self) -> Option<AudioPacing> {
+        self.audio.as_ref()?;
+
+        let rainier = self.rainier.borrow();
+        let mmu = rainier.mmu.borrow();
+        Some(audio::audio_pacing(mmu.apu().queued_frames(), mmu.apu().config().buffer_frames))
+    }
+
+    #[cfg(not(feature = "audio"))]
+    fn audio_pacing(&self) -> Option<AudioPacing> {
+        None
+    }
 }
 
 impl ApplicationHandler for LCD {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         let window_attributes = Window::default_attributes()
             .with_title("Rainier")
-            .with_resizable(false)
-            .with_inner_size(LogicalSize::new(WIDTH * SCALING_FACTOR, HEIGHT * SCALING_FACTOR));
+            // The image is scaled by whole pixels and letterboxed to keep the 10:9 aspect ratio when resized
+            .with_resizable(true)
+            .with_inner_size(LogicalSize::new(WIDTH * self.scale, HEIGHT * self.scale));
 
         let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
 
@@ -40,7 +194,7 @@ impl ApplicationHandler for LCD {
 
         let surface_texture = SurfaceTexture::new(size.width, size.height, window.clone());
 
-        let pixels: Pixels<'static> = Pixels::new(WIDTH * SCALING_FACTOR, HEIGHT * SCALING_FACTOR, surface_texture).unwrap();
+        let pixels: Pixels<'static> = Pixels::new(WIDTH * self.scale, HEIGHT * self.scale, surface_texture).unwrap();
 
         self.window = Some(window);
         self.pixels = Some(pixels);
@@ -49,13 +203,41 @@ impl ApplicationHandler for LCD {
     }
 
     fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(key), state, repeat: false, .. }, .. } = &event
+            && self.set_key(*key, *state)
+        {
+            return;
+        }
+
+        if let WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(key), state: ElementState::Pressed, repeat: false, .. }, .. } = event {
+            match key {
+                KeyCode::F4 => return self.set_scale(self.scale % MAX_SCALING_FACTOR + 1),
+                KeyCode::F11 => return self.toggle_fullscreen(),
+                // Save the last seconds of emulation
+                KeyCode::F10 => {
+                    let path = replay_path();
+                    match self.rainier.borrow_mut().export_replay(&path) {
+                        Ok(frames) => println!("Saved {} frames to {}", frames, path.display()),
+                        Err(error) => eprintln!("{:#}", error),
+                    }
+                    return;
+                }
+                _ => {}
+            }
+        }
+
         let pixels = self.pixels.as_mut().unwrap();
 
         match event {
             WindowEvent::CloseRequested => event_loop.exit(),
             WindowEvent::RedrawRequested => {
+                self.fps_counter.tick(Instant::now());
+
                 let frame = pixels.frame_mut();
                 frame.copy_from_slice(&self.pixel_buffer);
+                if self.show_fps {
+                    fps_overlay::draw_text(frame, (WIDTH * self.scale) as usize, &self.fps_counter.text());
+                }
 
                 if pixels.render().is_err() {
                     event_loop.exit();
@@ -64,7 +246,149 @@ impl ApplicationHandler for LCD {
             WindowEvent::Resized(size) => {
                 pixels.resize_surface(size.width, size.height).unwrap();
             }
+            // Fast-forward while Tab is held
+            WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(KeyCode::Tab), state, .. }, .. } => {
+                self.set_turbo(state == ElementState::Pressed);
+            }
+            WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(KeyCode::F3), state: ElementState::Pressed, repeat: false, .. }, .. } => {
+                self.show_fps = !self.show_fps;
+            }
+            WindowEvent::KeyboardInput { event: KeyEvent { physical_key: PhysicalKey::Code(KeyCode::F12), state: ElementState::Pressed, repeat: false, .. }, .. } => {
+                let path = screenshot_path();
+                match self.rainier.borrow().screenshot(&path) {
+                    Ok(()) => println!("Saved {}", path.display()),
+                    Err(error) => eprintln!("{}", error),
+                }
+            }
             _ => {}
         }
     }
+
+    // Emulate a frame whenever one is due and redraw when it is finished, sleeping in between
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let now = Instant::now();
+        let start_frame = match self.audio_pacing() {
+            Some(AudioPacing::Hurry) => Ok(()),
+            Some(AudioPacing::Wait) if !self.frame_limiter.is_turbo() => Err(now + audio::WAIT_DURATION),
+            _ => self.frame_limiter.try_start_frame(now),
+        };
+
+        match start_frame {
+            Ok(()) => {
+                #[cfg(feature = "gamepad")]
+                if let Some(gamepad) = &mut self.gamepad {
+                    gamepad.poll(&mut self.rainier.borrow_mut());
+                }
+
+                match self.step_emulator() {
+                    Ok(true) => {
+                        if let Some(window) = &self.window {
+                            window.request_redraw();
+                        }
+                    }
+                    Ok(false) => {}
+                    Err(error) => {
+                        eprintln!("{:#}", error);
+                        return event_loop.exit();
+                    }
+                }
+                event_loop.set_control_flow(ControlFlow::Poll);
+            }
+            Err(next_frame) => event_loop.set_control_flow(ControlFlow::WaitUntil(next_frame)),
+        }
+    }
+}
+
+// winit's key code names are the ones used in the key bindings
+fn key_button(key_bindings: &KeyBindings, key: KeyCode) -> Option<Button> {
+    key_bindings.button(&format!("{:?}", key))
+}
+
+// Largest whole scale of the 160x144 screen fitting in the surface, and the offsets that center it
+// Returns (scale, x offset, y offset), the scale is at least 1 even if the surface is smaller than the screen
+pub fn letterbox(surface_width: u32, surface_height: u32) -> (u32, u32, u32) {
+    let scale = (surface_width / WIDTH).min(surface_height / HEIGHT).max(1);
+
+    let x_offset = surface_width.saturating_sub(WIDTH * scale) / 2;
+    let y_offset = surface_height.saturating_sub(HEIGHT * scale) / 2;
+
+    (scale, x_offset, y_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Model;
+
+    #[test]
+    fn scale_resizes_pixel_buffer() {
+        let mut lcd = LCD::new(Rc::new(RefCell::new(Rainier::new().unwrap())), DEFAULT_SCALING_FACTOR);
+        assert_eq!(lcd.pixel_buffer.len(), (WIDTH * 2 * HEIGHT * 2 * 4) as usize);
+
+        lcd.set_scale(3);
+        assert_eq!(lcd.pixel_buffer.len(), (WIDTH * 3 * HEIGHT * 3 * 4) as usize);
+
+        lcd.set_scale(10);
+        assert_eq!(lcd.scale, MAX_SCALING_FACTOR);
+    }
+
+    #[test]
+    fn fullscreen_scale_ignores_windowed_limit() {
+        let mut lcd = LCD::new(Rc::new(RefCell::new(Rainier::new().unwrap())), DEFAULT_SCALING_FACTOR);
+
+        // A 1080p monitor fits 7x, one more than the windowed maximum
+        lcd.fit_monitor(1920, 1080);
+        assert_eq!(lcd.scale, 7);
+        assert_eq!(lcd.pixel_buffer.len(), (WIDTH * 7 * HEIGHT * 7 * 4) as usize);
+    }
+
+    #[test]
+    fn step_emulator_runs_a_frame() {
+        let rainier = Rc::new(RefCell::new(Rainier::new().unwrap()));
+        rainier.borrow_mut().load_rom(vec![0; 0x8000], Model::Dmg).unwrap();
+        // Every colour drawn black
+        rainier.borrow_mut().poke(0xFF47, 0xFF);
+
+        let mut lcd = LCD::new(rainier.clone(), DEFAULT_SCALING_FACTOR);
+        assert!(lcd.step_emulator().unwrap());
+
+        assert_eq!(rainier.borrow().frame_count(), 1);
+        assert_ne!(rainier.borrow().pc(), 0x100);
+        assert!(lcd.pixel_buffer.chunks_exact(4).all(|pixel| pixel == [0x00, 0x00, 0x00, 0xFF]));
+    }
+
+    #[test]
+    fn key_codes_map_to_buttons() {
+        let key_bindings = KeyBindings::default();
+        assert_eq!(key_button(&key_bindings, KeyCode::KeyX), Some(Button::A));
+        assert_eq!(key_button(&key_bindings, KeyCode::ArrowUp), Some(Button::Up));
+        assert_eq!(key_button(&key_bindings, KeyCode::Enter), Some(Button::Start));
+        assert_eq!(key_button(&key_bindings, KeyCode::KeyQ), None);
+
+        let key_bindings = KeyBindings::parse("a = \"KeyK\"").unwrap();
+        assert_eq!(key_button(&key_bindings, KeyCode::KeyK), Some(Button::A));
+        assert_eq!(key_button(&key_bindings, KeyCode::KeyX), None);
+    }
+
+    #[test]
+    fn keys_press_and_release_buttons() {
+        let rainier = Rc::new(RefCell::new(Rainier::new().unwrap()));
+        let mut lcd = LCD::new(rainier.clone(), DEFAULT_SCALING_FACTOR);
+        let is_pressed = || rainier.borrow().mmu.borrow().is_button_pressed(Button::B);
+
+        assert!(lcd.set_key(KeyCode::KeyZ, ElementState::Pressed));
+        assert!(is_pressed());
+        assert!(lcd.set_key(KeyCode::KeyZ, ElementState::Released));
+        assert!(!is_pressed());
+        assert!(!lcd.set_key(KeyCode::F4, ElementState::Pressed));
+    }
+
+    #[test]
+    fn letterbox_offsets() {
+        // 1080p fits 7x vertically: 1120x1008
+        assert_eq!(letterbox(1920, 1080), (7, 400, 36));
+        assert_eq!(letterbox(160, 144), (1, 0, 0));
+        assert_eq!(letterbox(1280, 800), (5, 240, 40));
+        assert_eq!(letterbox(100, 100), (1, 0, 0));
+    }
 }
@@ -1,6 +1,15 @@
 pub mod lcd;
+pub mod frame_limiter;
+pub mod breakpoint;
+pub mod key_bindings;
+pub mod gamepad;
+pub mod audio;
+mod fps_overlay;
+mod vram_viewer;
 
 use std::{cell::RefCell, io, rc::Rc};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, MouseEvent, MouseEventKind};
 use ratatui::{
     layout::{Layout, Constraint, Direction, Rect},
@@ -12,14 +21,119 @@ use ratatui::prelude::{Color, Style};
 use ratatui::style::Modifier;
 use ratatui::text::Span;
 use crate::cpu::instruction_set::DebugInstruction;
+use crate::cpu::interrupts::Interrupt;
+use crate::cpu::registers::Registers;
 use crate::mmu::MemoryRegion;
+use crate::ppu::OAM_ENTRY_COUNT;
 use crate::Rainier;
+use crate::ui::breakpoint::{Breakpoint, Condition};
+use crate::ui::key_bindings::KeyBindings;
 
 #[derive(Eq, PartialEq)]
 pub enum Action {
     Trace,
     StepOver,
     Run,
+    // Run to the end of the current frame
+    NextFrame,
+    // Run as many frames as the configured step count
+    RunFrames,
+}
+
+// What the main panel of the debugger shows
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum View {
+    Disassembly,
+    Tiles,
+    TileMap(usize),
+    Oam,
+}
+
+impl View {
+    fn next(self) -> Self {
+        match self {
+            View::Disassembly => View::Tiles,
+            View::Tiles => View::TileMap(0x9800),
+            View::TileMap(0x9800) => View::TileMap(0x9C00),
+            View::TileMap(_) => View::Oam,
+            View::Oam => View::Disassembly,
+        }
+    }
+}
+
+// Side panels shown next to the main view
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Panel {
+    Registers,
+    Memory,
+    Stack,
+}
+
+// Panel sets cycled through with Tab, the first one is the default
+const PANEL_CONFIGURATIONS: [&[Panel]; 4] = [
+    &[Panel::Registers],
+    &[Panel::Registers, Panel::Memory],
+    &[Panel::Registers, Panel::Stack],
+    &[Panel::Registers, Panel::Memory, Panel::Stack],
+];
+
+// Below this width the panels are stacked under the main view instead of next to it
+const NARROW_TERMINAL_WIDTH: u16 = 100;
+const MEMORY_PANEL_ROWS: usize = 16;
+const STACK_PANEL_ENTRIES: usize = 16;
+
+// Split the debugger area into a status bar followed by the main view and the given number of side panels
+fn layout(area: Rect, panel_count: usize) -> Vec<Rect> {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(area);
+
+    let direction = if area.width < NARROW_TERMINAL_WIDTH { Direction::Vertical } else { Direction::Horizontal };
+    let constraints = std::iter::once(Constraint::Fill(7)).chain(std::iter::repeat_n(Constraint::Fill(3), panel_count));
+    let panels = Layout::default()
+        .direction(direction)
+        .constraints(constraints)
+        .split(rows[1]);
+
+    std::iter::once(rows[0]).chain(panels.iter().copied()).collect()
+}
+
+// Single line inputs shown in the status bar
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Prompt {
+    // Hex address to show in the disassembly
+    Goto,
+    // Condition of the breakpoint under the cursor
+    Condition,
+    // Decimal number of hits the breakpoint under the cursor lets through
+    IgnoreCount,
+}
+
+impl Prompt {
+    fn title(self) -> &'static str {
+        match self {
+            Prompt::Goto => "Go to:",
+            Prompt::Condition => "Condition:",
+            Prompt::IgnoreCount => "Ignore count:",
+        }
+    }
+
+    fn accepts(self, character: char) -> bool {
+        match self {
+            Prompt::Goto => character.is_ascii_hexdigit(),
+            Prompt::Condition => character.is_ascii_graphic() || character == ' ',
+            Prompt::IgnoreCount => character.is_ascii_digit(),
+        }
+    }
+
+    fn max_length(self) -> usize {
+        match self {
+            Prompt::Goto => 4,
+            Prompt::Condition => 32,
+            Prompt::IgnoreCount => 9,
+        }
+    }
 }
 
 pub struct App {
@@ -28,15 +142,41 @@ pub struct App {
     pub exit: bool,
     current_instruction_set: Vec<DebugInstruction>,
     current_instruction_id: usize,
-    pub breakpoints: Vec<u16>,
+    pub breakpoints: Vec<Breakpoint>,
+    // Break before any instruction with one of these opcodes (0xCB for prefixed ones) runs
+    pub opcode_breakpoints: Vec<u8>,
+    // Break right after one of these interrupts is dispatched
+    pub interrupt_breakpoints: Vec<Interrupt>,
     pub last_hit_breakpoint: Option<u16>,
-    scroll: i16,
+    // Lines scrolled up from the current instruction, negative when scrolled down
+    scroll: i32,
     backward_instructions_count: usize,
+    turbo: bool,
+    // Result of the last action that doesn't show up anywhere else, e.g. a screenshot
+    message: Option<String>,
+    view: View,
+    // Text being typed into a prompt, e.g. the address after pressing g
+    prompt: Option<(Prompt, String)>,
+    // Index into PANEL_CONFIGURATIONS
+    panel_configuration: usize,
+    // Region written by the memory dump command, None for the whole address space
+    dump_region: Option<MemoryRegion>,
+    key_bindings: KeyBindings,
+    // Instructions left in the Run in progress, None when no Run is going
+    run_remaining: Option<u32>,
 }
 
+// Instructions a Run goes through without hitting a breakpoint before handing control back, a few seconds of emulation
+const RUN_INSTRUCTIONS: u32 = 1_000_000;
+// Run budget multiplier while turbo is on
+const TURBO_MULTIPLIER: u32 = 100;
+// Instructions a Run goes through between redraws, so the debugger stays responsive during a long one
+const RUN_SLICE: u32 = 100_000;
+const DISASSEMBLY_LINES: usize = 50;
+
 impl App {
     pub fn new(rainier: Rc<RefCell<Rainier>>) -> Self {
-        let breakpoints: Vec<u16> = vec![0xc669];
+        let breakpoints = vec![Breakpoint::new(0xc669)];
 
         Self {
             rainier,
@@ -45,12 +185,262 @@ impl App {
             current_instruction_set: Vec::new(),
             current_instruction_id: 0,
             breakpoints,
+            opcode_breakpoints: Vec::new(),
+            interrupt_breakpoints: Vec::new(),
             last_hit_breakpoint: None,
             scroll: 0,
-            backward_instructions_count: 5
+            backward_instructions_count: 5,
+            turbo: false,
+            message: None,
+            view: View::Disassembly,
+            prompt: None,
+            panel_configuration: 0,
+            dump_region: None,
+            key_bindings: KeyBindings::default(),
+            run_remaining: None,
+        }
+    }
+
+    pub fn set_turbo(&mut self, turbo: bool) { self.turbo = turbo }
+
+    pub fn set_key_bindings(&mut self, key_bindings: KeyBindings) { self.key_bindings = key_bindings }
+
+    // Terminals don't report key releases, so a bound key toggles its button between held and released
+    fn toggle_button(&mut self, code: KeyCode) -> bool {
+        let Some(button) = key_name(code).and_then(|name| self.key_bindings.button(&name)) else { return false };
+
+        let mut rainier = self.rainier.borrow_mut();
+        let pressed = !rainier.mmu.borrow().is_button_pressed(button);
+        rainier.set_button(button, pressed);
+        self.message = Some(format!("{:?} {}", button, if pressed { "held" } else { "released" }));
+
+        true
+    }
+
+    // Most instructions a single Run goes through before stopping
+    pub fn run_budget(&self) -> u32 {
+        if self.turbo { RUN_INSTRUCTIONS.saturating_mul(TURBO_MULTIPLIER) } else { RUN_INSTRUCTIONS }
+    }
+
+    pub fn start_run(&mut self) {
+        self.run_remaining = Some(self.run_budget());
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.run_remaining.is_some()
+    }
+
+    // Go through the next slice of the Run in progress, stopping on a breakpoint, a CPU fault or the end of the budget
+    pub fn continue_run(&mut self) {
+        let Some(remaining) = self.run_remaining else { return };
+        let slice = remaining.min(RUN_SLICE);
+        self.run_remaining = Some(remaining - slice);
+
+        let rainier = self.rainier.clone();
+        let mut rainier = rainier.borrow_mut();
+
+        for _ in 0..slice {
+            if self.breaks_at(&rainier.cpu.borrow().registers, rainier.peek(rainier.pc())) {
+                self.run_remaining = None;
+                self.last_hit_breakpoint = Some(rainier.pc());
+                return;
+            }
+
+            let result = rainier.step();
+            self.last_hit_breakpoint = None;
+            if let Err(error) = result {
+                self.run_remaining = None;
+                self.report_fault(&error);
+                return;
+            }
+            if rainier.cpu.borrow().dispatched_interrupt().is_some_and(|interrupt| self.breaks_on_interrupt(interrupt)) {
+                self.run_remaining = None;
+                self.last_hit_breakpoint = Some(rainier.pc());
+                return;
+            }
+        }
+
+        if self.run_remaining == Some(0) {
+            self.run_remaining = None;
+            // A breakpoint right after the last budgeted instruction still counts as hit
+            if self.breaks_at(&rainier.cpu.borrow().registers, rainier.peek(rainier.pc())) {
+                self.last_hit_breakpoint = Some(rainier.pc());
+            }
+            else {
+                self.report_run_paused();
+            }
         }
     }
 
+    // Whether Run should stop before executing the given opcode at pc, a breakpoint just hit doesn't stop it twice
+    // Every call counts as a hit for the breakpoints at pc
+    pub fn breaks_at(&mut self, registers: &Registers, opcode: u8) -> bool {
+        let pc = registers.pc();
+        if self.last_hit_breakpoint == Some(pc) {
+            return false;
+        }
+
+        let breakpoint_hit = self.breakpoints.iter_mut()
+            .find(|breakpoint| breakpoint.address == pc)
+            .is_some_and(|breakpoint| breakpoint.hit(registers));
+
+        breakpoint_hit || self.opcode_breakpoints.contains(&opcode)
+    }
+
+    pub fn breaks_on_interrupt(&self, interrupt: Interrupt) -> bool {
+        self.interrupt_breakpoints.contains(&interrupt)
+    }
+
+    // Instruction on the line of the current one, following the scrolling
+    fn cursor_address(&self) -> Option<u16> {
+        let index = self.current_instruction_id as i64 - self.scroll as i64;
+        let index = index.clamp(0, self.current_instruction_set.len() as i64 - 1);
+
+        self.current_instruction_set.get(index as usize).map(|instruction| instruction.address as u16)
+    }
+
+    // Add a breakpoint under the cursor, or remove the one already there
+    fn toggle_breakpoint(&mut self) {
+        let Some(address) = self.cursor_address() else { return };
+
+        match self.breakpoints.iter().position(|breakpoint| breakpoint.address == address) {
+            Some(index) => { self.breakpoints.remove(index); }
+            None => self.breakpoints.push(Breakpoint::new(address)),
+        }
+    }
+
+    // Enable or disable the breakpoint under the cursor without deleting it
+    fn toggle_breakpoint_enabled(&mut self) {
+        let Some(address) = self.cursor_address() else { return };
+
+        if let Some(breakpoint) = self.breakpoints.iter_mut().find(|breakpoint| breakpoint.address == address) {
+            breakpoint.enabled = !breakpoint.enabled;
+        }
+    }
+
+    // Index of the first instruction shown in the disassembly, always leaving a full panel of instructions
+    fn disassembly_start(&self) -> usize {
+        let max_start = self.current_instruction_set.len().saturating_sub(DISASSEMBLY_LINES) as i64;
+        let start = self.current_instruction_id as i64 - self.backward_instructions_count as i64 - self.scroll as i64;
+
+        start.clamp(0, max_start) as usize
+    }
+
+    // Scroll the disassembly, stopping at either end of the instructions
+    fn scroll_by(&mut self, lines: i32) {
+        let max_start = self.current_instruction_set.len().saturating_sub(DISASSEMBLY_LINES) as i64;
+        let top = self.current_instruction_id as i64 - self.backward_instructions_count as i64;
+        let scroll = self.scroll as i64 + lines as i64;
+
+        self.scroll = scroll.clamp(top - max_start, top) as i32;
+    }
+
+    // Recenter the disassembly on the instruction at (or nearest to) the given address, without running there
+    fn goto_address(&mut self, address: usize) {
+        let index = instruction_index(&self.current_instruction_set, address);
+        self.scroll = 0;
+        self.scroll_by(self.current_instruction_id as i32 - index as i32);
+    }
+
+    fn handle_prompt_key(&mut self, prompt: Prompt, code: KeyCode, mut input: String) {
+        match code {
+            KeyCode::Char(character) if prompt.accepts(character) && input.len() < prompt.max_length() => input.push(character),
+            KeyCode::Backspace => { input.pop(); }
+            KeyCode::Enter => {
+                self.message = None;
+                self.submit_prompt(prompt, &input);
+                return;
+            }
+            KeyCode::Esc => {
+                self.message = None;
+                return;
+            }
+            _ => {}
+        }
+
+        self.message = Some(format!("{} {}", prompt.title(), input));
+        self.prompt = Some((prompt, input));
+    }
+
+    fn submit_prompt(&mut self, prompt: Prompt, input: &str) {
+        match prompt {
+            Prompt::Goto => {
+                if let Ok(address) = usize::from_str_radix(input, 16) {
+                    self.goto_address(address);
+                }
+            }
+            Prompt::Condition | Prompt::IgnoreCount => {
+                let Some(address) = self.cursor_address() else { return };
+                let Some(breakpoint) = self.breakpoints.iter_mut().find(|breakpoint| breakpoint.address == address) else {
+                    self.message = Some(format!("No breakpoint at {:04X}", address));
+                    return;
+                };
+
+                if prompt == Prompt::IgnoreCount {
+                    breakpoint.ignore_count = input.parse().unwrap_or(0);
+                    breakpoint.hits = 0;
+                }
+                // An empty condition removes it
+                else if input.trim().is_empty() {
+                    breakpoint.condition = None;
+                }
+                else {
+                    match Condition::parse(input) {
+                        Ok(condition) => breakpoint.condition = Some(condition),
+                        Err(error) => self.message = Some(error.to_string()),
+                    }
+                }
+            }
+        }
+    }
+
+    fn take_screenshot(&mut self) {
+        let path = screenshot_path();
+        self.message = Some(match self.rainier.borrow().screenshot(&path) {
+            Ok(()) => format!("Saved {}", path.display()),
+            Err(error) => error.to_string(),
+        });
+    }
+
+    // Mute or unmute an APU channel (1-4), to listen to the others on their own
+    fn toggle_channel(&mut self, channel: u8) {
+        let rainier = self.rainier.borrow();
+        let mut mmu = rainier.mmu.borrow_mut();
+        let enabled = !mmu.apu().is_channel_enabled(channel);
+        mmu.apu_mut().set_channel_enabled(channel, enabled);
+
+        self.message = Some(format!("Channel {} {}", channel, if enabled { "unmuted" } else { "muted" }));
+    }
+
+    // Show a CPU fault in the title bar, the emulation stays halted until the next command
+    pub fn report_fault(&mut self, error: &anyhow::Error) {
+        self.message = Some(format!("{:#}", error));
+    }
+
+    // A Run went through its whole budget without a breakpoint
+    pub fn report_run_paused(&mut self) {
+        self.message = Some(format!("Run paused after {} instructions", self.run_budget()));
+    }
+
+    fn dump_memory(&mut self) {
+        let name = self.dump_region.map_or(String::from("All"), |region| format!("{:?}", region));
+        let path = timestamped_path(&format!("dump-{}", name), "bin");
+
+        self.message = Some(match self.rainier.borrow().mmu.borrow().dump_memory_region_to_file(self.dump_region, &path) {
+            Ok(()) => format!("Saved {}", path.display()),
+            Err(error) => error.to_string(),
+        });
+    }
+
+    // Cycle through the regions (and the whole address space) for the memory dump command
+    fn select_next_dump_region(&mut self) {
+        let index = self.dump_region.map_or(0, |region| MemoryRegion::VALUES.iter().position(|value| *value == region).unwrap() + 1);
+        self.dump_region = MemoryRegion::VALUES.get(index).copied();
+
+        let name = self.dump_region.map_or(String::from("All"), |region| format!("{:?}", region));
+        self.message = Some(format!("Dump region: {}", name));
+    }
+
     pub fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
         {
             let rainier = self.rainier.borrow();
@@ -81,6 +471,36 @@ impl App {
             Span::styled( "<F2>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
             Span::styled("  Run", Style::default()),
             Span::styled( "<F3>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Next frame", Style::default()),
+            Span::styled( "<F8>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Run frames", Style::default()),
+            Span::styled( "<F9>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Scroll", Style::default()),
+            Span::styled( "<PgUp/PgDn/Home>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Breakpoint", Style::default()),
+            Span::styled( "<B>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Enable", Style::default()),
+            Span::styled( "<E>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Condition", Style::default()),
+            Span::styled( "<C>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Ignore count", Style::default()),
+            Span::styled( "<I>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Go to", Style::default()),
+            Span::styled( "<G>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  View", Style::default()),
+            Span::styled( "<F5>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Panels", Style::default()),
+            Span::styled( "<Tab>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Turbo", Style::default()),
+            Span::styled( "<T>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Mute channel", Style::default()),
+            Span::styled( "<1-4>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Dump", Style::default()),
+            Span::styled( "<F6>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Dump region", Style::default()),
+            Span::styled( "<F7>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
+            Span::styled("  Screenshot", Style::default()),
+            Span::styled( "<F12>", Style::default().fg(Color::Green).add_modifier(Modifier::BOLD)),
         ]);
         let outer_block = Block::default()
             .title(title.centered())
@@ -89,19 +509,72 @@ impl App {
 
         frame.render_widget(outer_block.clone(), outer_area);
 
-        let inner_area = outer_block.inner(outer_area);
+        let panels = PANEL_CONFIGURATIONS[self.panel_configuration];
+        let areas = layout(outer_block.inner(outer_area), panels.len());
+
+        self.draw_status_bar(frame, areas[0]);
+
+        // Main view: disassembly or one of the VRAM views
+        match self.view {
+            View::Disassembly => self.draw_disassembly(frame, areas[1]),
+            View::Tiles | View::TileMap(_) => self.draw_vram(frame, areas[1]),
+            View::Oam => self.draw_oam(frame, areas[1]),
+        }
+
+        for (panel, area) in panels.iter().zip(&areas[2..]) {
+            match panel {
+                Panel::Registers => self.draw_registers(frame, *area),
+                Panel::Memory => self.draw_memory(frame, *area),
+                Panel::Stack => self.draw_stack(frame, *area),
+            }
+        }
+    }
+
+    fn draw_status_bar(&self, frame: &mut Frame, area: Rect) {
+        let rainier = self.rainier.borrow();
+        let cpu = rainier.cpu.borrow();
+
+        let mode = if self.turbo { "Turbo" } else { "Normal" };
+        let last_breakpoint = self.last_hit_breakpoint.map_or(String::from("-"), |breakpoint| format!("{:04X}", breakpoint));
+        let status = Line::from(format!(" Mode: {}  Frame: {}  Instructions: {}  Last breakpoint: {}",
+            mode, rainier.frame_count(), cpu.instruction_count(), last_breakpoint));
+        let message = Line::from(self.message.clone().unwrap_or_default()).right_aligned();
+
+        frame.render_widget(Paragraph::new(status).style(Style::default().add_modifier(Modifier::REVERSED)), area);
+        frame.render_widget(Paragraph::new(message).style(Style::default().add_modifier(Modifier::REVERSED)), area);
+    }
 
-        // Use layout instead of manual positioning
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
-            .split(inner_area);
+    // Hex dump of the memory around the address in HL
+    fn draw_memory(&self, frame: &mut Frame, area: Rect) {
+        let rainier = self.rainier.borrow();
+        let start = rainier.cpu.borrow().registers.hl() & 0xFFF0;
 
-        // Left panel: maybe disassembly/logs later
-        self.draw_disassembly(frame, chunks[0]);
+        let lines: Vec<Line> = (0..MEMORY_PANEL_ROWS)
+            .map(|row| start.wrapping_add(row as u16 * 8))
+            .map(|address| {
+                let bytes: Vec<String> = (0..8).map(|offset| format!("{:02X}", rainier.peek(address.wrapping_add(offset)))).collect();
+                Line::from(format!("{:04X}: {}", address, bytes.join(" ")))
+            })
+            .collect();
 
-        // Right panel: registers
-        self.draw_registers(frame, chunks[1]);
+        let block = Block::default().title("Memory (HL)").borders(Borders::ALL);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
+    fn draw_stack(&self, frame: &mut Frame, area: Rect) {
+        let rainier = self.rainier.borrow();
+        let sp = rainier.cpu.borrow().registers.sp();
+
+        let lines: Vec<Line> = (0..STACK_PANEL_ENTRIES)
+            .map(|entry| sp.wrapping_add(entry as u16 * 2))
+            .map(|address| {
+                let value = u16::from_le_bytes([rainier.peek(address), rainier.peek(address.wrapping_add(1))]);
+                Line::from(format!("{:04X}: {:04X}", address, value))
+            })
+            .collect();
+
+        let block = Block::default().title("Stack").borders(Borders::ALL);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
     }
 
     fn draw_registers(&self, frame: &mut Frame, area: Rect) {
@@ -132,29 +605,36 @@ impl App {
     fn draw_disassembly(&self, frame: &mut Frame, area: Rect) {
         let rainier = self.rainier.borrow();
 
-        let starting_point = self.current_instruction_id - self.backward_instructions_count - self.scroll as usize;
-        let lines = &self.current_instruction_set[starting_point..starting_point+50];
+        let starting_point = self.disassembly_start();
+        let ending_point = (starting_point + DISASSEMBLY_LINES).min(self.current_instruction_set.len());
+        let lines = &self.current_instruction_set[starting_point..ending_point];
 
         let lines = lines
             .iter()
             .enumerate()
             .map(|(i, instruction)| {
-                let breakpoint = if self.breakpoints.contains(&(instruction.address as u16)) { "🟠" } else { "  " };
-                let prefix = if i == self.backward_instructions_count + self.scroll as usize { "▶" } else { " " };
-                let memory_region = MemoryRegion::from_address(instruction.address).unwrap().as_str();
+                let breakpoint = match self.breakpoints.iter().find(|breakpoint| breakpoint.address == instruction.address as u16) {
+                    Some(breakpoint) if breakpoint.enabled => Span::raw("🟠"),
+                    Some(_) => Span::styled("○ ", Style::default().fg(Color::DarkGray)),
+                    None => Span::raw("  "),
+                };
+                let prefix = if starting_point + i == self.current_instruction_id { "▶" } else { " " };
+                let memory_region = rainier.mmu.borrow().region_label(MemoryRegion::from_address(instruction.address).unwrap());
                 let first_operand = instruction.first_operand.map_or(String::from("  "), |operand| format!("{:02X}", operand));
                 let second_operand = instruction.second_operand.map_or(String::from("  "), |operand| format!("{:02X}", operand));
 
-                Line::from(format!("{} {} {}:{:04X} {:02X} {} {}        {}",
-                    breakpoint,
+                let label = instruction.label.as_ref().map_or(String::new(), |label| format!("{}:", label));
+
+                Line::from(vec![breakpoint, Span::raw(format!(" {} {}:{:04X} {:02X} {} {}  {:<16}  {}",
                     prefix,
                     memory_region,
                     instruction.address,
                     instruction.opcode,
                     first_operand,
                     second_operand,
-                    instruction.name)
-                )})
+                    label,
+                    instruction.name))
+                ])})
             .collect::<Vec<Line>>();
 
         let block = Block::default().title("Disassembly").borders(Borders::ALL);
@@ -163,7 +643,57 @@ impl App {
         frame.render_widget(ops, area);
     }
 
+    fn draw_vram(&self, frame: &mut Frame, area: Rect) {
+        let rainier = self.rainier.borrow();
+        let mmu = rainier.mmu.borrow();
+
+        let (title, (width, pixels)) = match self.view {
+            View::TileMap(base) => (format!("Tile map {:04X}", base), vram_viewer::tile_map_pixels(&mmu, base)),
+            _ => (String::from("Tiles"), vram_viewer::tile_pixels(&mmu)),
+        };
+
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let vram = Paragraph::new(vram_viewer::half_block_lines(width, &pixels)).block(block);
+
+        frame.render_widget(vram, area);
+    }
+
+    fn draw_oam(&self, frame: &mut Frame, area: Rect) {
+        let rainier = self.rainier.borrow();
+        let ppu = &rainier.ppu;
+
+        let mut lines = vec![Line::from("ID   Y    X  Tile  Prio Y-Flip X-Flip OBP Bank CGB")];
+        for id in 0..OAM_ENTRY_COUNT {
+            let entry = ppu.fetch_oam_entry(id).unwrap();
+            let attributes = entry.decode_attributes();
+            let flag = |value: bool| if value { "✓" } else { "X" };
+
+            let line = Line::from(format!("{:02}  {:3}  {:3}  {:02X}    {}    {}      {}      {}   {}    {}",
+                id, entry.y_position, entry.x_position, entry.tile_index,
+                flag(attributes.background_priority), flag(attributes.y_flip), flag(attributes.x_flip),
+                attributes.dmg_palette, attributes.cgb_bank, attributes.cgb_palette));
+
+            // Highlight the sprites picked for the current scanline
+            if ppu.is_sprite_buffered(id) {
+                lines.push(line.style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)));
+            }
+            else {
+                lines.push(line);
+            }
+        }
+
+        let block = Block::default().title("OAM").borders(Borders::ALL);
+        let oam = Paragraph::new(lines).block(block);
+
+        frame.render_widget(oam, area);
+    }
+
     fn handle_events(&mut self) -> io::Result<()> {
+        // A Run in progress only takes the keys already waiting, it goes on with its next slice otherwise
+        if self.is_running() && !event::poll(Duration::ZERO)? {
+            return Ok(());
+        }
+
         match event::read()? {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_key_event(key_event)
@@ -177,8 +707,25 @@ impl App {
     }
 
     fn handle_key_event(&mut self, key_event: KeyEvent) {
+        // While a prompt is open every key goes to it
+        if let Some((prompt, input)) = self.prompt.take() {
+            self.handle_prompt_key(prompt, key_event.code, input);
+            return;
+        }
+
+        // Joypad bindings come before the debugger keys they shadow, e.g. the default arrow keys over Up/Down scrolling
+        // Binding the d-pad elsewhere in keybindings.toml gives the arrows back to the disassembly
+        if self.toggle_button(key_event.code) {
+            return;
+        }
+
         match key_event.code {
+            KeyCode::Char('g') => self.handle_prompt_key(Prompt::Goto, KeyCode::Null, String::new()),
+            KeyCode::Char('c') => self.handle_prompt_key(Prompt::Condition, KeyCode::Null, String::new()),
+            KeyCode::Char('i') => self.handle_prompt_key(Prompt::IgnoreCount, KeyCode::Null, String::new()),
             KeyCode::Char('q') => self.exit = true,
+            KeyCode::Char('b') => self.toggle_breakpoint(),
+            KeyCode::Char('e') => self.toggle_breakpoint_enabled(),
             KeyCode::F(1) => {
                 self.requested_action = Some(Action::Trace);
                 self.scroll = 0;
@@ -187,23 +734,281 @@ impl App {
                 self.requested_action = Some(Action::Run);
                 self.scroll = 0;
             }
+            KeyCode::F(8) => {
+                self.requested_action = Some(Action::NextFrame);
+                self.scroll = 0;
+            }
+            KeyCode::F(9) => {
+                self.requested_action = Some(Action::RunFrames);
+                self.scroll = 0;
+            }
+            KeyCode::F(5) => self.view = self.view.next(),
+            KeyCode::F(6) => self.dump_memory(),
+            KeyCode::F(7) => self.select_next_dump_region(),
+            KeyCode::F(12) => self.take_screenshot(),
+            KeyCode::PageUp => self.scroll_by(DISASSEMBLY_LINES as i32),
+            KeyCode::PageDown => self.scroll_by(-(DISASSEMBLY_LINES as i32)),
+            KeyCode::Up => self.scroll_by(1),
+            KeyCode::Down => self.scroll_by(-1),
+            // Recenter on the current instruction
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::Tab => self.panel_configuration = (self.panel_configuration + 1) % PANEL_CONFIGURATIONS.len(),
+            // Terminals don't reliably report key releases, so turbo is toggled rather than held here
+            KeyCode::Char('t') => self.set_turbo(!self.turbo),
+            KeyCode::Char(channel @ '1'..='4') => self.toggle_channel(channel as u8 - b'0'),
             _ => {}
         }
     }
 
     fn handle_mouse_event(&mut self, mouse_event: MouseEvent) {
         match mouse_event.kind {
-            // TODO: Bounds check
-            MouseEventKind::ScrollDown => {
-                self.scroll -= 1;
-            },
-            MouseEventKind::ScrollUp => {
-                //if (self.scroll as usize) < self.current_instruction_id - self.backward_instructions_count {
-                 //   self.scroll += 1;
-                //}
-                self.scroll += 1;
-            }
+            MouseEventKind::ScrollDown => self.scroll_by(-1),
+            MouseEventKind::ScrollUp => self.scroll_by(1),
             _ => {}
         }
     }
-}
\ No newline at end of file
+}
+
+// Index of the instruction starting at the given address, or of the closest one when it falls inside an instruction
+fn instruction_index(instructions: &[DebugInstruction], address: usize) -> usize {
+    let after = instructions.partition_point(|instruction| instruction.address < address);
+    if after == 0 {
+        return 0;
+    }
+    if after == instructions.len() {
+        return after - 1;
+    }
+
+    let before = after - 1;
+    if address - instructions[before].address <= instructions[after].address - address { before } else { after }
+}
+
+// Name of a terminal key in the winit physical key naming used by the key bindings
+fn key_name(code: KeyCode) -> Option<String> {
+    Some(match code {
+        KeyCode::Char(' ') => String::from("Space"),
+        KeyCode::Char(character) if character.is_ascii_alphabetic() => format!("Key{}", character.to_ascii_uppercase()),
+        KeyCode::Char(character) if character.is_ascii_digit() => format!("Digit{}", character),
+        KeyCode::Up => String::from("ArrowUp"),
+        KeyCode::Down => String::from("ArrowDown"),
+        KeyCode::Left => String::from("ArrowLeft"),
+        KeyCode::Right => String::from("ArrowRight"),
+        KeyCode::Enter => String::from("Enter"),
+        KeyCode::Backspace => String::from("Backspace"),
+        KeyCode::Esc => String::from("Escape"),
+        _ => return None,
+    })
+}
+
+// Screenshots and dumps are saved in the working directory, named after the time they were taken
+fn timestamped_path(name: &str, extension: &str) -> PathBuf {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+
+    PathBuf::from(format!("{}-{}.{}", name, timestamp, extension))
+}
+
+pub fn screenshot_path() -> PathBuf {
+    timestamped_path("screenshot", "png")
+}
+
+pub fn replay_path() -> PathBuf {
+    timestamped_path("replay", "gif")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mmu::joypad::Button;
+    use crate::Model;
+
+    fn app_with_instructions(count: usize, current_instruction_id: usize) -> App {
+        let mut app = App::new(Rc::new(RefCell::new(Rainier::new().unwrap())));
+        app.current_instruction_set = (0..count)
+            .map(|address| DebugInstruction { address, opcode: 0, first_operand: None, second_operand: None, name: String::from("NOP"), label: None })
+            .collect();
+        app.current_instruction_id = current_instruction_id;
+
+        app
+    }
+
+    fn registers_at(pc: u16) -> Registers {
+        let mut registers = Registers::new();
+        registers.set_pc(pc);
+
+        registers
+    }
+
+    #[test]
+    fn scrolling_stays_within_instructions() {
+        let count = 200;
+        for current_instruction_id in [0, 3, 100, 199] {
+            let mut app = app_with_instructions(count, current_instruction_id);
+            let max_start = count - DISASSEMBLY_LINES;
+
+            let events = [(MouseEventKind::ScrollUp, 300), (MouseEventKind::ScrollDown, 600), (MouseEventKind::ScrollUp, 20)];
+            for (kind, repeat) in events {
+                for _ in 0..repeat {
+                    app.handle_mouse_event(MouseEvent { kind, column: 0, row: 0, modifiers: crossterm::event::KeyModifiers::NONE });
+                    assert!(app.disassembly_start() <= max_start);
+                }
+            }
+
+            // After a long scroll down, 20 lines up moves the view 20 lines back from the end
+            assert_eq!(app.disassembly_start(), max_start - 20);
+        }
+    }
+
+    #[test]
+    fn bound_keys_go_to_the_joypad_first() {
+        let mut app = app_with_instructions(200, 100);
+
+        app.handle_key_event(KeyEvent::new(KeyCode::Up, crossterm::event::KeyModifiers::NONE));
+        assert!(app.rainier.borrow().mmu.borrow().is_button_pressed(Button::Up));
+        assert_eq!(app.scroll, 0);
+
+        // Unbound debugger keys keep working
+        app.handle_key_event(KeyEvent::new(KeyCode::PageUp, crossterm::event::KeyModifiers::NONE));
+        assert_eq!(app.scroll, DISASSEMBLY_LINES as i32);
+    }
+
+    #[test]
+    fn turbo_multiplies_run_budget() {
+        let mut app = app_with_instructions(1, 0);
+        assert_eq!(app.run_budget(), RUN_INSTRUCTIONS);
+
+        app.set_turbo(true);
+        assert_eq!(app.run_budget(), RUN_INSTRUCTIONS * TURBO_MULTIPLIER);
+    }
+
+    fn looping_app() -> App {
+        // JR -2 at 0x100, spinning in place
+        let mut rom = vec![0; 0x8000];
+        rom[0x100] = 0x18;
+        rom[0x101] = 0xFE;
+
+        let app = App::new(Rc::new(RefCell::new(Rainier::new().unwrap())));
+        app.rainier.borrow_mut().load_rom(rom, Model::Dmg).unwrap();
+
+        app
+    }
+
+    #[test]
+    fn run_goes_in_slices() {
+        let mut app = looping_app();
+        app.breakpoints.clear();
+
+        app.start_run();
+        app.continue_run();
+        assert_eq!(app.run_remaining, Some(RUN_INSTRUCTIONS - RUN_SLICE));
+
+        while app.is_running() {
+            app.continue_run();
+        }
+        assert_eq!(app.message, Some(format!("Run paused after {} instructions", RUN_INSTRUCTIONS)));
+        assert_eq!(app.last_hit_breakpoint, None);
+    }
+
+    #[test]
+    fn breakpoint_after_last_budgeted_instruction_is_hit() {
+        let mut app = App::new(Rc::new(RefCell::new(Rainier::new().unwrap())));
+        app.rainier.borrow_mut().load_rom(vec![0; 0x8000], Model::Dmg).unwrap();
+        app.breakpoints = vec![Breakpoint::new(0x110)];
+
+        // Just enough NOPs to reach the breakpoint
+        app.run_remaining = Some(0x10);
+        app.continue_run();
+
+        assert!(!app.is_running());
+        assert_eq!(app.last_hit_breakpoint, Some(0x110));
+        assert_eq!(app.message, None);
+    }
+
+    #[test]
+    fn layout_has_status_bar_and_panels() {
+        for (width, panels) in [(160, 1), (160, 3), (60, 2)] {
+            let areas = layout(Rect::new(0, 0, width, 50), panels);
+
+            assert_eq!(areas.len(), panels + 2);
+            assert_eq!(areas[0].height, 1);
+            assert!(areas.iter().all(|area| !area.is_empty()));
+        }
+    }
+
+    #[test]
+    fn address_to_instruction_index() {
+        let instructions: Vec<DebugInstruction> = [0x100, 0x101, 0x104, 0x150, 0x153]
+            .map(|address| DebugInstruction { address, opcode: 0, first_operand: None, second_operand: None, name: String::from("NOP"), label: None })
+            .into();
+
+        assert_eq!(instruction_index(&instructions, 0x104), 2);
+        assert_eq!(instruction_index(&instructions, 0x102), 1);
+        assert_eq!(instruction_index(&instructions, 0x103), 2);
+        assert_eq!(instruction_index(&instructions, 0x151), 3);
+        assert_eq!(instruction_index(&instructions, 0x0000), 0);
+        assert_eq!(instruction_index(&instructions, 0xFFFF), 4);
+    }
+
+    #[test]
+    fn keyboard_scrolling() {
+        let mut app = app_with_instructions(200, 100);
+        // The default bindings take the arrow keys for the joypad
+        app.set_key_bindings(KeyBindings::parse("up = \"KeyW\"\ndown = \"KeyS\"").unwrap());
+        let press = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+
+        app.handle_key_event(press(KeyCode::PageDown));
+        assert_eq!(app.scroll, -(DISASSEMBLY_LINES as i32));
+
+        app.handle_key_event(press(KeyCode::Up));
+        assert_eq!(app.scroll, 1 - DISASSEMBLY_LINES as i32);
+
+        app.handle_key_event(press(KeyCode::Home));
+        assert_eq!(app.scroll, 0);
+    }
+
+    #[test]
+    fn number_keys_mute_channels() {
+        let mut app = App::new(Rc::new(RefCell::new(Rainier::new().unwrap())));
+        let press = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+        let channel_enabled = |app: &App, channel| app.rainier.borrow().mmu.borrow().apu().is_channel_enabled(channel);
+
+        app.handle_key_event(press(KeyCode::Char('2')));
+        assert!(!channel_enabled(&app, 2));
+        assert!(channel_enabled(&app, 1));
+
+        app.handle_key_event(press(KeyCode::Char('2')));
+        assert!(channel_enabled(&app, 2));
+    }
+
+    #[test]
+    fn disabled_breakpoints_dont_stop() {
+        let mut app = app_with_instructions(200, 100);
+        app.breakpoints.clear();
+        app.set_key_bindings(KeyBindings::parse("up = \"KeyW\"").unwrap());
+        let press = |code| KeyEvent::new(code, crossterm::event::KeyModifiers::NONE);
+
+        app.handle_key_event(press(KeyCode::Char('b')));
+        app.handle_key_event(press(KeyCode::Up));
+        app.handle_key_event(press(KeyCode::Char('b')));
+        app.handle_key_event(press(KeyCode::Char('e')));
+
+        assert_eq!(app.breakpoints, vec![Breakpoint::new(100), Breakpoint { enabled: false, ..Breakpoint::new(99) }]);
+        assert!(app.breaks_at(&registers_at(100), 0x00));
+        assert!(!app.breaks_at(&registers_at(99), 0x00));
+    }
+
+    #[test]
+    fn breaks_on_configured_opcodes() {
+        let mut app = App::new(Rc::new(RefCell::new(Rainier::new().unwrap())));
+        app.breakpoints.clear();
+        app.opcode_breakpoints = vec![0x76, 0xCB];
+
+        assert!(app.breaks_at(&registers_at(0x150), 0x76));
+        assert!(app.breaks_at(&registers_at(0x150), 0xCB));
+        assert!(!app.breaks_at(&registers_at(0x150), 0x00));
+
+        // Resuming from the hit doesn't stop on the same instruction again
+        app.last_hit_breakpoint = Some(0x150);
+        assert!(!app.breaks_at(&registers_at(0x150), 0x76));
+        assert!(app.breaks_at(&registers_at(0x151), 0x76));
+    }
+}
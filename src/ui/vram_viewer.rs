@@ -0,0 +1,69 @@
+use ratatui::prelude::{Color, Style};
+use ratatui::text::{Line, Span};
+use crate::mmu::Mmu;
+use crate::ppu::Ppu;
+
+const TILE_COUNT: usize = 384;
+const TILES_PER_ROW: usize = 16;
+const TILE_MAP_SIZE: usize = 32;
+
+// Pixels of every tile in VRAM (0x8000-0x97FF), 16 tiles per row, as (width, shades)
+pub fn tile_pixels(mmu: &Mmu) -> (usize, Vec<u8>) {
+    let width = TILES_PER_ROW * 8;
+    let mut pixels = vec![0; TILE_COUNT * 64];
+
+    for tile in 0..TILE_COUNT {
+        let (tile_x, tile_y) = ((tile % TILES_PER_ROW) * 8, (tile / TILES_PER_ROW) * 8);
+        draw_tile(mmu, 0x8000 + tile * 16, &mut pixels, width, tile_x, tile_y);
+    }
+
+    (width, pixels)
+}
+
+// Pixels of the 32x32 tile map at the given base (0x9800 or 0x9C00), as (width, shades)
+pub fn tile_map_pixels(mmu: &Mmu, base: usize) -> (usize, Vec<u8>) {
+    let width = TILE_MAP_SIZE * 8;
+    let mut pixels = vec![0; width * width];
+    let unsigned_addressing = mmu.lcdc() & (1 << 4) != 0;
+
+    for i in 0..TILE_MAP_SIZE * TILE_MAP_SIZE {
+        let tile_index = mmu.ppu_read_byte(base + i).unwrap();
+        let (tile_x, tile_y) = ((i % TILE_MAP_SIZE) * 8, (i / TILE_MAP_SIZE) * 8);
+        draw_tile(mmu, Ppu::tile_data_address(tile_index, unsigned_addressing), &mut pixels, width, tile_x, tile_y);
+    }
+
+    (width, pixels)
+}
+
+fn draw_tile(mmu: &Mmu, address: usize, pixels: &mut [u8], width: usize, tile_x: usize, tile_y: usize) {
+    let bgp = mmu.bgp();
+
+    for row in 0..8 {
+        let low = mmu.ppu_read_byte(address + row * 2).unwrap();
+        let high = mmu.ppu_read_byte(address + row * 2 + 1).unwrap();
+
+        for (x, color_id) in Ppu::decode_tile_row(low, high).into_iter().enumerate() {
+            pixels[(tile_y + row) * width + tile_x + x] = Ppu::apply_palette(bgp, color_id);
+        }
+    }
+}
+
+// Draw two pixel rows per terminal line, the upper half block takes the top pixel and its background the bottom one
+pub fn half_block_lines(width: usize, pixels: &[u8]) -> Vec<Line<'static>> {
+    pixels.chunks(width * 2)
+        .map(|rows| {
+            let (top, bottom) = rows.split_at(width.min(rows.len()));
+            Line::from((0..width)
+                .map(|x| {
+                    let bottom = bottom.get(x).copied().unwrap_or(0);
+                    Span::styled("▀", Style::default().fg(shade_color(top[x])).bg(shade_color(bottom)))
+                })
+                .collect::<Vec<Span>>())
+        })
+        .collect()
+}
+
+fn shade_color(shade: u8) -> Color {
+    let [r, g, b, _] = Ppu::shade_rgba(shade);
+    Color::Rgb(r, g, b)
+}